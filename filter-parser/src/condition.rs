@@ -1,32 +1,127 @@
 //! BNF grammar:
 //!
 //! ```text
-//! condition      = value ("==" | ">" ...) value
-//! to             = value value TO value
+//! condition      = value ("==" | ">" | "=#" | "!=#" | "~" ...) value
+//! to             = value value TO value (a value can be "*" but not both)
+//! exists         = value EXISTS
+//! notExists      = value NOT WS+ EXISTS
+//! in             = value IN WS* "[" (value ("," value)* ","?)? "]"
+//! notIn          = value NOT WS+ IN WS* "[" (value ("," value)* ","?)? "]"
+//! inBuckets      = value IN_BUCKETS WS* "[" value ("," value)+ ","? "]"
+//! valuesCount    = value HAS WS* ("=" | "!=" | ">" | ">=" | "<" | "<=") value VALUES
+//! semver         = value SEMVER WS* ("=" | "!=" | ">" | ">=" | "<" | "<=") value
+//! length         = value (LENGTH | CHARS) WS* ("=" | "!=" | ">" | ">=" | "<" | "<=") value
+//! fieldComparison = value ("=" | "!=" | ">" | ">=" | "<" | "<=") WS* "@" value
+//! position       = value "[" digit+ "]" WS* ("=" | "!=" | ">" | ">=" | "<" | "<=") value
+//! isEmpty        = value IS WS+ EMPTY
+//! isNotEmpty     = value IS WS+ NOT WS+ EMPTY
 //! ```
 
+use std::cmp::Ordering;
+use std::ops::Bound::{self, Excluded, Included};
+use std::ops::RangeBounds;
+
 use nom::branch::alt;
-use nom::bytes::complete::tag;
-use nom::combinator::cut;
-use nom::sequence::tuple;
+use nom::bytes::complete::{tag, tag_no_case};
+use nom::character::complete::{char, digit1, multispace1, satisfy};
+use nom::combinator::{cut, not, opt};
+use nom::multi::separated_list0;
+use nom::sequence::{delimited, terminated, tuple};
+use nom::InputTake;
 use Condition::*;
 
-use crate::{parse_value, FilterCondition, IResult, Span, Token};
+use crate::error::cut_with_err;
+use crate::value::is_value_component;
+use crate::{blank, parse_value, ws, Error, ErrorKind, FilterCondition, IResult, Span, Token};
+
+/// The lower and upper bounds of a numeric range.
+pub type NumericBounds = (Bound<f64>, Bound<f64>);
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// The values of the conditions are tokens, the numbers are only parsed when the filter is
+/// evaluated. The conditions are compared, ordered and hashed on their values as written in
+/// the filter: `NaN` is a value like any other and `0.0` and `-0.0` are different values.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Condition<'a> {
     GreaterThan(Token<'a>),
     GreaterThanOrEqual(Token<'a>),
+    /// Matches the strings equal to the value, both lowercased, and, when the value
+    /// can be parsed as a number, the numbers equal to it. The strings are compared
+    /// as written: `05` matches `"05"` and `5`, but not `"5"`.
     Equal(Token<'a>),
+    /// Like `Equal` but only matches the numbers, never the strings.
+    NumericEqual(Token<'a>),
     NotEqual(Token<'a>),
+    /// Like `NotEqual` but only matches the numbers, the documents with only
+    /// string values never match.
+    NumericNotEqual(Token<'a>),
     LowerThan(Token<'a>),
     LowerThanOrEqual(Token<'a>),
-    Between { from: Token<'a>, to: Token<'a> },
+    /// A numeric range, the bounds are included. Unlike `Equal`, a range with
+    /// two equal bounds never matches the string values. One of the bounds can
+    /// be `*` to leave this side of the range open.
+    Between {
+        from: Token<'a>,
+        to: Token<'a>,
+    },
+    /// The negation of `Between`, the numbers outside of the range.
+    NotBetween {
+        from: Token<'a>,
+        to: Token<'a>,
+    },
+    Exists,
+    NotExists,
+    /// Matches the documents that are equal to any of the values.
+    In(Vec<Token<'a>>),
+    /// Matches the documents that are equal to none of the values.
+    NotIn(Vec<Token<'a>>),
+    /// Matches the numbers falling in one of the buckets delimited by the sorted edges, a
+    /// bucket goes from its edge, included, to the next one, excluded, except for the last
+    /// bucket that includes both of its edges. There are always at least two edges.
+    InBuckets(Vec<Token<'a>>),
+    /// Matches the documents with a string value matching the regular expression.
+    Regex(Token<'a>),
+    /// Matches the documents with string values but none matching the regular expression.
+    NotRegex(Token<'a>),
+    /// Matches the documents where the attribute is present but only contains strings
+    /// that are empty once normalized, i.e. `""` or whitespaces.
+    IsEmpty,
+    /// The negation of `IsEmpty`, it also matches the documents without the attribute.
+    IsNotEmpty,
+    /// Compares the number of distinct values of the documents, the boxed condition
+    /// is the comparison (`=`, `!=`, `>`, `>=`, `<` or `<=`) against the count.
+    ValuesCount(Box<Condition<'a>>),
+    /// Compares the string values as semantic versions, the boxed condition is the
+    /// comparison (`=`, `!=`, `>`, `>=`, `<` or `<=`) against the version.
+    SemVer(Box<Condition<'a>>),
+    /// Compares the number of words, separated by whitespaces, of the string values, the
+    /// boxed condition is the comparison (`=`, `!=`, `>`, `>=`, `<` or `<=`) against the
+    /// count. A document matches if any of its values matches.
+    WordsCount(Box<Condition<'a>>),
+    /// Like `WordsCount` but compares the number of characters of the string values.
+    CharsCount(Box<Condition<'a>>),
+    /// Compares the numbers of the attribute with the numbers of another attribute of the
+    /// same document, the boxed condition is the comparison (`=`, `!=`, `>`, `>=`, `<`
+    /// or `<=`) against the name of the other attribute.
+    FieldComparison(Box<Condition<'a>>),
+    /// Compares the value found at a position of the array of the documents, the first
+    /// value being at `0`, a value that isn't in an array is at `0`. The boxed condition
+    /// is the comparison (`=`, `!=`, `>`, `>=`, `<` or `<=`) against the value.
+    Position {
+        index: Token<'a>,
+        comparison: Box<Condition<'a>>,
+    },
+    /// Compares the distance, in meters, between the `_geo` point of the documents and
+    /// the latitude and longitude of the point, the attribute is `_geoDistance`. The boxed
+    /// condition is the comparison (`>`, `>=`, `<` or `<=`) against the distance.
+    GeoDistance {
+        point: [Token<'a>; 2],
+        comparison: Box<Condition<'a>>,
+    },
 }
 
 impl<'a> Condition<'a> {
     /// This method can return two operations in case it must express
-    /// an OR operation for the between case (i.e. `TO`).
+    /// an OR operation, none of the current operations need it.
     pub fn negate(self) -> (Self, Option<Self>) {
         match self {
             GreaterThan(n) => (LowerThanOrEqual(n), None),
@@ -35,33 +130,632 @@ impl<'a> Condition<'a> {
             NotEqual(s) => (Equal(s), None),
             LowerThan(n) => (GreaterThanOrEqual(n), None),
             LowerThanOrEqual(n) => (GreaterThan(n), None),
-            Between { from, to } => (LowerThan(from), Some(GreaterThan(to))),
+            NumericEqual(n) => (NumericNotEqual(n), None),
+            NumericNotEqual(n) => (NumericEqual(n), None),
+            Between { from, to } => (NotBetween { from, to }, None),
+            NotBetween { from, to } => (Between { from, to }, None),
+            Exists => (NotExists, None),
+            NotExists => (Exists, None),
+            In(values) => (NotIn(values), None),
+            NotIn(values) => (In(values), None),
+            // the buckets are contiguous, outside of them is outside of the whole range
+            InBuckets(edges) => {
+                let to = edges[edges.len() - 1].clone();
+                (NotBetween { from: edges[0].clone(), to }, None)
+            }
+            IsEmpty => (IsNotEmpty, None),
+            IsNotEmpty => (IsEmpty, None),
+            Regex(pattern) => (NotRegex(pattern), None),
+            NotRegex(pattern) => (Regex(pattern), None),
+            ValuesCount(comparison) => match comparison.negate() {
+                (comparison, None) => (ValuesCount(Box::new(comparison)), None),
+                (_, Some(_)) => unreachable!("the values count only supports simple comparisons"),
+            },
+            SemVer(comparison) => match comparison.negate() {
+                (comparison, None) => (SemVer(Box::new(comparison)), None),
+                (_, Some(_)) => unreachable!("the semver only supports simple comparisons"),
+            },
+            WordsCount(comparison) => match comparison.negate() {
+                (comparison, None) => (WordsCount(Box::new(comparison)), None),
+                (_, Some(_)) => unreachable!("the lengths only support simple comparisons"),
+            },
+            CharsCount(comparison) => match comparison.negate() {
+                (comparison, None) => (CharsCount(Box::new(comparison)), None),
+                (_, Some(_)) => unreachable!("the lengths only support simple comparisons"),
+            },
+            FieldComparison(comparison) => match comparison.negate() {
+                (comparison, None) => (FieldComparison(Box::new(comparison)), None),
+                (_, Some(_)) => {
+                    unreachable!("the field comparisons only support simple comparisons")
+                }
+            },
+            Position { index, comparison } => match comparison.negate() {
+                (comparison, None) => (Position { index, comparison: Box::new(comparison) }, None),
+                (_, Some(_)) => unreachable!("the positions only support simple comparisons"),
+            },
+            GeoDistance { point, comparison } => match comparison.negate() {
+                (comparison, None) => {
+                    (GeoDistance { point, comparison: Box::new(comparison) }, None)
+                }
+                (_, Some(_)) => unreachable!("the geo distances only support simple comparisons"),
+            },
         }
     }
+
+    /// Returns the range of numbers matched by this operation, `None` if the
+    /// operation is not a single numeric range (i.e. `=`, `!=`, `EXISTS`, `NOT TO`...).
+    /// The dates are converted into unix timestamps, see [`Token::parse_number`].
+    pub fn numeric_bounds(&self) -> Result<Option<NumericBounds>, Error> {
+        let bounds = match self {
+            GreaterThan(val) => (Excluded(val.parse_number()?), Included(f64::MAX)),
+            GreaterThanOrEqual(val) => (Included(val.parse_number()?), Included(f64::MAX)),
+            LowerThan(val) => (Included(f64::MIN), Excluded(val.parse_number()?)),
+            LowerThanOrEqual(val) => (Included(f64::MIN), Included(val.parse_number()?)),
+            NumericEqual(val) => {
+                let val = val.parse_number()?;
+                (Included(val), Included(val))
+            }
+            Between { from, to } => {
+                let from = if from.is_wildcard() { f64::MIN } else { from.parse_number()? };
+                let to = if to.is_wildcard() { f64::MAX } else { to.parse_number()? };
+                (Included(from), Included(to))
+            }
+            InBuckets(edges) => {
+                let from = edges[0].parse_number()?;
+                let to = edges[edges.len() - 1].parse_number()?;
+                (Included(from), Included(to))
+            }
+            Equal(_)
+            | NotEqual(_)
+            | NumericNotEqual(_)
+            | NotBetween { .. }
+            | Exists
+            | NotExists
+            | In(_)
+            | NotIn(_)
+            | Regex(_)
+            | NotRegex(_)
+            | IsEmpty
+            | IsNotEmpty
+            | ValuesCount(_)
+            | SemVer(_)
+            | WordsCount(_)
+            | CharsCount(_)
+            | FieldComparison(_)
+            | Position { .. }
+            | GeoDistance { .. } => return Ok(None),
+        };
+        Ok(Some(bounds))
+    }
+
+    /// Evaluates the operation against a value of a document without reading the index,
+    /// i.e. to filter the documents that are not indexed yet. The string is normalized
+    /// (trimmed and lowercased) like the facet values are when they are indexed.
+    ///
+    /// Returns `None` when the operation can't be evaluated on a single value: the geo
+    /// distances need the rtree, the regexes, semantic versions, values counts, field
+    /// comparisons and positions need the whole document, and a malformed number
+    /// can't be compared.
+    pub fn matches_value(&self, number: Option<f64>, string: Option<&str>) -> Option<bool> {
+        // the empty strings are not indexed as facet values
+        let normalized = string.map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty());
+        // like in the index, `05` matches `"05"` and `5` but not `"5"`
+        let equal = |val: &Token| {
+            matches!(&normalized, Some(s) if *s == val.to_lowercase())
+                || matches!((number, val.parse::<f64>()), (Some(n), Ok(v)) if n == v)
+        };
+        // the numbers are only considered by `!=` and `NOT IN` if a value is a number
+        let exists =
+            |val: &Token| normalized.is_some() || (number.is_some() && val.parse::<f64>().is_ok());
+
+        let matches = match self {
+            Equal(val) => equal(val),
+            NotEqual(val) => exists(val) && !equal(val),
+            NumericNotEqual(val) => {
+                number.is_some() && !NumericEqual(val.clone()).matches_value(number, None)?
+            }
+            In(values) => values.iter().any(equal),
+            NotIn(values) => values.iter().any(exists) && !values.iter().any(equal),
+            Exists => number.is_some() || normalized.is_some(),
+            NotExists => number.is_none() && normalized.is_none(),
+            IsEmpty => number.is_none() && string.is_some() && normalized.is_none(),
+            IsNotEmpty => !IsEmpty.matches_value(number, string)?,
+            NotBetween { from, to } => {
+                let between = Between { from: from.clone(), to: to.clone() };
+                number.is_some() && !between.matches_value(number, None)?
+            }
+            GreaterThan(_)
+            | GreaterThanOrEqual(_)
+            | LowerThan(_)
+            | LowerThanOrEqual(_)
+            | NumericEqual(_)
+            | Between { .. }
+            | InBuckets(_) => match (number, self.numeric_bounds().ok()??) {
+                (Some(n), bounds) => bounds.contains(&n),
+                (None, _) => false,
+            },
+            WordsCount(comparison) | CharsCount(comparison) => {
+                let (count, accept): (_, fn(Ordering) -> bool) = match comparison.as_ref() {
+                    GreaterThan(n) => (n, |o| o == Ordering::Greater),
+                    GreaterThanOrEqual(n) => (n, |o| o != Ordering::Less),
+                    Equal(n) => (n, |o| o == Ordering::Equal),
+                    NotEqual(n) => (n, |o| o != Ordering::Equal),
+                    LowerThan(n) => (n, |o| o == Ordering::Less),
+                    LowerThanOrEqual(n) => (n, |o| o != Ordering::Greater),
+                    _ => unreachable!("the lengths only support simple comparisons"),
+                };
+                let count = count.parse::<usize>().ok()?;
+                match string.map(str::trim).filter(|s| !s.is_empty()) {
+                    Some(s) if matches!(self, WordsCount(_)) => {
+                        accept(s.split_whitespace().count().cmp(&count))
+                    }
+                    Some(s) => accept(s.chars().count().cmp(&count)),
+                    None => false,
+                }
+            }
+            Regex(_)
+            | NotRegex(_)
+            | ValuesCount(_)
+            | SemVer(_)
+            | FieldComparison(_)
+            | Position { .. }
+            | GeoDistance { .. } => return None,
+        };
+        Some(matches)
+    }
 }
 
-/// condition      = value ("==" | ">" ...) value
+/// condition      = value ("==" | ">" | "=#" | "!=#" | "~" ...) value
 pub fn parse_condition(input: Span) -> IResult<FilterCondition> {
-    let operator = alt((tag("<="), tag(">="), tag("!="), tag("<"), tag(">"), tag("=")));
+    let operator = alt((
+        tag("<="),
+        tag(">="),
+        tag("!=#"),
+        tag("!="),
+        tag("<"),
+        tag(">"),
+        tag("=#"),
+        tag("="),
+        tag("~"),
+    ));
     let (input, (fid, op, value)) = tuple((parse_value, operator, cut(parse_value)))(input)?;
 
     let condition = match *op.fragment() {
         "<=" => FilterCondition::Condition { fid, op: LowerThanOrEqual(value) },
         ">=" => FilterCondition::Condition { fid, op: GreaterThanOrEqual(value) },
+        "!=#" => FilterCondition::Condition { fid, op: NumericNotEqual(value) },
         "!=" => FilterCondition::Condition { fid, op: NotEqual(value) },
         "<" => FilterCondition::Condition { fid, op: LowerThan(value) },
         ">" => FilterCondition::Condition { fid, op: GreaterThan(value) },
+        "=#" => FilterCondition::Condition { fid, op: NumericEqual(value) },
         "=" => FilterCondition::Condition { fid, op: Equal(value) },
+        "~" => FilterCondition::Condition { fid, op: Regex(value) },
         _ => unreachable!(),
     };
 
     Ok((input, condition))
 }
 
-/// to             = value value TO value
+/// to             = value value TO value (a value can be "*" but not both)
+/// The `TO` keyword is case-insensitive and can't be directly followed by a value,
+/// `price 1 TO5` is not a range.
 pub fn parse_to(input: Span) -> IResult<FilterCondition> {
-    let (input, (key, from, _, to)) =
-        tuple((parse_value, parse_value, tag("TO"), cut(parse_value)))(input)?;
+    let keyword = terminated(tag_no_case("TO"), not(satisfy(is_value_component)));
+    let (rest, (key, from, _, to)) =
+        tuple((parse_value, parse_value, keyword, cut(parse_value)))(input)?;
+
+    if from.is_wildcard() && to.is_wildcard() {
+        let range = input.take(input.len() - rest.len());
+        return Err(nom::Err::Failure(Error::new_from_kind(range, ErrorKind::UnboundedRange)));
+    }
+
+    Ok((rest, FilterCondition::Condition { fid: key, op: Between { from, to } }))
+}
+
+/// between        = value BETWEEN value AND value (a value can be "*" but not both)
+/// The `AND` separates the bounds of the range, it isn't a boolean `AND`:
+/// `price BETWEEN 10 AND 20 AND color = red` is the range of `price` and the color.
+pub fn parse_between(input: Span) -> IResult<FilterCondition> {
+    let keyword = |keyword| terminated(tag(keyword), not(satisfy(is_value_component)));
+    let (rest, (key, _, from, _, to)) = tuple((
+        parse_value,
+        keyword("BETWEEN"),
+        cut(parse_value),
+        keyword("AND"),
+        cut(parse_value),
+    ))(input)?;
+
+    if from.is_wildcard() && to.is_wildcard() {
+        let range = input.take(input.len() - rest.len());
+        return Err(nom::Err::Failure(Error::new_from_kind(range, ErrorKind::UnboundedRange)));
+    }
+
+    Ok((rest, FilterCondition::Condition { fid: key, op: Between { from, to } }))
+}
+
+/// exists         = value EXISTS
+pub fn parse_exists(input: Span) -> IResult<FilterCondition> {
+    let (input, (key, _)) = tuple((parse_value, tag("EXISTS")))(input)?;
+
+    Ok((input, FilterCondition::Condition { fid: key, op: Exists }))
+}
+
+/// notExists      = value NOT WS+ EXISTS
+pub fn parse_not_exists(input: Span) -> IResult<FilterCondition> {
+    let (input, (key, _, _, _)) =
+        tuple((parse_value, tag("NOT"), multispace1, tag("EXISTS")))(input)?;
+
+    Ok((input, FilterCondition::Condition { fid: key, op: NotExists }))
+}
+
+/// valuesCount    = value HAS WS* ("=" | "!=" | ">" | ">=" | "<" | "<=") value VALUES
+pub fn parse_values_count(input: Span) -> IResult<FilterCondition> {
+    let operator = alt((tag("<="), tag(">="), tag("!="), tag("<"), tag(">"), tag("=")));
+    let (input, (fid, _, _, op, count, _)) =
+        tuple((parse_value, tag("HAS"), blank, operator, parse_value, tag("VALUES")))(input)?;
+
+    let comparison = match *op.fragment() {
+        "<=" => LowerThanOrEqual(count),
+        ">=" => GreaterThanOrEqual(count),
+        "!=" => NotEqual(count),
+        "<" => LowerThan(count),
+        ">" => GreaterThan(count),
+        "=" => Equal(count),
+        _ => unreachable!(),
+    };
+
+    Ok((input, FilterCondition::Condition { fid, op: ValuesCount(Box::new(comparison)) }))
+}
+
+/// semver         = value SEMVER WS* ("=" | "!=" | ">" | ">=" | "<" | "<=") value
+pub fn parse_semver(input: Span) -> IResult<FilterCondition> {
+    let operator = alt((tag("<="), tag(">="), tag("!="), tag("<"), tag(">"), tag("=")));
+    let (input, (fid, _, _, op, version)) =
+        tuple((parse_value, tag("SEMVER"), blank, operator, cut(parse_value)))(input)?;
+
+    let comparison = match *op.fragment() {
+        "<=" => LowerThanOrEqual(version),
+        ">=" => GreaterThanOrEqual(version),
+        "!=" => NotEqual(version),
+        "<" => LowerThan(version),
+        ">" => GreaterThan(version),
+        "=" => Equal(version),
+        _ => unreachable!(),
+    };
+
+    Ok((input, FilterCondition::Condition { fid, op: SemVer(Box::new(comparison)) }))
+}
+
+/// length         = value (LENGTH | CHARS) WS* ("=" | "!=" | ">" | ">=" | "<" | "<=") value
+pub fn parse_length(input: Span) -> IResult<FilterCondition> {
+    let operator = alt((tag("<="), tag(">="), tag("!="), tag("<"), tag(">"), tag("=")));
+    let (input, (fid, unit, _, op, count)) = tuple((
+        parse_value,
+        alt((tag("LENGTH"), tag("CHARS"))),
+        blank,
+        operator,
+        cut(parse_value),
+    ))(input)?;
+
+    let comparison = match *op.fragment() {
+        "<=" => LowerThanOrEqual(count),
+        ">=" => GreaterThanOrEqual(count),
+        "!=" => NotEqual(count),
+        "<" => LowerThan(count),
+        ">" => GreaterThan(count),
+        "=" => Equal(count),
+        _ => unreachable!(),
+    };
+    let op = match *unit.fragment() {
+        "LENGTH" => WordsCount(Box::new(comparison)),
+        "CHARS" => CharsCount(Box::new(comparison)),
+        _ => unreachable!(),
+    };
+
+    Ok((input, FilterCondition::Condition { fid, op }))
+}
+
+/// fieldComparison = value ("=" | "!=" | ">" | ">=" | "<" | "<=") WS* "@" value
+pub fn parse_field_comparison(input: Span) -> IResult<FilterCondition> {
+    let operator = alt((tag("<="), tag(">="), tag("!="), tag("<"), tag(">"), tag("=")));
+    let (input, (fid, op, _, _, other)) =
+        tuple((parse_value, operator, blank, char('@'), cut(parse_value)))(input)?;
 
-    Ok((input, FilterCondition::Condition { fid: key, op: Between { from, to } }))
+    let comparison = match *op.fragment() {
+        "<=" => LowerThanOrEqual(other),
+        ">=" => GreaterThanOrEqual(other),
+        "!=" => NotEqual(other),
+        "<" => LowerThan(other),
+        ">" => GreaterThan(other),
+        "=" => Equal(other),
+        _ => unreachable!(),
+    };
+
+    Ok((input, FilterCondition::Condition { fid, op: FieldComparison(Box::new(comparison)) }))
+}
+
+/// position       = value "[" digit+ "]" WS* ("=" | "!=" | ">" | ">=" | "<" | "<=") value
+pub fn parse_position(input: Span) -> IResult<FilterCondition> {
+    let operator = alt((tag("<="), tag(">="), tag("!="), tag("<"), tag(">"), tag("=")));
+    let (input, (fid, index, _, op, value)) = tuple((
+        parse_value,
+        delimited(char('['), digit1, char(']')),
+        blank,
+        operator,
+        cut(parse_value),
+    ))(input)?;
+
+    let comparison = match *op.fragment() {
+        "<=" => LowerThanOrEqual(value),
+        ">=" => GreaterThanOrEqual(value),
+        "!=" => NotEqual(value),
+        "<" => LowerThan(value),
+        ">" => GreaterThan(value),
+        "=" => Equal(value),
+        _ => unreachable!(),
+    };
+
+    let op = Position { index: index.into(), comparison: Box::new(comparison) };
+    Ok((input, FilterCondition::Condition { fid, op }))
+}
+
+/// isEmpty        = value IS WS+ EMPTY
+pub fn parse_is_empty(input: Span) -> IResult<FilterCondition> {
+    let (input, (key, _, _, _)) =
+        tuple((parse_value, tag("IS"), multispace1, tag("EMPTY")))(input)?;
+
+    Ok((input, FilterCondition::Condition { fid: key, op: IsEmpty }))
+}
+
+/// isNotEmpty     = value IS WS+ NOT WS+ EMPTY
+pub fn parse_is_not_empty(input: Span) -> IResult<FilterCondition> {
+    let (input, (key, _, _, _, _, _)) =
+        tuple((parse_value, tag("IS"), multispace1, tag("NOT"), multispace1, tag("EMPTY")))(input)?;
+
+    Ok((input, FilterCondition::Condition { fid: key, op: IsNotEmpty }))
+}
+
+/// The maximum number of values an `IN` list can contain.
+pub const MAX_LIST_VALUES: usize = 10_000;
+
+/// The list of values following an `IN`, the opening bracket must already be consumed.
+/// If the list is not closed or contains more than [`MAX_LIST_VALUES`] values we return a failure.
+fn parse_value_list<'a>(input: Span<'a>, start: Span<'a>) -> IResult<'a, Vec<Token<'a>>> {
+    let (rest, values) = terminated(
+        terminated(separated_list0(char(','), parse_value), opt(char(','))),
+        cut_with_err(ws(char(']')), |_| {
+            Error::new_from_kind(start, ErrorKind::MissingClosingDelimiter(']'))
+        }),
+    )(input)?;
+
+    if values.len() > MAX_LIST_VALUES {
+        let list = input.take(input.len() - rest.len());
+        return Err(nom::Err::Failure(Error::new_from_kind(list, ErrorKind::TooManyValues)));
+    }
+
+    Ok((rest, values))
+}
+
+/// in             = value IN WS* "[" (value ("," value)* ","?)? "]"
+pub fn parse_in(input: Span) -> IResult<FilterCondition> {
+    let (rest, (key, _, _, _)) = tuple((parse_value, tag("IN"), blank, char('[')))(input)?;
+    let (rest, values) = parse_value_list(rest, input)?;
+
+    Ok((rest, FilterCondition::Condition { fid: key, op: In(values) }))
+}
+
+/// notIn          = value NOT WS+ IN WS* "[" (value ("," value)* ","?)? "]"
+pub fn parse_not_in(input: Span) -> IResult<FilterCondition> {
+    let (rest, (key, _, _, _, _, _)) =
+        tuple((parse_value, tag("NOT"), multispace1, tag("IN"), blank, char('[')))(input)?;
+    let (rest, values) = parse_value_list(rest, input)?;
+
+    Ok((rest, FilterCondition::Condition { fid: key, op: NotIn(values) }))
+}
+
+/// inBuckets      = value IN_BUCKETS WS* "[" value ("," value)+ ","? "]"
+pub fn parse_in_buckets(input: Span) -> IResult<FilterCondition> {
+    let (rest, (key, _, _, _)) = tuple((parse_value, tag("IN_BUCKETS"), blank, char('[')))(input)?;
+    let (rest, edges) = parse_value_list(rest, input)?;
+    if edges.len() < 2 {
+        let list = input.take(input.len() - rest.len());
+        return Err(nom::Err::Failure(Error::new_from_kind(list, ErrorKind::Buckets)));
+    }
+
+    Ok((rest, FilterCondition::Condition { fid: key, op: InBuckets(edges) }))
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::tests::rtok;
+
+    #[test]
+    fn numeric_bounds() {
+        let test_case = [
+            (GreaterThan(rtok("", "12")), Some((Excluded(12.), Included(f64::MAX)))),
+            (GreaterThanOrEqual(rtok("", "12")), Some((Included(12.), Included(f64::MAX)))),
+            (LowerThan(rtok("", "-3.5")), Some((Included(f64::MIN), Excluded(-3.5)))),
+            (LowerThanOrEqual(rtok("", "-3.5")), Some((Included(f64::MIN), Included(-3.5)))),
+            (
+                Between { from: rtok("", "1"), to: rtok("", "10") },
+                Some((Included(1.), Included(10.))),
+            ),
+            (
+                GreaterThan(rtok("", "2024-01-01T00:00:00Z")),
+                Some((Excluded(1704067200.), Included(f64::MAX))),
+            ),
+            (
+                Between { from: rtok("", "2024-01-01"), to: rtok("", "1704153600") },
+                Some((Included(1704067200.), Included(1704153600.))),
+            ),
+            (GreaterThan(rtok("", "$10")), Some((Excluded(10.), Included(f64::MAX)))),
+            (GreaterThan(rtok("", "15%")), Some((Excluded(0.15), Included(f64::MAX)))),
+            (LowerThan(rtok("", "10€")), Some((Included(f64::MIN), Excluded(10.)))),
+            (
+                Between { from: rtok("", "£2.5"), to: rtok("", "¥300") },
+                Some((Included(2.5), Included(300.))),
+            ),
+            (
+                Between { from: rtok("", "10"), to: rtok("", "*") },
+                Some((Included(10.), Included(f64::MAX))),
+            ),
+            (
+                Between { from: rtok("", "*"), to: rtok("", "20") },
+                Some((Included(f64::MIN), Included(20.))),
+            ),
+            (NumericEqual(rtok("", "12")), Some((Included(12.), Included(12.)))),
+            (NumericNotEqual(rtok("", "12")), None),
+            (
+                InBuckets(vec![rtok("", "0"), rtok("", "10"), rtok("", "100")]),
+                Some((Included(0.), Included(100.))),
+            ),
+            (NotBetween { from: rtok("", "1"), to: rtok("", "10") }, None),
+            (Equal(rtok("", "12")), None),
+            (NotEqual(rtok("", "12")), None),
+            (Exists, None),
+            (NotExists, None),
+            (IsEmpty, None),
+            (Regex(rtok("", "^po.*ce$")), None),
+            (ValuesCount(Box::new(GreaterThan(rtok("", "2")))), None),
+            (SemVer(Box::new(GreaterThan(rtok("", "1.9.0")))), None),
+            (WordsCount(Box::new(GreaterThan(rtok("", "5")))), None),
+            (FieldComparison(Box::new(LowerThan(rtok("", "end")))), None),
+            (
+                Position { index: rtok("", "0"), comparison: Box::new(LowerThan(rtok("", "3"))) },
+                None,
+            ),
+            (
+                GeoDistance {
+                    point: [rtok("", "1"), rtok("", "2")],
+                    comparison: Box::new(LowerThan(rtok("", "3"))),
+                },
+                None,
+            ),
+        ];
+
+        for (condition, expected) in test_case {
+            let bounds = condition.numeric_bounds().unwrap();
+            assert_eq!(bounds, expected, "Filter `{:?}` failed.", condition);
+        }
+
+        // the values of a numeric operation must be numbers
+        let condition = GreaterThan(rtok("", "twelve"));
+        let error = condition.numeric_bounds().unwrap_err();
+        assert!(matches!(error.kind(), crate::ErrorKind::External(_)));
+
+        // only a few currency symbols are recognized
+        let condition = GreaterThan(rtok("", "10CHF"));
+        let error = condition.numeric_bounds().unwrap_err();
+        assert!(matches!(error.kind(), crate::ErrorKind::External(_)));
+
+        let condition = GreaterThan(rtok("", "$10%"));
+        assert!(condition.numeric_bounds().is_err());
+
+        let condition = GreaterThan(rtok("", "2024-13-01"));
+        let error = condition.numeric_bounds().unwrap_err();
+        assert!(error.to_string().starts_with(
+            "`2024-13-01` is not a valid ISO-8601 date. Expected a date like `2024-01-01` or `2024-01-01T00:00:00Z`."
+        ));
+    }
+
+    #[test]
+    fn matches_value() {
+        let ten = || rtok("", "10");
+        let ponce = || rtok("", "Ponce");
+        let between = |from, to| Between { from: rtok("", from), to: rtok("", to) };
+        let not_between = |from, to| NotBetween { from: rtok("", from), to: rtok("", to) };
+        let (number, string) = (Some(10.), Some("  PONCE "));
+
+        let test_case = [
+            (GreaterThan(rtok("", "9")), number, None, Some(true)),
+            (GreaterThan(ten()), number, None, Some(false)),
+            (GreaterThan(ten()), None, Some("11"), Some(false)),
+            (GreaterThanOrEqual(ten()), number, None, Some(true)),
+            (GreaterThanOrEqual(rtok("", "11")), number, None, Some(false)),
+            (LowerThan(rtok("", "11")), number, None, Some(true)),
+            (LowerThan(ten()), number, None, Some(false)),
+            (LowerThanOrEqual(ten()), number, None, Some(true)),
+            (LowerThanOrEqual(rtok("", "9")), number, None, Some(false)),
+            (LowerThan(rtok("", "twelve")), number, None, None),
+            // the strings are lowercased, the numbers are compared once parsed
+            (Equal(ponce()), None, string, Some(true)),
+            (Equal(ponce()), None, Some("ponces"), Some(false)),
+            (Equal(ten()), number, None, Some(true)),
+            (Equal(rtok("", "10.0")), number, None, Some(true)),
+            (Equal(ten()), None, Some("10"), Some(true)),
+            (Equal(rtok("", "10.0")), None, Some("10"), Some(false)),
+            (Equal(ten()), None, None, Some(false)),
+            (NumericEqual(ten()), number, None, Some(true)),
+            (NumericEqual(ten()), None, Some("10"), Some(false)),
+            (NotEqual(ponce()), None, string, Some(false)),
+            (NotEqual(ponce()), None, Some("jean"), Some(true)),
+            (NotEqual(ten()), Some(12.), None, Some(true)),
+            (NotEqual(ponce()), Some(12.), None, Some(false)),
+            (NotEqual(ponce()), None, None, Some(false)),
+            (NumericNotEqual(ten()), Some(12.), None, Some(true)),
+            (NumericNotEqual(ten()), number, None, Some(false)),
+            (NumericNotEqual(ten()), None, Some("12"), Some(false)),
+            (In(vec![rtok("", "jean"), ponce()]), None, string, Some(true)),
+            (In(vec![rtok("", "jean"), ten()]), number, None, Some(true)),
+            (In(vec![rtok("", "jean"), ponce()]), number, None, Some(false)),
+            (NotIn(vec![rtok("", "jean"), ponce()]), None, string, Some(false)),
+            (NotIn(vec![rtok("", "jean"), rtok("", "paul")]), None, string, Some(true)),
+            (NotIn(vec![rtok("", "jean"), ponce()]), number, None, Some(false)),
+            (NotIn(vec![rtok("", "12"), ponce()]), number, None, Some(true)),
+            (Exists, number, None, Some(true)),
+            (Exists, None, string, Some(true)),
+            (Exists, None, Some("  "), Some(false)),
+            (Exists, None, None, Some(false)),
+            (NotExists, None, None, Some(true)),
+            (NotExists, number, None, Some(false)),
+            (IsEmpty, None, Some("  "), Some(true)),
+            (IsEmpty, None, string, Some(false)),
+            (IsEmpty, None, None, Some(false)),
+            (IsNotEmpty, None, None, Some(true)),
+            (IsNotEmpty, None, Some(""), Some(false)),
+            (between("1", "10"), number, None, Some(true)),
+            (between("10", "*"), number, None, Some(true)),
+            (between("*", "9"), number, None, Some(false)),
+            (between("1", "10"), None, Some("5"), Some(false)),
+            (between("1", "ten"), number, None, None),
+            (not_between("1", "10"), number, None, Some(false)),
+            (not_between("1", "9"), number, None, Some(true)),
+            (not_between("1", "9"), None, string, Some(false)),
+            (InBuckets(vec![rtok("", "0"), rtok("", "5"), ten()]), number, None, Some(true)),
+            (InBuckets(vec![rtok("", "0"), rtok("", "5")]), number, None, Some(false)),
+            (Regex(rtok("", "^po")), None, string, None),
+            (NotRegex(rtok("", "^po")), None, string, None),
+            (ValuesCount(Box::new(Equal(rtok("", "1")))), number, None, None),
+            (SemVer(Box::new(Equal(rtok("", "1.0.0")))), None, Some("1.0.0"), None),
+            (WordsCount(Box::new(GreaterThan(rtok("", "1")))), None, string, Some(false)),
+            (WordsCount(Box::new(Equal(rtok("", "2")))), None, Some(" jean  paul "), Some(true)),
+            (WordsCount(Box::new(LowerThan(rtok("", "1")))), None, Some(" "), Some(false)),
+            (WordsCount(Box::new(LowerThan(rtok("", "5")))), number, None, Some(false)),
+            (CharsCount(Box::new(Equal(rtok("", "5")))), None, string, Some(true)),
+            (CharsCount(Box::new(GreaterThanOrEqual(rtok("", "6")))), None, string, Some(false)),
+            (CharsCount(Box::new(Equal(rtok("", "five")))), None, string, None),
+            (FieldComparison(Box::new(Equal(rtok("", "end")))), number, None, None),
+            (
+                Position { index: rtok("", "0"), comparison: Box::new(Equal(ten())) },
+                number,
+                None,
+                None,
+            ),
+            (
+                GeoDistance {
+                    point: [rtok("", "1"), rtok("", "2")],
+                    comparison: Box::new(LowerThan(ten())),
+                },
+                number,
+                None,
+                None,
+            ),
+        ];
+
+        for (condition, number, string, expected) in test_case {
+            let matches = condition.matches_value(number, string);
+            assert_eq!(matches, expected, "`{:?}` failed on {:?} {:?}.", condition, number, string);
+        }
+    }
 }
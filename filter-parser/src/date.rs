@@ -0,0 +1,229 @@
+//! Conversion of the ISO-8601 dates into unix timestamps.
+//!
+//! ```text
+//! date           = year "-" month "-" day (("T" | " ") time)?
+//! time           = hour ":" minute (":" second ("." fraction)?)? offset?
+//! offset         = "Z" | ("+" | "-") hour ":" minute
+//! ```
+//!
+//! A date without a time is the midnight of this day and a time without an
+//! offset is considered to be in UTC.
+
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub struct DateError(String);
+
+impl std::error::Error for DateError {}
+
+impl Display for DateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "`{}` is not a valid ISO-8601 date. Expected a date like `2024-01-01` or `2024-01-01T00:00:00Z`.",
+            self.0
+        )
+    }
+}
+
+/// Returns `true` if the value starts like a date, i.e. `YYYY-MM-DD`.
+pub fn looks_like_date(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes.len() >= 10
+        && bytes[..4].iter().all(u8::is_ascii_digit)
+        && bytes[4] == b'-'
+        && bytes[5..7].iter().all(u8::is_ascii_digit)
+        && bytes[7] == b'-'
+        && bytes[8..10].iter().all(u8::is_ascii_digit)
+}
+
+/// Converts an ISO-8601 date into the number of seconds since the unix epoch.
+pub fn parse_timestamp(value: &str) -> Result<f64, DateError> {
+    inner_parse_timestamp(value).ok_or_else(|| DateError(value.to_string()))
+}
+
+fn inner_parse_timestamp(value: &str) -> Option<f64> {
+    let mut cursor = Cursor(value);
+
+    let year = cursor.number(4)?;
+    cursor.expect(b'-')?;
+    let month = cursor.number(2)?;
+    cursor.expect(b'-')?;
+    let day = cursor.number(2)?;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    let mut seconds = days_from_civil(year, month, day) as f64 * 86400.;
+    if cursor.is_empty() {
+        return Some(seconds);
+    }
+
+    cursor.expect(b'T').or_else(|| cursor.expect(b't')).or_else(|| cursor.expect(b' '))?;
+    let hour = cursor.number(2)?;
+    cursor.expect(b':')?;
+    let minute = cursor.number(2)?;
+    let second = match cursor.expect(b':') {
+        Some(()) => cursor.number(2)?,
+        None => 0,
+    };
+    if hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    seconds += (hour * 3600 + minute * 60 + second) as f64;
+
+    if cursor.expect(b'.').is_some() {
+        let digits = cursor.digits()?;
+        seconds += format!("0.{}", digits).parse::<f64>().ok()?;
+    }
+
+    let offset = match cursor.peek() {
+        None => 0,
+        Some(b'Z' | b'z') => {
+            cursor.advance(1);
+            0
+        }
+        Some(sign @ (b'+' | b'-')) => {
+            cursor.advance(1);
+            let hour = cursor.number(2)?;
+            cursor.expect(b':')?;
+            let minute = cursor.number(2)?;
+            if hour > 23 || minute > 59 {
+                return None;
+            }
+            let offset = (hour * 3600 + minute * 60) as i64;
+            if sign == b'+' {
+                offset
+            } else {
+                -offset
+            }
+        }
+        Some(_) => return None,
+    };
+
+    if cursor.is_empty() {
+        Some(seconds - offset as f64)
+    } else {
+        None
+    }
+}
+
+struct Cursor<'a>(&'a str);
+
+impl<'a> Cursor<'a> {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.0.as_bytes().first().copied()
+    }
+
+    fn advance(&mut self, n: usize) {
+        self.0 = &self.0[n..];
+    }
+
+    fn expect(&mut self, c: u8) -> Option<()> {
+        (self.peek()? == c).then(|| self.advance(1))
+    }
+
+    /// Consumes exactly `len` digits.
+    fn number(&mut self, len: usize) -> Option<u32> {
+        let digits = self.0.get(..len)?;
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        self.advance(len);
+        digits.parse().ok()
+    }
+
+    /// Consumes all the consecutive digits, at least one.
+    fn digits(&mut self) -> Option<&'a str> {
+        let len = self.0.bytes().take_while(u8::is_ascii_digit).count();
+        let digits = &self.0[..len];
+        self.advance(len);
+        (len > 0).then(|| digits)
+    }
+}
+
+fn is_leap_year(year: u32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The number of days between the unix epoch and the provided date, see
+/// <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+fn days_from_civil(year: u32, month: u32, day: u32) -> i64 {
+    let (year, month, day) = (year as i64, month as i64, day as i64);
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps() {
+        let test_case = [
+            ("1970-01-01", 0.),
+            ("2024-01-01", 1704067200.),
+            ("2024-01-01T00:00:00Z", 1704067200.),
+            ("2024-01-01T00:00Z", 1704067200.),
+            ("2024-01-01T00:00:00", 1704067200.),
+            ("2024-01-01 12:30:15z", 1704112215.),
+            ("2024-02-29T23:59:59.5Z", 1709251199.5),
+            ("2024-01-01T01:00:00+01:00", 1704067200.),
+            ("2023-12-31T22:00:00-02:00", 1704067200.),
+            ("1969-12-31T23:59:59Z", -1.),
+        ];
+
+        for (input, expected) in test_case {
+            assert!(looks_like_date(input), "`{}` should look like a date.", input);
+            let timestamp = parse_timestamp(input).unwrap();
+            assert_eq!(timestamp, expected, "Date `{}` failed.", input);
+        }
+    }
+
+    #[test]
+    fn invalid_dates() {
+        let test_case = [
+            "2024-13-01",
+            "2024-00-10",
+            "2023-02-29",
+            "2024-04-31",
+            "2024-01-01T",
+            "2024-01-01T24:00:00Z",
+            "2024-01-01T12:60:00Z",
+            "2024-01-01T12:00:00.Z",
+            "2024-01-01T12:00:00+1",
+            "2024-01-01T12:00:00Zulu",
+            "2024-01-01garbage",
+        ];
+
+        for input in test_case {
+            assert!(looks_like_date(input), "`{}` should look like a date.", input);
+            let error = parse_timestamp(input).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!("`{}` is not a valid ISO-8601 date. Expected a date like `2024-01-01` or `2024-01-01T00:00:00Z`.", input)
+            );
+        }
+
+        for input in ["12", "-12.5", "2024", "2024-01", "24-01-01", "channel"] {
+            assert!(!looks_like_date(input), "`{}` should not look like a date.", input);
+        }
+    }
+}
@@ -3,7 +3,7 @@ use std::fmt::Display;
 use nom::error::{self, ParseError};
 use nom::Parser;
 
-use crate::{IResult, Span};
+use crate::{IResult, Span, MAX_LIST_VALUES};
 
 pub trait NomErrorExt<E> {
     fn is_failure(&self) -> bool;
@@ -42,6 +42,9 @@ pub fn cut_with_err<'a, O>(
     }
 }
 
+/// The operations a filter is made of, listed by the errors expecting one of them.
+const OPERATIONS: &str = "`=`, `!=`, `=#`, `!=#`, `>=`, `>`, `<=`, `<`, `~`, `IN`, `NOT IN`, `IN_BUCKETS`, `TO`, `BETWEEN`, `EXISTS`, `NOT EXISTS`, `IS EMPTY`, `IS NOT EMPTY`, `HAS`, `SEMVER`, `LENGTH`, `CHARS`, `ANY`, `ALL`, `_geoRadius`, `_geoPolygon`, `_geoRing` or `_geoDistance`";
+
 #[derive(Debug)]
 pub struct Error<'a> {
     context: Span<'a>,
@@ -52,12 +55,21 @@ pub struct Error<'a> {
 pub enum ErrorKind<'a> {
     ReservedGeo(&'a str),
     Geo,
+    GeoRadiusArguments(usize),
+    GeoPolygon,
+    GeoRing,
+    GeoRingRadii,
+    GeoDistance,
     MisusedGeo,
     InvalidPrimary,
     ExpectedEof,
     ExpectedValue,
     MalformedValue,
     MissingClosingDelimiter(char),
+    TooManyValues,
+    Buckets,
+    UnboundedRange,
+    TooManyTerms(usize),
     Char(char),
     InternalError(error::ErrorKind),
     External(String),
@@ -72,6 +84,19 @@ impl<'a> Error<'a> {
         &self.context
     }
 
+    /// Returns the `(start, end)` byte offsets of the erroneous part of the filter,
+    /// they can be used to highlight it in the original expression.
+    pub fn byte_offsets(&self) -> (usize, usize) {
+        let start = self.context.location_offset();
+        (start, start + self.context.fragment().len())
+    }
+
+    /// Returns the whole filter the error was raised on, it stays available once the
+    /// filter is dropped as long as the original expression lives.
+    pub fn expression(&self) -> &'a str {
+        self.context.extra
+    }
+
     pub fn new_from_kind(context: Span<'a>, kind: ErrorKind<'a>) -> Self {
         Self { context, kind }
     }
@@ -124,14 +149,26 @@ impl<'a> Display for Error<'a> {
             ErrorKind::MissingClosingDelimiter(c) => {
                 writeln!(f, "Expression `{}` is missing the following closing delimiter: `{}`.", escaped_input, c)?
             }
+            ErrorKind::TooManyValues => {
+                writeln!(f, "The list contains too many values, at most {} values are allowed.", MAX_LIST_VALUES)?
+            }
+            ErrorKind::Buckets => {
+                writeln!(f, "The `IN_BUCKETS` operator expects at least two edges: `price IN_BUCKETS [0, 10, 100]`.")?
+            }
+            ErrorKind::UnboundedRange => {
+                writeln!(f, "The range `{}` is unbounded on both sides, use `EXISTS` to match all the values.", escaped_input)?
+            }
+            ErrorKind::TooManyTerms(limit) => {
+                writeln!(f, "The filter has more than {} terms once rewritten as an `OR` of `AND`s.", limit)?
+            }
             ErrorKind::ExpectedValue => {
                 writeln!(f, "Was expecting a value but instead got `{}`.", escaped_input)?
             }
             ErrorKind::InvalidPrimary if input.trim().is_empty() => {
-                writeln!(f, "Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `TO` or `_geoRadius` but instead got nothing.")?
+                writeln!(f, "Was expecting an operation {} but instead got nothing.", OPERATIONS)?
             }
             ErrorKind::InvalidPrimary => {
-                writeln!(f, "Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `TO` or `_geoRadius` at `{}`.", escaped_input)?
+                writeln!(f, "Was expecting an operation {} at `{}`.", OPERATIONS, escaped_input)?
             }
             ErrorKind::ExpectedEof => {
                 writeln!(f, "Found unexpected characters at the end of the filter: `{}`. You probably forgot an `OR` or an `AND` rule.", escaped_input)?
@@ -139,6 +176,21 @@ impl<'a> Display for Error<'a> {
             ErrorKind::Geo => {
                 writeln!(f, "The `_geoRadius` filter expects three arguments: `_geoRadius(latitude, longitude, radius)`.")?
             }
+            ErrorKind::GeoRadiusArguments(count) => {
+                writeln!(f, "The `_geoRadius` filter expects three arguments: `_geoRadius(latitude, longitude, radius)`, but got {}.", count)?
+            }
+            ErrorKind::GeoPolygon => {
+                writeln!(f, "The `_geoPolygon` filter expects at least three points: `_geoPolygon(latitude1, longitude1, latitude2, longitude2, latitude3, longitude3, ...)`.")?
+            }
+            ErrorKind::GeoRing => {
+                writeln!(f, "The `_geoRing` filter expects four arguments: `_geoRing(latitude, longitude, innerRadius, outerRadius)`.")?
+            }
+            ErrorKind::GeoRingRadii => {
+                writeln!(f, "The radii of the `_geoRing` filter must be positive and the inner radius must be lower than the outer radius: `{}`.", escaped_input)?
+            }
+            ErrorKind::GeoDistance => {
+                writeln!(f, "The `_geoDistance` filter expects two arguments followed by a comparison: `_geoDistance(latitude, longitude) < distance`, the comparison can be `<`, `<=`, `>` or `>=`.")?
+            }
             ErrorKind::ReservedGeo(name) => {
                 writeln!(f, "`{}` is a reserved keyword and thus can't be used as a filter expression. Use the `_geoRadius(latitude, longitude, distance) built-in rule to filter on `_geo` coordinates.", name.escape_debug())?
             }
@@ -6,14 +6,34 @@
 //! or             = and (~ "OR" ~ and)
 //! and            = not (~ "AND" not)*
 //! not            = ("NOT" ~ not) | primary
-//! primary        = (WS* ~ "("  expression ")" ~ WS*) | geoRadius | condition | to
+//! primary        = (WS* ~ "("  expression ")" ~ WS*) | anyAll | geoRadius | geoPolygon | geoRing | geoDistance | fieldComparison | position | condition | exists | notExists | inBuckets | in | notIn | valuesCount | semver | length | isEmpty | isNotEmpty | to | between
+//! anyAll         = WS* ~ ("ANY" | "ALL") ~ WS* ~ "(" ~ expression ("," expression)* ~ ")" ~ WS*
 //! condition      = value ("==" | ">" ...) value
-//! to             = value value TO value
-//! value          = WS* ~ ( word | singleQuoted | doubleQuoted) ~ WS*
+//! exists         = value EXISTS
+//! notExists      = value NOT WS+ EXISTS
+//! in             = value IN WS* "[" (value ("," value)* ","?)? "]"
+//! notIn          = value NOT WS+ IN WS* "[" (value ("," value)* ","?)? "]"
+//! inBuckets      = value IN_BUCKETS WS* "[" value ("," value)+ ","? "]"
+//! valuesCount    = value HAS WS* ("=" | "!=" | ">" | ">=" | "<" | "<=") value VALUES
+//! semver         = value SEMVER WS* ("=" | "!=" | ">" | ">=" | "<" | "<=") value
+//! length         = value (LENGTH | CHARS) WS* ("=" | "!=" | ">" | ">=" | "<" | "<=") value
+//! fieldComparison = value ("=" | "!=" | ">" | ">=" | "<" | "<=") WS* "@" value
+//! position       = value "[" digit+ "]" WS* ("=" | "!=" | ">" | ">=" | "<" | "<=") value
+//! isEmpty        = value IS WS+ EMPTY
+//! isNotEmpty     = value IS WS+ NOT WS+ EMPTY
+//! to             = value value TO value (a value can be "*" but not both, TO is case-insensitive)
+//! between        = value BETWEEN value AND value (a value can be "*" but not both)
+//! value          = WS* ~ ( word | singleQuoted | doubleQuoted | backQuoted) ~ WS*
 //! singleQuoted   = "'" .* all but quotes "'"
 //! doubleQuoted   = "\"" .* all but double quotes "\""
-//! word           = (alphanumeric | _ | - | .)+
-//! geoRadius      = WS* ~ "_geoRadius(" ~ WS* ~ float ~ WS* ~ "," ~ WS* ~ float ~ WS* ~ "," float ~ WS* ~ ")"
+//! backQuoted     = "`" .* all but backquotes "`"
+//! word           = (alphanumeric | _ | - | . | : | + | * | % | $ | € | £ | ¥)+
+//! geoRadius      = WS* ~ "_geoRadius(" ~ WS* ~ ((point ~ WS* ~ ",")+ | float ~ WS* ~ "," ~ WS* ~ float ~ WS* ~ ",") ~ WS* ~ float ~ WS* ~ ")"
+//! point          = "[" ~ WS* ~ float ~ WS* ~ "," ~ WS* ~ float ~ WS* ~ "]"
+//! geoPolygon     = WS* ~ "_geoPolygon(" ~ WS* ~ float ~ WS* ~ ("," ~ WS* ~ float ~ WS*)+ ~ ")"
+//! geoRing        = WS* ~ "_geoRing(" ~ WS* ~ float ~ WS* ~ "," ~ WS* ~ float ~ WS* ~ "," ~ WS* ~ float ~ WS* ~ "," ~ WS* ~ float ~ WS* ~ ")"
+//! geoDistance    = WS* ~ "_geoDistance(" ~ WS* ~ float ~ WS* ~ "," ~ WS* ~ float ~ WS* ~ ")" ~ WS* ~ ("<" | "<=" | ">" | ">=") ~ value
+//! WS             = whitespace | "/*" .* "*/" | "#" .* all but new lines
 //! ```
 //!
 //! Other BNF grammar used to handle some specific errors:
@@ -36,29 +56,43 @@
 //!
 
 mod condition;
+mod date;
 mod error;
 mod value;
 
+use std::cmp::Ordering;
 use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
 use std::ops::Deref;
 use std::str::FromStr;
 
-pub use condition::{parse_condition, parse_to, Condition};
+pub use condition::{
+    parse_between, parse_condition, parse_exists, parse_field_comparison, parse_in,
+    parse_in_buckets, parse_is_empty, parse_is_not_empty, parse_length, parse_not_exists,
+    parse_not_in, parse_position, parse_semver, parse_to, parse_values_count, Condition,
+    NumericBounds, MAX_LIST_VALUES,
+};
 use error::{cut_with_err, NomErrorExt};
 pub use error::{Error, ErrorKind};
 use nom::branch::alt;
-use nom::bytes::complete::tag;
-use nom::character::complete::{char, multispace0};
-use nom::combinator::{cut, eof, map};
-use nom::multi::{many0, separated_list1};
+use nom::bytes::complete::{tag, take_till, take_until};
+use nom::character::complete::{char, multispace1};
+use nom::combinator::{cut, eof, map, recognize, verify};
+use nom::multi::{many0, many1, separated_list0, separated_list1};
 use nom::number::complete::recognize_float;
 use nom::sequence::{delimited, preceded, terminated, tuple};
-use nom::Finish;
-use nom_locate::LocatedSpan;
+use nom::{Finish, InputTake, Slice};
+use nom_locate::{position, LocatedSpan};
 pub(crate) use value::parse_value;
 
 pub type Span<'a> = LocatedSpan<&'a str, &'a str>;
 
+/// The currency symbols that are ignored when a value is parsed as a number.
+pub const CURRENCY_SYMBOLS: [char; 4] = ['$', '€', '£', '¥'];
+
+/// The maximum number of prefixes of an invalid filter parsed by `parse_partial`.
+const MAX_PARTIAL_ATTEMPTS: usize = 10;
+
 type IResult<'a, Ret> = nom::IResult<Span<'a>, Ret, Error<'a>>;
 
 #[derive(Debug, Clone, Eq)]
@@ -68,6 +102,8 @@ pub struct Token<'a> {
     /// If you need to modify the original input you can use the `value` field
     /// to store your modified input.
     value: Option<String>,
+    /// Whether the token was written between quotes, a quoted `"*"` isn't a wildcard.
+    quoted: bool,
 }
 
 impl<'a> Deref for Token<'a> {
@@ -84,9 +120,35 @@ impl<'a> PartialEq for Token<'a> {
     }
 }
 
+/// Like the equality, the tokens are ordered on their original input,
+/// wherever they are located in the filter.
+impl<'a> PartialOrd for Token<'a> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a> Ord for Token<'a> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.span.fragment().cmp(other.span.fragment())
+    }
+}
+
+/// The tokens are hashed on their original input to be consistent with the equality.
+impl<'a> Hash for Token<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.span.fragment().hash(state)
+    }
+}
+
 impl<'a> Token<'a> {
     pub fn new(span: Span<'a>, value: Option<String>) -> Self {
-        Self { span, value }
+        Self { span, value, quoted: false }
+    }
+
+    /// Creates a token that was written between quotes, `span` being the inside of the quotes.
+    pub fn new_quoted(span: Span<'a>, value: Option<String>) -> Self {
+        Self { span, value, quoted: true }
     }
 
     pub fn value(&self) -> &str {
@@ -97,6 +159,19 @@ impl<'a> Token<'a> {
         Error::new_from_external(self.span, error)
     }
 
+    /// Like [`Token::as_external_error`] but the error goes from the start of this token
+    /// to the end of the `end` token, both tokens must come from the same filter.
+    pub fn as_external_error_until(
+        &self,
+        end: &Token<'a>,
+        error: impl std::error::Error,
+    ) -> Error<'a> {
+        let start = self.span.location_offset();
+        let end = end.span.location_offset() + end.span.fragment().len();
+        let input = Span::new_extra(self.span.extra, self.span.extra);
+        Error::new_from_external(input.slice(start..end), error)
+    }
+
     pub fn parse<T>(&self) -> Result<T, Error>
     where
         T: FromStr,
@@ -104,24 +179,76 @@ impl<'a> Token<'a> {
     {
         self.span.parse().map_err(|e| self.as_external_error(e))
     }
+
+    /// Returns `true` if the token is the `*` wildcard, it stands for any attribute
+    /// before an `EXISTS` and for an unbounded side of a range.
+    pub fn is_wildcard(&self) -> bool {
+        self.wildcard_prefix() == Some("")
+    }
+
+    /// Returns the prefix of a token ending with the `*` wildcard, i.e. `size` for `size*`,
+    /// it stands for all the attributes starting with the prefix before an `EXISTS`.
+    /// The quoted tokens never are wildcards.
+    pub fn wildcard_prefix(&self) -> Option<&str> {
+        if self.quoted {
+            None
+        } else {
+            self.value().strip_suffix('*')
+        }
+    }
+
+    /// Parses the token as a number. The ISO-8601 dates (i.e. `2024-01-01T00:00:00Z`)
+    /// are converted into the number of seconds since the unix epoch.
+    ///
+    /// The percentages (i.e. `15%`) are divided by 100 and the amounts prefixed or
+    /// suffixed by one of the [`CURRENCY_SYMBOLS`] (i.e. `$10` or `10€`) lose their symbol.
+    pub fn parse_number(&self) -> Result<f64, Error> {
+        let value = self.value();
+        if date::looks_like_date(value) {
+            date::parse_timestamp(value).map_err(|e| self.as_external_error(e))
+        } else if let Some(percent) = value.strip_suffix('%') {
+            percent.parse::<f64>().map(|n| n / 100.).map_err(|e| self.as_external_error(e))
+        } else if let Some(amount) = value
+            .strip_prefix(&CURRENCY_SYMBOLS[..])
+            .or_else(|| value.strip_suffix(&CURRENCY_SYMBOLS[..]))
+        {
+            amount.parse().map_err(|e| self.as_external_error(e))
+        } else {
+            self.parse()
+        }
+    }
 }
 
 impl<'a> From<Span<'a>> for Token<'a> {
     fn from(span: Span<'a>) -> Self {
-        Self { span, value: None }
+        Self { span, value: None, quoted: false }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum FilterCondition<'a> {
     Condition { fid: Token<'a>, op: Condition<'a> },
     Or(Box<Self>, Box<Self>),
     And(Box<Self>, Box<Self>),
     GeoLowerThan { point: [Token<'a>; 2], radius: Token<'a> },
     GeoGreaterThan { point: [Token<'a>; 2], radius: Token<'a> },
+    GeoInsidePolygon { points: Vec<[Token<'a>; 2]> },
+    GeoOutsidePolygon { points: Vec<[Token<'a>; 2]> },
 }
 
 impl<'a> FilterCondition<'a> {
+    /// ANDs all the conditions together, from left to right like the parser does.
+    /// Returns `None` if there is no condition and the condition itself if there is only one.
+    pub fn and_all(conditions: impl IntoIterator<Item = Self>) -> Option<Self> {
+        conditions.into_iter().reduce(|lhs, rhs| FilterCondition::And(Box::new(lhs), Box::new(rhs)))
+    }
+
+    /// ORs all the conditions together, from left to right like the parser does.
+    /// Returns `None` if there is no condition and the condition itself if there is only one.
+    pub fn or_all(conditions: impl IntoIterator<Item = Self>) -> Option<Self> {
+        conditions.into_iter().reduce(|lhs, rhs| FilterCondition::Or(Box::new(lhs), Box::new(rhs)))
+    }
+
     /// Returns the first token found at the specified depth, `None` if no token at this depth.
     pub fn token_at_depth(&self, depth: usize) -> Option<&Token> {
         match self {
@@ -136,6 +263,12 @@ impl<'a> FilterCondition<'a> {
             }
             FilterCondition::GeoLowerThan { point: [point, _], .. } if depth == 0 => Some(point),
             FilterCondition::GeoGreaterThan { point: [point, _], .. } if depth == 0 => Some(point),
+            FilterCondition::GeoInsidePolygon { points } if depth == 0 => {
+                points.first().map(|[point, _]| point)
+            }
+            FilterCondition::GeoOutsidePolygon { points } if depth == 0 => {
+                points.first().map(|[point, _]| point)
+            }
             _ => None,
         }
     }
@@ -155,21 +288,489 @@ impl<'a> FilterCondition<'a> {
             And(a, b) => Or(a.negate().into(), b.negate().into()),
             GeoLowerThan { point, radius } => GeoGreaterThan { point, radius },
             GeoGreaterThan { point, radius } => GeoLowerThan { point, radius },
+            GeoInsidePolygon { points } => GeoOutsidePolygon { points },
+            GeoOutsidePolygon { points } => GeoInsidePolygon { points },
+        }
+    }
+
+    /// Rewrites the filter in disjunctive normal form, an `OR` of `AND`s of operations,
+    /// by distributing the `AND`s over the `OR`s. The `NOT`s are already pushed down to the
+    /// operations by [`FilterCondition::negate`] when the filter is parsed.
+    ///
+    /// The number of `AND`s can grow exponentially with the size of the filter,
+    /// an error is returned if there would be more than `max_terms` of them.
+    pub fn to_dnf(self, max_terms: usize) -> Result<Self, Error<'a>> {
+        let context = self.first_token().span;
+        let context = Span::new_extra(context.extra, context.extra);
+        let terms = self
+            .dnf_terms(max_terms)
+            .ok_or_else(|| Error::new_from_kind(context, ErrorKind::TooManyTerms(max_terms)))?;
+        let terms = terms.into_iter().map(|term| FilterCondition::and_all(term).unwrap());
+        Ok(FilterCondition::or_all(terms).unwrap())
+    }
+
+    /// Returns the operands of the `AND`s that are `OR`ed together, or `None` if there
+    /// are more than `max_terms` of them.
+    fn dnf_terms(self, max_terms: usize) -> Option<Vec<Vec<Self>>> {
+        match self {
+            FilterCondition::Or(lhs, rhs) => {
+                let mut terms = lhs.dnf_terms(max_terms)?;
+                terms.extend(rhs.dnf_terms(max_terms)?);
+                (terms.len() <= max_terms).then(|| terms)
+            }
+            FilterCondition::And(lhs, rhs) => {
+                let lhs = lhs.dnf_terms(max_terms)?;
+                let rhs = rhs.dnf_terms(max_terms)?;
+                if lhs.len().checked_mul(rhs.len())? > max_terms {
+                    return None;
+                }
+                let mut terms = Vec::with_capacity(lhs.len() * rhs.len());
+                for left in &lhs {
+                    for right in &rhs {
+                        terms.push(left.iter().chain(right).cloned().collect());
+                    }
+                }
+                Some(terms)
+            }
+            condition => (max_terms > 0).then(|| vec![vec![condition]]),
+        }
+    }
+
+    /// Returns the leftmost token of the filter.
+    fn first_token(&self) -> &Token<'a> {
+        match self {
+            FilterCondition::Condition { fid, .. } => fid,
+            FilterCondition::Or(lhs, _) | FilterCondition::And(lhs, _) => lhs.first_token(),
+            FilterCondition::GeoLowerThan { point: [lat, _], .. }
+            | FilterCondition::GeoGreaterThan { point: [lat, _], .. } => lat,
+            FilterCondition::GeoInsidePolygon { points }
+            | FilterCondition::GeoOutsidePolygon { points } => &points[0][0],
+        }
+    }
+
+    /// Reorders the operands of the `AND`s so that the cheapest ones are evaluated first,
+    /// the result of the filter is unchanged. The estimated costs are, from the cheapest to
+    /// the most expensive:
+    ///  - the exact lookups (`=`, `!=`, `IN`, `EXISTS`...),
+    ///  - the numeric ranges (`<`, `>=`, `TO`...) that must walk the facet levels,
+    ///  - the geo filters that must go through the rtree, the values counts and the
+    ///    regular expressions that must go through all the facet values of the field
+    ///    and the `IS EMPTY` that must read the documents.
+    ///
+    /// A nested `OR` costs as much as its most expensive operand. The order of the `OR`s
+    /// operands is kept as is, as are the two radii of a `_geoRing` that are evaluated
+    /// together.
+    pub fn optimize_order(self) -> Self {
+        if let FilterCondition::And(lhs, rhs) = &self {
+            if is_geo_ring(lhs, rhs) {
+                return self;
+            }
+        }
+        match self {
+            FilterCondition::And(..) => {
+                let mut operands = Vec::new();
+                self.flatten_and_keeping_rings(&mut operands);
+                let mut operands: Vec<_> =
+                    operands.into_iter().map(FilterCondition::optimize_order).collect();
+                // the sort is stable, operands with the same cost keep their order
+                operands.sort_by_key(FilterCondition::estimated_cost);
+                let mut operands = operands.into_iter();
+                let first = operands.next().unwrap();
+                operands
+                    .fold(first, |acc, operand| FilterCondition::And(acc.into(), operand.into()))
+            }
+            FilterCondition::Or(lhs, rhs) => {
+                FilterCondition::Or(lhs.optimize_order().into(), rhs.optimize_order().into())
+            }
+            condition => condition,
+        }
+    }
+
+    fn flatten_and(self, operands: &mut Vec<Self>) {
+        match self {
+            FilterCondition::And(lhs, rhs) => {
+                lhs.flatten_and(operands);
+                rhs.flatten_and(operands);
+            }
+            condition => operands.push(condition),
+        }
+    }
+
+    fn flatten_and_keeping_rings(self, operands: &mut Vec<Self>) {
+        match self {
+            FilterCondition::And(lhs, rhs) if !is_geo_ring(&lhs, &rhs) => {
+                lhs.flatten_and_keeping_rings(operands);
+                rhs.flatten_and_keeping_rings(operands);
+            }
+            condition => operands.push(condition),
+        }
+    }
+
+    fn flatten_or(self, operands: &mut Vec<Self>) {
+        match self {
+            FilterCondition::Or(lhs, rhs) => {
+                lhs.flatten_or(operands);
+                rhs.flatten_or(operands);
+            }
+            condition => operands.push(condition),
+        }
+    }
+
+    /// Rewrites the filter in a canonical form where the operands of the chained `AND`s and
+    /// `OR`s are sorted, as are the values of the `IN`s, so that the filters only differing
+    /// by the order of their operands, i.e. `a = 1 AND b = 2` and `b = 2 AND a = 1`, are
+    /// equal once canonicalized. The operations are ordered on their attribute first, then
+    /// on their operation, the values being compared as written in the filter.
+    pub fn canonicalize(self) -> Self {
+        match self {
+            FilterCondition::And(..) => {
+                let mut operands = Vec::new();
+                self.flatten_and(&mut operands);
+                let mut operands: Vec<_> =
+                    operands.into_iter().map(FilterCondition::canonicalize).collect();
+                operands.sort();
+                FilterCondition::and_all(operands).unwrap()
+            }
+            FilterCondition::Or(..) => {
+                let mut operands = Vec::new();
+                self.flatten_or(&mut operands);
+                let mut operands: Vec<_> =
+                    operands.into_iter().map(FilterCondition::canonicalize).collect();
+                operands.sort();
+                FilterCondition::or_all(operands).unwrap()
+            }
+            FilterCondition::Condition { fid, op: Condition::In(mut values) } => {
+                values.sort();
+                FilterCondition::Condition { fid, op: Condition::In(values) }
+            }
+            FilterCondition::Condition { fid, op: Condition::NotIn(mut values) } => {
+                values.sort();
+                FilterCondition::Condition { fid, op: Condition::NotIn(values) }
+            }
+            condition => condition,
+        }
+    }
+
+    /// Splits the filter in two: the operands of its top-level `AND`s that only use the
+    /// attributes matching the predicate, and the rest of the filter. It allows to evaluate
+    /// a part of the filter where these attributes are, i.e. on a shard of the index, and the
+    /// remainder elsewhere, the filter being the `AND` of the two parts.
+    ///
+    /// An `OR` can't be split, it is returned whole in the remainder when it uses an attribute
+    /// not matching the predicate. The geo filters use the `_geo` attribute.
+    pub fn split_by_field(&self, predicate: impl Fn(&str) -> bool) -> (Option<Self>, Option<Self>) {
+        let mut operands = Vec::new();
+        self.clone().flatten_and(&mut operands);
+        let (matching, remainder): (Vec<_>, Vec<_>) =
+            operands.into_iter().partition(|operand| operand.all_attributes(&predicate));
+        (FilterCondition::and_all(matching), FilterCondition::and_all(remainder))
+    }
+
+    /// Returns `true` if all the attributes used by the filter match the predicate.
+    fn all_attributes(&self, predicate: &dyn Fn(&str) -> bool) -> bool {
+        match self {
+            FilterCondition::Condition { op: Condition::GeoDistance { .. }, .. } => {
+                predicate("_geo")
+            }
+            FilterCondition::Condition { fid, op: Condition::FieldComparison(comparison) } => {
+                let other = match comparison.as_ref() {
+                    Condition::GreaterThan(other)
+                    | Condition::GreaterThanOrEqual(other)
+                    | Condition::Equal(other)
+                    | Condition::NotEqual(other)
+                    | Condition::LowerThan(other)
+                    | Condition::LowerThanOrEqual(other) => other,
+                    _ => unreachable!("the field comparisons only support simple comparisons"),
+                };
+                predicate(fid.value()) && predicate(other.value())
+            }
+            FilterCondition::Condition { fid, .. } => predicate(fid.value()),
+            FilterCondition::Or(lhs, rhs) | FilterCondition::And(lhs, rhs) => {
+                lhs.all_attributes(predicate) && rhs.all_attributes(predicate)
+            }
+            FilterCondition::GeoLowerThan { .. }
+            | FilterCondition::GeoGreaterThan { .. }
+            | FilterCondition::GeoInsidePolygon { .. }
+            | FilterCondition::GeoOutsidePolygon { .. } => predicate("_geo"),
+        }
+    }
+
+    fn estimated_cost(&self) -> u8 {
+        match self {
+            FilterCondition::Condition { op, .. } => match op {
+                Condition::GreaterThan(_)
+                | Condition::GreaterThanOrEqual(_)
+                | Condition::LowerThan(_)
+                | Condition::LowerThanOrEqual(_)
+                | Condition::Between { .. }
+                | Condition::NotBetween { .. }
+                | Condition::InBuckets(_) => 1,
+                // the values of all the documents must be counted, matched or read
+                Condition::ValuesCount(_)
+                | Condition::SemVer(_)
+                | Condition::WordsCount(_)
+                | Condition::CharsCount(_)
+                | Condition::FieldComparison(_)
+                | Condition::Position { .. }
+                | Condition::GeoDistance { .. }
+                | Condition::Regex(_)
+                | Condition::NotRegex(_)
+                | Condition::IsEmpty
+                | Condition::IsNotEmpty => 2,
+                _ => 0,
+            },
+            FilterCondition::Or(lhs, rhs) | FilterCondition::And(lhs, rhs) => {
+                lhs.estimated_cost().max(rhs.estimated_cost())
+            }
+            FilterCondition::GeoLowerThan { .. }
+            | FilterCondition::GeoGreaterThan { .. }
+            | FilterCondition::GeoInsidePolygon { .. }
+            | FilterCondition::GeoOutsidePolygon { .. } => 2,
+        }
+    }
+
+    /// Returns `true` if the filter contains a geo operation (`_geoRadius`, `_geoPolygon`...),
+    /// such a filter can only be evaluated on an index with `_geo` coordinates.
+    pub fn contains_geo(&self) -> bool {
+        match self {
+            FilterCondition::Condition { op, .. } => matches!(op, Condition::GeoDistance { .. }),
+            FilterCondition::Or(lhs, rhs) | FilterCondition::And(lhs, rhs) => {
+                lhs.contains_geo() || rhs.contains_geo()
+            }
+            FilterCondition::GeoLowerThan { .. }
+            | FilterCondition::GeoGreaterThan { .. }
+            | FilterCondition::GeoInsidePolygon { .. }
+            | FilterCondition::GeoOutsidePolygon { .. } => true,
+        }
+    }
+
+    /// Returns `true` if any of the operations on the attributes matches the predicate,
+    /// the filter is not explored any further once a matching operation is found.
+    pub fn contains_operator(&self, predicate: impl Fn(&Condition) -> bool) -> bool {
+        self.any_operator(&predicate)
+    }
+
+    fn any_operator(&self, predicate: &dyn Fn(&Condition) -> bool) -> bool {
+        match self {
+            FilterCondition::Condition { op, .. } => predicate(op),
+            FilterCondition::Or(lhs, rhs) | FilterCondition::And(lhs, rhs) => {
+                lhs.any_operator(predicate) || rhs.any_operator(predicate)
+            }
+            _ => false,
+        }
+    }
+
+    /// Rebuilds the filter by applying `f` on the attribute and the operation of each
+    /// condition, the `AND`s, the `OR`s and the geo filters are kept as is.
+    pub fn map_operators(
+        self,
+        f: impl Fn(Token<'a>, Condition<'a>) -> (Token<'a>, Condition<'a>),
+    ) -> Self {
+        self.map_each_operator(&f)
+    }
+
+    fn map_each_operator(
+        self,
+        f: &dyn Fn(Token<'a>, Condition<'a>) -> (Token<'a>, Condition<'a>),
+    ) -> Self {
+        match self {
+            FilterCondition::Condition { fid, op } => {
+                let (fid, op) = f(fid, op);
+                FilterCondition::Condition { fid, op }
+            }
+            FilterCondition::Or(lhs, rhs) => FilterCondition::Or(
+                lhs.map_each_operator(f).into(),
+                rhs.map_each_operator(f).into(),
+            ),
+            FilterCondition::And(lhs, rhs) => FilterCondition::And(
+                lhs.map_each_operator(f).into(),
+                rhs.map_each_operator(f).into(),
+            ),
+            condition => condition,
+        }
+    }
+
+    /// Swaps the two coordinates of the points of the geo filters, to read a filter written
+    /// in the `(longitude, latitude)` order of GeoJSON as the `(latitude, longitude)` order
+    /// the geo filters are evaluated with. The radii are kept as is.
+    pub fn swap_coordinates(self) -> Self {
+        let swap = |[lat, lng]: [Token<'a>; 2]| [lng, lat];
+        match self {
+            FilterCondition::Or(lhs, rhs) => {
+                FilterCondition::Or(lhs.swap_coordinates().into(), rhs.swap_coordinates().into())
+            }
+            FilterCondition::And(lhs, rhs) => {
+                FilterCondition::And(lhs.swap_coordinates().into(), rhs.swap_coordinates().into())
+            }
+            FilterCondition::GeoLowerThan { point, radius } => {
+                FilterCondition::GeoLowerThan { point: swap(point), radius }
+            }
+            FilterCondition::GeoGreaterThan { point, radius } => {
+                FilterCondition::GeoGreaterThan { point: swap(point), radius }
+            }
+            FilterCondition::GeoInsidePolygon { points } => {
+                FilterCondition::GeoInsidePolygon { points: points.into_iter().map(swap).collect() }
+            }
+            FilterCondition::GeoOutsidePolygon { points } => FilterCondition::GeoOutsidePolygon {
+                points: points.into_iter().map(swap).collect(),
+            },
+            FilterCondition::Condition {
+                fid,
+                op: Condition::GeoDistance { point, comparison },
+            } => {
+                let op = Condition::GeoDistance { point: swap(point), comparison };
+                FilterCondition::Condition { fid, op }
+            }
+            condition => condition,
+        }
+    }
+
+    /// Returns `true` if the filter can't match any document whatever the content of the
+    /// index, because one of its `AND`s contains an operation and its complement on the same
+    /// attribute (i.e. `tag = red AND tag != red`, `a EXISTS AND a NOT EXISTS` or a
+    /// `_geoRadius` with its negation), or because it is a `_geoRadius` with a radius of zero.
+    ///
+    /// The detection is conservative, `false` doesn't mean that the filter matches documents.
+    /// The disjoint ranges (i.e. `price > 10 AND price < 5`) are not reported as a document
+    /// with several values, `[3, 20]` here, can match both of them. The coordinates and radii
+    /// of the geo filters must be exactly equal, `_geoRadius(1, 2, 3)` is the negation of
+    /// `NOT _geoRadius(1.0, 2, 3)` but not of `NOT _geoRadius(1.0000001, 2, 3)`.
+    pub fn is_trivially_empty(&self) -> bool {
+        match self {
+            FilterCondition::Or(lhs, rhs) => lhs.is_trivially_empty() && rhs.is_trivially_empty(),
+            FilterCondition::And(..) => {
+                let mut operands = Vec::new();
+                self.operands_of_and(&mut operands);
+                operands.iter().any(|operand| operand.is_trivially_empty())
+                    || operands.iter().enumerate().any(|(i, lhs)| {
+                        operands[i + 1..]
+                            .iter()
+                            .any(|rhs| lhs.contradicts(rhs) || rhs.contradicts(lhs))
+                    })
+            }
+            // the documents are strictly within the radius
+            FilterCondition::GeoLowerThan { radius, .. } => {
+                matches!(radius.parse::<f64>(), Ok(radius) if radius <= 0.)
+            }
+            _ => false,
+        }
+    }
+
+    fn operands_of_and<'b>(&'b self, operands: &mut Vec<&'b Self>) {
+        match self {
+            FilterCondition::And(lhs, rhs) => {
+                lhs.operands_of_and(operands);
+                rhs.operands_of_and(operands);
+            }
+            condition => operands.push(condition),
+        }
+    }
+
+    /// Returns `true` if no document can match both `self` and `other`,
+    /// only the operations negated by `other` are checked.
+    fn contradicts(&self, other: &Self) -> bool {
+        use Condition::*;
+
+        // the values are compared the way they are normalized in the index
+        let normalize = |token: &Token| token.value().trim().to_lowercase();
+        // the coordinates are compared as numbers, `1` and `1.0` are the same coordinate
+        let same_number = |lhs: &Token, rhs: &Token| match (lhs.parse::<f64>(), rhs.parse::<f64>())
+        {
+            (Ok(lhs), Ok(rhs)) => lhs == rhs,
+            _ => lhs == rhs,
+        };
+        let same_point = |[lat, lng]: &[Token; 2], [other_lat, other_lng]: &[Token; 2]| {
+            same_number(lat, other_lat) && same_number(lng, other_lng)
+        };
+
+        match (self, other) {
+            (
+                FilterCondition::Condition { fid, op },
+                FilterCondition::Condition { fid: other_fid, op: other_op },
+            ) if fid == other_fid => match (op, other_op) {
+                (Exists, NotExists) | (IsEmpty, IsNotEmpty) => true,
+                (NumericEqual(lhs), NumericNotEqual(rhs)) | (Regex(lhs), NotRegex(rhs)) => {
+                    lhs == rhs
+                }
+                (Equal(value), NotEqual(excluded)) => normalize(value) == normalize(excluded),
+                (Equal(value), NotIn(excluded)) => {
+                    let value = normalize(value);
+                    excluded.iter().any(|excluded| normalize(excluded) == value)
+                }
+                (In(values), NotEqual(excluded)) => {
+                    let excluded = normalize(excluded);
+                    values.iter().all(|value| normalize(value) == excluded)
+                }
+                (In(values), NotIn(excluded)) => {
+                    let excluded: Vec<_> = excluded.iter().map(normalize).collect();
+                    values.iter().all(|value| excluded.contains(&normalize(value)))
+                }
+                _ => false,
+            },
+            (
+                FilterCondition::GeoLowerThan { point, radius },
+                FilterCondition::GeoGreaterThan { point: other_point, radius: other_radius },
+            ) => same_point(point, other_point) && same_number(radius, other_radius),
+            (
+                FilterCondition::GeoInsidePolygon { points },
+                FilterCondition::GeoOutsidePolygon { points: other_points },
+            ) => {
+                points.len() == other_points.len()
+                    && points.iter().zip(other_points).all(|(lhs, rhs)| same_point(lhs, rhs))
+            }
+            _ => false,
         }
     }
 
     pub fn parse(input: &'a str) -> Result<Option<Self>, Error> {
-        if input.trim().is_empty() {
+        let span = Span::new_extra(input, input);
+        // a filter only made of whitespaces and comments is empty
+        if matches!(blank(span), Ok((rest, _)) if rest.fragment().is_empty()) {
             return Ok(None);
         }
-        let span = Span::new_extra(input, input);
         parse_filter(span).finish().map(|(_rem, output)| Some(output))
     }
+
+    /// Parses as much of a filter as possible, i.e. a filter that is still being written.
+    /// When the filter is invalid, the error is returned along with the longest prefix of
+    /// the filter, cut at the end of a word before the error, that is a valid filter. Only
+    /// the few longest prefixes are tried, the filter is parsed a bounded number of times.
+    pub fn parse_partial(input: &'a str) -> (Option<Self>, Option<Error<'a>>) {
+        let error = match Self::parse(input) {
+            Ok(condition) => return (condition, None),
+            Err(error) => error,
+        };
+
+        let (error_start, _) = error.byte_offsets();
+        let condition = input[..error_start]
+            .char_indices()
+            .rev()
+            .filter(|(_, c)| c.is_whitespace())
+            .map(|(i, _)| &input[..i])
+            .filter(|prefix| !prefix.ends_with(char::is_whitespace))
+            .take(MAX_PARTIAL_ATTEMPTS)
+            .find_map(|prefix| Self::parse(prefix).ok().flatten());
+        (condition, Some(error))
+    }
+}
+
+/// Parses the OPTIONAL whitespaces and comments, the `/* ... */` block comments and
+/// the `# ...` line comments are skipped like whitespaces. A `#` only starts a comment
+/// at the start of the filter or after a whitespace, `C#` is not cut.
+pub(crate) fn blank(input: Span) -> IResult<Span> {
+    let block_comment = delimited(tag("/*"), take_until("*/"), tag("*/"));
+    let after_blank = |span: &Span| {
+        let before = span.extra.get(..span.location_offset()).unwrap_or_default();
+        before.is_empty() || before.ends_with(char::is_whitespace)
+    };
+    let line_comment =
+        preceded(verify(position, after_blank), preceded(char('#'), take_till(|c| c == '\n')));
+    recognize(many0(alt((multispace1, block_comment, line_comment))))(input)
 }
 
-/// remove OPTIONAL whitespaces before AND after the provided parser.
+/// remove OPTIONAL whitespaces and comments before AND after the provided parser.
 fn ws<'a, O>(inner: impl FnMut(Span<'a>) -> IResult<O>) -> impl FnMut(Span<'a>) -> IResult<O> {
-    delimited(multispace0, inner, multispace0)
+    delimited(blank, inner, blank)
 }
 
 /// or             = and (~ "OR" ~ and)
@@ -202,35 +803,173 @@ fn parse_not(input: Span) -> IResult<FilterCondition> {
     alt((map(preceded(tag("NOT"), cut(parse_not)), |e| e.negate()), parse_primary))(input)
 }
 
+/// anyAll         = WS* ~ ("ANY" | "ALL") ~ WS* ~ "(" ~ expression ("," expression)* ~ ")" ~ WS*
+/// `ANY(...)` is an `OR` of its operands and `ALL(...)` an `AND` of its operands.
+/// If we parse `ANY(` or `ALL(` we MUST parse the rest of the expression.
+fn parse_any_all(input: Span) -> IResult<FilterCondition> {
+    let (rest, keyword) = terminated(ws(alt((tag("ANY"), tag("ALL")))), char('('))(input)?;
+    let (rest, operands) = terminated(
+        separated_list1(ws(char(',')), cut(parse_expression)),
+        cut_with_err(ws(char(')')), |c| {
+            Error::new_from_kind(input, ErrorKind::MissingClosingDelimiter(c.char()))
+        }),
+    )(rest)?;
+
+    // there is always at least one operand
+    let condition = match *keyword.fragment() {
+        "ANY" => FilterCondition::or_all(operands),
+        _ => FilterCondition::and_all(operands),
+    };
+    Ok((rest, condition.unwrap()))
+}
+
 /// geoRadius      = WS* ~ "_geoRadius(float ~ "," ~ float ~ "," float)
+///                | WS* ~ "_geoRadius((point ~ ",")+ ~ float)
 /// If we parse `_geoRadius` we MUST parse the rest of the expression.
+/// With several points the documents within the radius of any of them match,
+/// the filter is an `OR` of one `_geoRadius` per point.
 fn parse_geo_radius(input: Span) -> IResult<FilterCondition> {
+    // the points can also be given as `[lat, lng]` pairs
+    let point = map(
+        tuple((
+            ws(char('[')),
+            ws(recognize_float),
+            char(','),
+            ws(recognize_float),
+            ws(char(']')),
+            char(','),
+        )),
+        |(_, lat, _, lng, _, _)| [lat, lng],
+    );
+    let bracketed_points =
+        map(tuple((many1(point), ws(recognize_float))), |(points, radius)| Ok((points, radius)));
+    // all the arguments are parsed to report how many of them were given
+    let flat_args = map(separated_list0(tag(","), ws(recognize_float)), |args| match args[..] {
+        [lat, lng, radius] => Ok((vec![[lat, lng]], radius)),
+        _ => Err(args.len()),
+    });
     // we want to forbid space BEFORE the _geoRadius but not after
     let parsed = preceded(
-        tuple((multispace0, tag("_geoRadius"))),
+        tuple((blank, tag("_geoRadius"))),
         // if we were able to parse `_geoRadius` and can't parse the rest of the input we return a failure
-        cut(delimited(char('('), separated_list1(tag(","), ws(recognize_float)), char(')'))),
+        cut(delimited(char('('), alt((bracketed_points, flat_args)), char(')'))),
     )(input)
     .map_err(|e| e.map(|_| Error::new_from_kind(input, ErrorKind::Geo)));
 
+    let (input, (points, radius)) = match parsed? {
+        (rest, Ok(args)) => (rest, args),
+        (rest, Err(count)) => {
+            let geo_radius = input.take(input.len() - rest.len());
+            let kind = ErrorKind::GeoRadiusArguments(count);
+            return Err(nom::Err::Failure(Error::new_from_kind(geo_radius, kind)));
+        }
+    };
+
+    let radii = points.into_iter().map(|[lat, lng]| FilterCondition::GeoLowerThan {
+        point: [lat.into(), lng.into()],
+        radius: radius.into(),
+    });
+    // there is always at least one point
+    Ok((input, FilterCondition::or_all(radii).unwrap()))
+}
+
+/// geoPolygon     = WS* ~ "_geoPolygon(float ~ "," ~ float ~ ("," ~ float ~ "," ~ float)+)
+/// If we parse `_geoPolygon` we MUST parse the rest of the expression.
+fn parse_geo_polygon(input: Span) -> IResult<FilterCondition> {
+    // we want to forbid space BEFORE the _geoPolygon but not after
+    let parsed = preceded(
+        tuple((blank, tag("_geoPolygon"))),
+        // if we were able to parse `_geoPolygon` and can't parse the rest of the input we return a failure
+        cut(delimited(char('('), separated_list1(tag(","), ws(recognize_float)), char(')'))),
+    )(input)
+    .map_err(|e| e.map(|_| Error::new_from_kind(input, ErrorKind::GeoPolygon)));
+
     let (input, args) = parsed?;
 
-    if args.len() != 3 {
-        return Err(nom::Err::Failure(Error::new_from_kind(input, ErrorKind::Geo)));
+    // a polygon is made of at least three points, each one of them being a latitude and a longitude
+    if args.len() < 6 || args.len() % 2 != 0 {
+        return Err(nom::Err::Failure(Error::new_from_kind(input, ErrorKind::GeoPolygon)));
+    }
+
+    let points = args.chunks(2).map(|point| [point[0].into(), point[1].into()]).collect();
+    Ok((input, FilterCondition::GeoInsidePolygon { points }))
+}
+
+/// Returns `true` if the operands of an `AND` are the two radii of a `_geoRing`, see
+/// [`parse_geo_ring`].
+fn is_geo_ring(lhs: &FilterCondition, rhs: &FilterCondition) -> bool {
+    matches!(
+        (lhs, rhs),
+        (FilterCondition::GeoLowerThan { point, .. }, FilterCondition::GeoGreaterThan { point: other, .. })
+            if point == other
+    )
+}
+
+/// geoRing        = WS* ~ "_geoRing(float ~ "," ~ float ~ "," ~ float ~ "," ~ float)
+/// The ring is made of the documents at a distance of the point greater than or equal to the
+/// inner radius and lower than the outer one, it is parsed as `_geoRadius(lat, lng, outer)
+/// AND NOT _geoRadius(lat, lng, inner)`. If we parse `_geoRing` we MUST parse the rest of the
+/// expression.
+fn parse_geo_ring(input: Span) -> IResult<FilterCondition> {
+    // we want to forbid space BEFORE the _geoRing but not after
+    let (rest, args) = preceded(
+        tuple((blank, tag("_geoRing"))),
+        cut(delimited(char('('), separated_list1(tag(","), ws(recognize_float)), char(')'))),
+    )(input)
+    .map_err(|e| e.map(|_| Error::new_from_kind(input, ErrorKind::GeoRing)))?;
+
+    let geo_ring = input.take(input.len() - rest.len());
+    let (lat, lng, inner, outer) = match args[..] {
+        [lat, lng, inner, outer] => (lat, lng, inner, outer),
+        _ => return Err(nom::Err::Failure(Error::new_from_kind(geo_ring, ErrorKind::GeoRing))),
+    };
+    let radii = (inner.fragment().parse::<f64>(), outer.fragment().parse::<f64>());
+    if !matches!(radii, (Ok(inner), Ok(outer)) if 0. <= inner && inner < outer) {
+        return Err(nom::Err::Failure(Error::new_from_kind(geo_ring, ErrorKind::GeoRingRadii)));
     }
 
-    let res = FilterCondition::GeoLowerThan {
-        point: [args[0].into(), args[1].into()],
-        radius: args[2].into(),
+    let point = [Token::from(lat), Token::from(lng)];
+    let within = FilterCondition::GeoLowerThan { point: point.clone(), radius: outer.into() };
+    let outside = FilterCondition::GeoGreaterThan { point, radius: inner.into() };
+    Ok((rest, FilterCondition::And(Box::new(within), Box::new(outside))))
+}
+
+/// geoDistance    = WS* ~ "_geoDistance(float ~ "," ~ float ~ ")" ~ WS* ~ ("<" | "<=" | ">" | ">=") ~ value
+/// If we parse `_geoDistance(` we MUST parse the rest of the expression.
+fn parse_geo_distance(input: Span) -> IResult<FilterCondition> {
+    let (rest, fid) = terminated(preceded(blank, tag("_geoDistance")), char('('))(input)?;
+
+    let operator = alt((tag("<="), tag(">="), tag("<"), tag(">")));
+    let (rest, (lat, _, lng, _, _, op, distance)) = cut(tuple((
+        ws(recognize_float),
+        char(','),
+        ws(recognize_float),
+        char(')'),
+        blank,
+        operator,
+        parse_value,
+    )))(rest)
+    .map_err(|e| e.map(|_| Error::new_from_kind(input, ErrorKind::GeoDistance)))?;
+
+    let comparison = match *op.fragment() {
+        "<=" => Condition::LowerThanOrEqual(distance),
+        ">=" => Condition::GreaterThanOrEqual(distance),
+        "<" => Condition::LowerThan(distance),
+        ">" => Condition::GreaterThan(distance),
+        _ => unreachable!(),
+    };
+    let op = Condition::GeoDistance {
+        point: [lat.into(), lng.into()],
+        comparison: Box::new(comparison),
     };
-    Ok((input, res))
+    Ok((rest, FilterCondition::Condition { fid: fid.into(), op }))
 }
 
 /// geoPoint      = WS* ~ "_geoPoint(float ~ "," ~ float ~ "," float)
 fn parse_geo_point(input: Span) -> IResult<FilterCondition> {
     // we want to forbid space BEFORE the _geoPoint but not after
     tuple((
-        multispace0,
+        blank,
         tag("_geoPoint"),
         // if we were able to parse `_geoPoint` we are going to return a Failure whatever happens next.
         cut(delimited(char('('), separated_list1(tag(","), ws(recognize_float)), char(')'))),
@@ -240,7 +979,7 @@ fn parse_geo_point(input: Span) -> IResult<FilterCondition> {
     Err(nom::Err::Failure(Error::new_from_kind(input, ErrorKind::ReservedGeo("_geoPoint"))))
 }
 
-/// primary        = (WS* ~ "("  expression ")" ~ WS*) | geoRadius | condition | to
+/// primary        = (WS* ~ "("  expression ")" ~ WS*) | geoRadius | geoPolygon | geoRing | condition | exists | notExists | inBuckets | in | notIn | valuesCount | semver | length | isEmpty | isNotEmpty | to | between
 fn parse_primary(input: Span) -> IResult<FilterCondition> {
     alt((
         // if we find a first parenthesis, then we must parse an expression and find the closing parenthesis
@@ -251,9 +990,26 @@ fn parse_primary(input: Span) -> IResult<FilterCondition> {
                 Error::new_from_kind(input, ErrorKind::MissingClosingDelimiter(c.char()))
             }),
         ),
-        parse_geo_radius,
+        parse_any_all,
+        // nom can't try more than 21 parsers at once, the geo ones are grouped
+        alt((parse_geo_radius, parse_geo_polygon, parse_geo_ring, parse_geo_distance)),
+        // must be tried before the conditions comparing the attribute with a value
+        parse_field_comparison,
+        parse_position,
         parse_condition,
+        parse_exists,
+        parse_not_exists,
+        // must be tried before `IN` that would stop at the `_BUCKETS`
+        parse_in_buckets,
+        parse_in,
+        parse_not_in,
+        parse_values_count,
+        parse_semver,
+        parse_length,
+        parse_is_empty,
+        parse_is_not_empty,
         parse_to,
+        parse_between,
         // the next lines are only for error handling and are written at the end to have the less possible performance impact
         parse_geo_point,
     ))(input)
@@ -383,6 +1139,20 @@ pub mod tests {
                     op: Condition::GreaterThan(rtok("subscribers > ", "1000")),
                 },
             ),
+            (
+                "price > $10",
+                Fc::Condition {
+                    fid: rtok("", "price"),
+                    op: Condition::GreaterThan(rtok("price > ", "$10")),
+                },
+            ),
+            (
+                "discount >= 15%",
+                Fc::Condition {
+                    fid: rtok("", "discount"),
+                    op: Condition::GreaterThanOrEqual(rtok("discount >= ", "15%")),
+                },
+            ),
             (
                 "subscribers <= 1000",
                 Fc::Condition {
@@ -436,71 +1206,500 @@ pub mod tests {
                 },
             ),
             (
-                "NOT subscribers 100 TO 1000",
-                Fc::Or(
-                    Fc::Condition {
-                        fid: rtok("NOT ", "subscribers"),
-                        op: Condition::LowerThan(rtok("NOT subscribers ", "100")),
-                    }
-                    .into(),
-                    Fc::Condition {
-                        fid: rtok("NOT ", "subscribers"),
-                        op: Condition::GreaterThan(rtok("NOT subscribers 100 TO ", "1000")),
-                    }
-                    .into(),
-                ),
+                "subscribers 100 TO *",
+                Fc::Condition {
+                    fid: rtok("", "subscribers"),
+                    op: Condition::Between {
+                        from: rtok("subscribers ", "100"),
+                        to: rtok("subscribers 100 TO ", "*"),
+                    },
+                },
             ),
             (
-                "_geoRadius(12, 13, 14)",
-                Fc::GeoLowerThan {
-                    point: [rtok("_geoRadius(", "12"), rtok("_geoRadius(12, ", "13")],
-                    radius: rtok("_geoRadius(12, 13, ", "14"),
+                "subscribers * TO 1000",
+                Fc::Condition {
+                    fid: rtok("", "subscribers"),
+                    op: Condition::Between {
+                        from: rtok("subscribers ", "*"),
+                        to: rtok("subscribers * TO ", "1000"),
+                    },
                 },
             ),
             (
-                "NOT _geoRadius(12, 13, 14)",
-                Fc::GeoGreaterThan {
-                    point: [rtok("NOT _geoRadius(", "12"), rtok("NOT _geoRadius(12, ", "13")],
-                    radius: rtok("NOT _geoRadius(12, 13, ", "14"),
+                "subscribers 100 to 1000",
+                Fc::Condition {
+                    fid: rtok("", "subscribers"),
+                    op: Condition::Between {
+                        from: rtok("subscribers ", "100"),
+                        to: rtok("subscribers 100 to ", "1000"),
+                    },
                 },
             ),
-            // test simple `or` and `and`
             (
-                "channel = ponce AND 'dog race' != 'bernese mountain'",
-                Fc::And(
-                    Fc::Condition {
-                        fid: rtok("", "channel"),
-                        op: Condition::Equal(rtok("channel = ", "ponce")),
-                    }
-                    .into(),
-                    Fc::Condition {
-                        fid: rtok("channel = ponce AND '", "dog race"),
-                        op: Condition::NotEqual(rtok(
-                            "channel = ponce AND 'dog race' != '",
-                            "bernese mountain",
-                        )),
-                    }
-                    .into(),
-                ),
+                "TO 1 To 5",
+                Fc::Condition {
+                    fid: rtok("", "TO"),
+                    op: Condition::Between {
+                        from: rtok("TO ", "1"),
+                        to: rtok("TO 1 To ", "5"),
+                    },
+                },
             ),
             (
-                "channel = ponce OR 'dog race' != 'bernese mountain'",
-                Fc::Or(
-                    Fc::Condition {
-                        fid: rtok("", "channel"),
-                        op: Condition::Equal(rtok("channel = ", "ponce")),
-                    }
-                    .into(),
-                    Fc::Condition {
-                        fid: rtok("channel = ponce OR '", "dog race"),
-                        op: Condition::NotEqual(rtok(
-                            "channel = ponce OR 'dog race' != '",
-                            "bernese mountain",
-                        )),
-                    }
-                    .into(),
-                ),
-            ),
+                "word \"TO\" TO \"to\"",
+                Fc::Condition {
+                    fid: rtok("", "word"),
+                    op: Condition::Between {
+                        from: rtok("word \"", "TO"),
+                        to: rtok("word \"TO\" TO \"", "to"),
+                    },
+                },
+            ),
+            (
+                "word = \"TO\"",
+                Fc::Condition {
+                    fid: rtok("", "word"),
+                    op: Condition::Equal(rtok("word = \"", "TO")),
+                },
+            ),
+            (
+                "NOT subscribers 100 TO 1000",
+                Fc::Condition {
+                    fid: rtok("NOT ", "subscribers"),
+                    op: Condition::NotBetween {
+                        from: rtok("NOT subscribers ", "100"),
+                        to: rtok("NOT subscribers 100 TO ", "1000"),
+                    },
+                },
+            ),
+            (
+                "subscribers BETWEEN 100 AND 1000",
+                Fc::Condition {
+                    fid: rtok("", "subscribers"),
+                    op: Condition::Between {
+                        from: rtok("subscribers BETWEEN ", "100"),
+                        to: rtok("subscribers BETWEEN 100 AND ", "1000"),
+                    },
+                },
+            ),
+            (
+                "price BETWEEN 10 AND 20",
+                Fc::Condition {
+                    fid: rtok("", "price"),
+                    op: Condition::Between {
+                        from: rtok("price BETWEEN ", "10"),
+                        to: rtok("price BETWEEN 10 AND ", "20"),
+                    },
+                },
+            ),
+            (
+                "subscribers BETWEEN * AND 1000",
+                Fc::Condition {
+                    fid: rtok("", "subscribers"),
+                    op: Condition::Between {
+                        from: rtok("subscribers BETWEEN ", "*"),
+                        to: rtok("subscribers BETWEEN * AND ", "1000"),
+                    },
+                },
+            ),
+            (
+                "NOT subscribers BETWEEN 100 AND 1000",
+                Fc::Condition {
+                    fid: rtok("NOT ", "subscribers"),
+                    op: Condition::NotBetween {
+                        from: rtok("NOT subscribers BETWEEN ", "100"),
+                        to: rtok("NOT subscribers BETWEEN 100 AND ", "1000"),
+                    },
+                },
+            ),
+            (
+                "subscribers BETWEEN 100 AND 1000 AND channel = ponce",
+                Fc::And(
+                    Fc::Condition {
+                        fid: rtok("", "subscribers"),
+                        op: Condition::Between {
+                            from: rtok("subscribers BETWEEN ", "100"),
+                            to: rtok("subscribers BETWEEN 100 AND ", "1000"),
+                        },
+                    }
+                    .into(),
+                    Fc::Condition {
+                        fid: rtok("subscribers BETWEEN 100 AND 1000 AND ", "channel"),
+                        op: Condition::Equal(rtok(
+                            "subscribers BETWEEN 100 AND 1000 AND channel = ",
+                            "ponce",
+                        )),
+                    }
+                    .into(),
+                ),
+            ),
+            (
+                "rating =# 5",
+                Fc::Condition {
+                    fid: rtok("", "rating"),
+                    op: Condition::NumericEqual(rtok("rating =# ", "5")),
+                },
+            ),
+            (
+                "NOT rating =# 5",
+                Fc::Condition {
+                    fid: rtok("NOT ", "rating"),
+                    op: Condition::NumericNotEqual(rtok("NOT rating =# ", "5")),
+                },
+            ),
+            (
+                "rating !=# 5",
+                Fc::Condition {
+                    fid: rtok("", "rating"),
+                    op: Condition::NumericNotEqual(rtok("rating !=# ", "5")),
+                },
+            ),
+            (
+                "NOT rating !=# 5",
+                Fc::Condition {
+                    fid: rtok("NOT ", "rating"),
+                    op: Condition::NumericEqual(rtok("NOT rating !=# ", "5")),
+                },
+            ),
+            (
+                "name ~ \"^po.*ce$\"",
+                Fc::Condition {
+                    fid: rtok("", "name"),
+                    op: Condition::Regex(rtok("name ~ \"", "^po.*ce$")),
+                },
+            ),
+            (
+                "NOT name ~ po",
+                Fc::Condition {
+                    fid: rtok("NOT ", "name"),
+                    op: Condition::NotRegex(rtok("NOT name ~ ", "po")),
+                },
+            ),
+            (
+                "* EXISTS",
+                Fc::Condition { fid: rtok("", "*"), op: Condition::Exists },
+            ),
+            (
+                "price* NOT EXISTS",
+                Fc::Condition { fid: rtok("", "price*"), op: Condition::NotExists },
+            ),
+            (
+                "description IS EMPTY",
+                Fc::Condition { fid: rtok("", "description"), op: Condition::IsEmpty },
+            ),
+            (
+                "description IS NOT EMPTY",
+                Fc::Condition { fid: rtok("", "description"), op: Condition::IsNotEmpty },
+            ),
+            (
+                "NOT description IS EMPTY",
+                Fc::Condition { fid: rtok("NOT ", "description"), op: Condition::IsNotEmpty },
+            ),
+            (
+                "tags HAS >= 3 VALUES",
+                Fc::Condition {
+                    fid: rtok("", "tags"),
+                    op: Condition::ValuesCount(Box::new(Condition::GreaterThanOrEqual(rtok(
+                        "tags HAS >= ",
+                        "3",
+                    )))),
+                },
+            ),
+            (
+                "NOT tags HAS>=3 VALUES",
+                Fc::Condition {
+                    fid: rtok("NOT ", "tags"),
+                    op: Condition::ValuesCount(Box::new(Condition::LowerThan(rtok(
+                        "NOT tags HAS>=",
+                        "3",
+                    )))),
+                },
+            ),
+            (
+                "version SEMVER >= 1.9.0",
+                Fc::Condition {
+                    fid: rtok("", "version"),
+                    op: Condition::SemVer(Box::new(Condition::GreaterThanOrEqual(rtok(
+                        "version SEMVER >= ",
+                        "1.9.0",
+                    )))),
+                },
+            ),
+            (
+                "NOT version SEMVER<1.10.0-alpha",
+                Fc::Condition {
+                    fid: rtok("NOT ", "version"),
+                    op: Condition::SemVer(Box::new(Condition::GreaterThanOrEqual(rtok(
+                        "NOT version SEMVER<",
+                        "1.10.0-alpha",
+                    )))),
+                },
+            ),
+            (
+                "title LENGTH > 5",
+                Fc::Condition {
+                    fid: rtok("", "title"),
+                    op: Condition::WordsCount(Box::new(Condition::GreaterThan(rtok(
+                        "title LENGTH > ",
+                        "5",
+                    )))),
+                },
+            ),
+            (
+                "NOT title CHARS<=100",
+                Fc::Condition {
+                    fid: rtok("NOT ", "title"),
+                    op: Condition::CharsCount(Box::new(Condition::GreaterThan(rtok(
+                        "NOT title CHARS<=",
+                        "100",
+                    )))),
+                },
+            ),
+            (
+                "start < @end",
+                Fc::Condition {
+                    fid: rtok("", "start"),
+                    op: Condition::FieldComparison(Box::new(Condition::LowerThan(rtok(
+                        "start < @",
+                        "end",
+                    )))),
+                },
+            ),
+            (
+                "NOT start >=@'end date'",
+                Fc::Condition {
+                    fid: rtok("NOT ", "start"),
+                    op: Condition::FieldComparison(Box::new(Condition::LowerThan(rtok(
+                        "NOT start >=@'",
+                        "end date",
+                    )))),
+                },
+            ),
+            (
+                "tags[0] = primary",
+                Fc::Condition {
+                    fid: rtok("", "tags"),
+                    op: Condition::Position {
+                        index: rtok("tags[", "0"),
+                        comparison: Box::new(Condition::Equal(rtok("tags[0] = ", "primary"))),
+                    },
+                },
+            ),
+            (
+                "NOT 'release dates' [12]< 2000",
+                Fc::Condition {
+                    fid: rtok("NOT '", "release dates"),
+                    op: Condition::Position {
+                        index: rtok("NOT 'release dates' [", "12"),
+                        comparison: Box::new(Condition::GreaterThanOrEqual(rtok(
+                            "NOT 'release dates' [12]< ",
+                            "2000",
+                        ))),
+                    },
+                },
+            ),
+            (
+                "_geoDistance(48.8, 2.3) < 5000",
+                Fc::Condition {
+                    fid: rtok("", "_geoDistance"),
+                    op: Condition::GeoDistance {
+                        point: [rtok("_geoDistance(", "48.8"), rtok("_geoDistance(48.8, ", "2.3")],
+                        comparison: Box::new(Condition::LowerThan(rtok(
+                            "_geoDistance(48.8, 2.3) < ",
+                            "5000",
+                        ))),
+                    },
+                },
+            ),
+            (
+                "NOT _geoDistance( 1,2 )>=3",
+                Fc::Condition {
+                    fid: rtok("NOT ", "_geoDistance"),
+                    op: Condition::GeoDistance {
+                        point: [rtok("NOT _geoDistance( ", "1"), rtok("NOT _geoDistance( 1,", "2")],
+                        comparison: Box::new(Condition::LowerThan(rtok(
+                            "NOT _geoDistance( 1,2 )>=",
+                            "3",
+                        ))),
+                    },
+                },
+            ),
+            (
+                "start = '@end'",
+                Fc::Condition {
+                    fid: rtok("", "start"),
+                    op: Condition::Equal(rtok("start = '", "@end")),
+                },
+            ),
+            (
+                "subscribers EXISTS",
+                Fc::Condition { fid: rtok("", "subscribers"), op: Condition::Exists },
+            ),
+            (
+                "subscribers NOT EXISTS",
+                Fc::Condition { fid: rtok("", "subscribers"), op: Condition::NotExists },
+            ),
+            (
+                "NOT subscribers EXISTS",
+                Fc::Condition { fid: rtok("NOT ", "subscribers"), op: Condition::NotExists },
+            ),
+            (
+                "NOT subscribers NOT EXISTS",
+                Fc::Condition { fid: rtok("NOT ", "subscribers"), op: Condition::Exists },
+            ),
+            (
+                "_geo EXISTS",
+                Fc::Condition { fid: rtok("", "_geo"), op: Condition::Exists },
+            ),
+            (
+                "colour IN [green, 'dark blue']",
+                Fc::Condition {
+                    fid: rtok("", "colour"),
+                    op: Condition::In(vec![
+                        rtok("colour IN [", "green"),
+                        rtok("colour IN [green, '", "dark blue"),
+                    ]),
+                },
+            ),
+            (
+                "colour IN[green,blue,]",
+                Fc::Condition {
+                    fid: rtok("", "colour"),
+                    op: Condition::In(vec![
+                        rtok("colour IN[", "green"),
+                        rtok("colour IN[green,", "blue"),
+                    ]),
+                },
+            ),
+            ("colour IN []", Fc::Condition { fid: rtok("", "colour"), op: Condition::In(vec![]) }),
+            (
+                "colour NOT IN [green]",
+                Fc::Condition {
+                    fid: rtok("", "colour"),
+                    op: Condition::NotIn(vec![rtok("colour NOT IN [", "green")]),
+                },
+            ),
+            (
+                "NOT colour IN [green]",
+                Fc::Condition {
+                    fid: rtok("NOT ", "colour"),
+                    op: Condition::NotIn(vec![rtok("NOT colour IN [", "green")]),
+                },
+            ),
+            (
+                "price IN_BUCKETS [0, 10,100]",
+                Fc::Condition {
+                    fid: rtok("", "price"),
+                    op: Condition::InBuckets(vec![
+                        rtok("price IN_BUCKETS [", "0"),
+                        rtok("price IN_BUCKETS [0, ", "10"),
+                        rtok("price IN_BUCKETS [0, 10,", "100"),
+                    ]),
+                },
+            ),
+            (
+                "NOT price IN_BUCKETS[0, 10, 100]",
+                Fc::Condition {
+                    fid: rtok("NOT ", "price"),
+                    op: Condition::NotBetween {
+                        from: rtok("NOT price IN_BUCKETS[", "0"),
+                        to: rtok("NOT price IN_BUCKETS[0, 10, ", "100"),
+                    },
+                },
+            ),
+            (
+                "_geoRadius(12, 13, 14)",
+                Fc::GeoLowerThan {
+                    point: [rtok("_geoRadius(", "12"), rtok("_geoRadius(12, ", "13")],
+                    radius: rtok("_geoRadius(12, 13, ", "14"),
+                },
+            ),
+            (
+                "_geoRadius([12, 13], 14)",
+                Fc::GeoLowerThan {
+                    point: [rtok("_geoRadius([", "12"), rtok("_geoRadius([12, ", "13")],
+                    radius: rtok("_geoRadius([12, 13], ", "14"),
+                },
+            ),
+            (
+                "NOT _geoRadius(12, 13, 14)",
+                Fc::GeoGreaterThan {
+                    point: [rtok("NOT _geoRadius(", "12"), rtok("NOT _geoRadius(12, ", "13")],
+                    radius: rtok("NOT _geoRadius(12, 13, ", "14"),
+                },
+            ),
+            (
+                "_geoPolygon(1, 2, 3, 4, 5, 6)",
+                Fc::GeoInsidePolygon {
+                    points: vec![
+                        [rtok("_geoPolygon(", "1"), rtok("_geoPolygon(1, ", "2")],
+                        [rtok("_geoPolygon(1, 2, ", "3"), rtok("_geoPolygon(1, 2, 3, ", "4")],
+                        [rtok("_geoPolygon(1, 2, 3, 4, ", "5"), rtok("_geoPolygon(1, 2, 3, 4, 5, ", "6")],
+                    ],
+                },
+            ),
+            (
+                "NOT _geoPolygon(1, 2, 3, 4, 5, 6)",
+                Fc::GeoOutsidePolygon {
+                    points: vec![
+                        [rtok("NOT _geoPolygon(", "1"), rtok("NOT _geoPolygon(1, ", "2")],
+                        [rtok("NOT _geoPolygon(1, 2, ", "3"), rtok("NOT _geoPolygon(1, 2, 3, ", "4")],
+                        [rtok("NOT _geoPolygon(1, 2, 3, 4, ", "5"), rtok("NOT _geoPolygon(1, 2, 3, 4, 5, ", "6")],
+                    ],
+                },
+            ),
+            (
+                "_geoRing(12, 13, 10, 100)",
+                Fc::And(
+                    Fc::GeoLowerThan {
+                        point: [rtok("_geoRing(", "12"), rtok("_geoRing(12, ", "13")],
+                        radius: rtok("_geoRing(12, 13, 10, ", "100"),
+                    }
+                    .into(),
+                    Fc::GeoGreaterThan {
+                        point: [rtok("_geoRing(", "12"), rtok("_geoRing(12, ", "13")],
+                        radius: rtok("_geoRing(12, 13, ", "10"),
+                    }
+                    .into(),
+                ),
+            ),
+            // test simple `or` and `and`
+            (
+                "channel = ponce AND 'dog race' != 'bernese mountain'",
+                Fc::And(
+                    Fc::Condition {
+                        fid: rtok("", "channel"),
+                        op: Condition::Equal(rtok("channel = ", "ponce")),
+                    }
+                    .into(),
+                    Fc::Condition {
+                        fid: rtok("channel = ponce AND '", "dog race"),
+                        op: Condition::NotEqual(rtok(
+                            "channel = ponce AND 'dog race' != '",
+                            "bernese mountain",
+                        )),
+                    }
+                    .into(),
+                ),
+            ),
+            (
+                "channel = ponce OR 'dog race' != 'bernese mountain'",
+                Fc::Or(
+                    Fc::Condition {
+                        fid: rtok("", "channel"),
+                        op: Condition::Equal(rtok("channel = ", "ponce")),
+                    }
+                    .into(),
+                    Fc::Condition {
+                        fid: rtok("channel = ponce OR '", "dog race"),
+                        op: Condition::NotEqual(rtok(
+                            "channel = ponce OR 'dog race' != '",
+                            "bernese mountain",
+                        )),
+                    }
+                    .into(),
+                ),
+            ),
             (
                 "channel = ponce AND 'dog race' != 'bernese mountain' OR subscribers > 1000",
                 Fc::Or(
@@ -580,20 +1779,52 @@ pub mod tests {
             // simple test
             ("channel = Ponce = 12", "Found unexpected characters at the end of the filter: `= 12`. You probably forgot an `OR` or an `AND` rule."),
             ("channel =    ", "Was expecting a value but instead got nothing."),
+            ("start < @", "Was expecting a value but instead got nothing."),
             ("channel = 🐻", "Was expecting a value but instead got `🐻`."),
             ("channel = 🐻 AND followers < 100", "Was expecting a value but instead got `🐻`."),
-            ("OR", "Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `TO` or `_geoRadius` at `OR`."),
-            ("AND", "Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `TO` or `_geoRadius` at `AND`."),
-            ("channel Ponce", "Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `TO` or `_geoRadius` at `channel Ponce`."),
-            ("channel = Ponce OR", "Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `TO` or `_geoRadius` but instead got nothing."),
+            ("OR", "Was expecting an operation `=`, `!=`, `=#`, `!=#`, `>=`, `>`, `<=`, `<`, `~`, `IN`, `NOT IN`, `IN_BUCKETS`, `TO`, `BETWEEN`, `EXISTS`, `NOT EXISTS`, `IS EMPTY`, `IS NOT EMPTY`, `HAS`, `SEMVER`, `LENGTH`, `CHARS`, `ANY`, `ALL`, `_geoRadius`, `_geoPolygon`, `_geoRing` or `_geoDistance` at `OR`."),
+            ("AND", "Was expecting an operation `=`, `!=`, `=#`, `!=#`, `>=`, `>`, `<=`, `<`, `~`, `IN`, `NOT IN`, `IN_BUCKETS`, `TO`, `BETWEEN`, `EXISTS`, `NOT EXISTS`, `IS EMPTY`, `IS NOT EMPTY`, `HAS`, `SEMVER`, `LENGTH`, `CHARS`, `ANY`, `ALL`, `_geoRadius`, `_geoPolygon`, `_geoRing` or `_geoDistance` at `AND`."),
+            ("subscribers 1 TO5", "Was expecting an operation `=`, `!=`, `=#`, `!=#`, `>=`, `>`, `<=`, `<`, `~`, `IN`, `NOT IN`, `IN_BUCKETS`, `TO`, `BETWEEN`, `EXISTS`, `NOT EXISTS`, `IS EMPTY`, `IS NOT EMPTY`, `HAS`, `SEMVER`, `LENGTH`, `CHARS`, `ANY`, `ALL`, `_geoRadius`, `_geoPolygon`, `_geoRing` or `_geoDistance` at `subscribers 1 TO5`."),
+            ("channel Ponce", "Was expecting an operation `=`, `!=`, `=#`, `!=#`, `>=`, `>`, `<=`, `<`, `~`, `IN`, `NOT IN`, `IN_BUCKETS`, `TO`, `BETWEEN`, `EXISTS`, `NOT EXISTS`, `IS EMPTY`, `IS NOT EMPTY`, `HAS`, `SEMVER`, `LENGTH`, `CHARS`, `ANY`, `ALL`, `_geoRadius`, `_geoPolygon`, `_geoRing` or `_geoDistance` at `channel Ponce`."),
+            ("channel = Ponce OR", "Was expecting an operation `=`, `!=`, `=#`, `!=#`, `>=`, `>`, `<=`, `<`, `~`, `IN`, `NOT IN`, `IN_BUCKETS`, `TO`, `BETWEEN`, `EXISTS`, `NOT EXISTS`, `IS EMPTY`, `IS NOT EMPTY`, `HAS`, `SEMVER`, `LENGTH`, `CHARS`, `ANY`, `ALL`, `_geoRadius`, `_geoPolygon`, `_geoRing` or `_geoDistance` but instead got nothing."),
+            ("colour IN [green, blue", "Expression `colour IN [green, blue` is missing the following closing delimiter: `]`."),
+            ("colour NOT IN [green", "Expression `colour NOT IN [green` is missing the following closing delimiter: `]`."),
+            ("price IN_BUCKETS [0, 10", "Expression `price IN_BUCKETS [0, 10` is missing the following closing delimiter: `]`."),
+            ("price IN_BUCKETS [10]", "The `IN_BUCKETS` operator expects at least two edges: `price IN_BUCKETS [0, 10, 100]`."),
+            ("price IN_BUCKETS []", "The `IN_BUCKETS` operator expects at least two edges: `price IN_BUCKETS [0, 10, 100]`."),
             ("_geoRadius", "The `_geoRadius` filter expects three arguments: `_geoRadius(latitude, longitude, radius)`."),
             ("_geoRadius = 12", "The `_geoRadius` filter expects three arguments: `_geoRadius(latitude, longitude, radius)`."),
+            ("_geoRadius([12, 13])", "The `_geoRadius` filter expects three arguments: `_geoRadius(latitude, longitude, radius)`."),
+            ("_geoRadius([12, 13, 14], 15)", "The `_geoRadius` filter expects three arguments: `_geoRadius(latitude, longitude, radius)`."),
+            ("_geoRadius([12], 13)", "The `_geoRadius` filter expects three arguments: `_geoRadius(latitude, longitude, radius)`."),
+            ("_geoRadius()", "The `_geoRadius` filter expects three arguments: `_geoRadius(latitude, longitude, radius)`, but got 0.\n1:13 _geoRadius()"),
+            ("_geoRadius(12, 13)", "The `_geoRadius` filter expects three arguments: `_geoRadius(latitude, longitude, radius)`, but got 2."),
+            ("_geoRadius(1, 2, 3, 4)", "The `_geoRadius` filter expects three arguments: `_geoRadius(latitude, longitude, radius)`, but got 4.\n1:23 _geoRadius(1, 2, 3, 4)"),
+            ("a = 1 AND _geoRadius(1, 2, 3, 4, 5)", "The `_geoRadius` filter expects three arguments: `_geoRadius(latitude, longitude, radius)`, but got 5.\n11:36 a = 1 AND _geoRadius(1, 2, 3, 4, 5)"),
+            ("_geoPolygon(1, 2, 3, 4)", "The `_geoPolygon` filter expects at least three points: `_geoPolygon(latitude1, longitude1, latitude2, longitude2, latitude3, longitude3, ...)`."),
+            ("_geoPolygon(1, 2, 3, 4, 5, 6, 7)", "The `_geoPolygon` filter expects at least three points: `_geoPolygon(latitude1, longitude1, latitude2, longitude2, latitude3, longitude3, ...)`."),
+            ("_geoRing(1, 2, 3)", "The `_geoRing` filter expects four arguments: `_geoRing(latitude, longitude, innerRadius, outerRadius)`."),
+            ("_geoRing(1, 2, 3, 4, 5)", "The `_geoRing` filter expects four arguments: `_geoRing(latitude, longitude, innerRadius, outerRadius)`."),
+            ("_geoRing(1, 2, 100, 10)", "The radii of the `_geoRing` filter must be positive and the inner radius must be lower than the outer radius: `_geoRing(1, 2, 100, 10)`."),
+            ("_geoRing(1, 2, 10, 10)", "The radii of the `_geoRing` filter must be positive and the inner radius must be lower than the outer radius: `_geoRing(1, 2, 10, 10)`."),
+            ("_geoRing(1, 2, -10, 10)", "The radii of the `_geoRing` filter must be positive and the inner radius must be lower than the outer radius: `_geoRing(1, 2, -10, 10)`."),
+            ("_geoPolygon = 12", "The `_geoPolygon` filter expects at least three points: `_geoPolygon(latitude1, longitude1, latitude2, longitude2, latitude3, longitude3, ...)`."),
+            ("_geoDistance(12) < 13", "The `_geoDistance` filter expects two arguments followed by a comparison: `_geoDistance(latitude, longitude) < distance`, the comparison can be `<`, `<=`, `>` or `>=`.\n1:22 _geoDistance(12) < 13"),
+            ("_geoDistance(12, 13) = 14", "The `_geoDistance` filter expects two arguments followed by a comparison"),
+            ("_geoDistance(12, 13)", "The `_geoDistance` filter expects two arguments followed by a comparison"),
             ("_geoPoint(12, 13, 14)", "`_geoPoint` is a reserved keyword and thus can't be used as a filter expression. Use the `_geoRadius(latitude, longitude, distance) built-in rule to filter on `_geo` coordinates."),
             ("position <= _geoPoint(12, 13, 14)", "`_geoPoint` is a reserved keyword and thus can't be used as a filter expression. Use the `_geoRadius(latitude, longitude, distance) built-in rule to filter on `_geo` coordinates."),
             ("position <= _geoRadius(12, 13, 14)", "The `_geoRadius` filter is an operation and can't be used as a value."),
             ("channel = 'ponce", "Expression `\\'ponce` is missing the following closing delimiter: `'`."),
             ("channel = \"ponce", "Expression `\\\"ponce` is missing the following closing delimiter: `\"`."),
+            ("`release date = 2022", "Expression ``release date = 2022` is missing the following closing delimiter: ```."),
+            ("price * TO *", "The range `price * TO *` is unbounded on both sides, use `EXISTS` to match all the values."),
+            ("price BETWEEN * AND *", "The range `price BETWEEN * AND *` is unbounded on both sides, use `EXISTS` to match all the values."),
+            ("price BETWEEN 10 AND", "Was expecting a value but instead got nothing."),
             ("channel = mv OR (followers >= 1000", "Expression `(followers >= 1000` is missing the following closing delimiter: `)`."),
+            ("ALL(tags = a, tags = b", "Expression `ALL(tags = a, tags = b` is missing the following closing delimiter: `)`."),
+            ("ANY()", "Was expecting an operation `=`, `!=`, `=#`, `!=#`, `>=`, `>`, `<=`, `<`, `~`, `IN`, `NOT IN`, `IN_BUCKETS`, `TO`, `BETWEEN`, `EXISTS`, `NOT EXISTS`, `IS EMPTY`, `IS NOT EMPTY`, `HAS`, `SEMVER`, `LENGTH`, `CHARS`, `ANY`, `ALL`, `_geoRadius`, `_geoPolygon`, `_geoRing` or `_geoDistance` at `)`."),
+            ("ALL(tags = a,)", "Was expecting an operation `=`, `!=`, `=#`, `!=#`, `>=`, `>`, `<=`, `<`, `~`, `IN`, `NOT IN`, `IN_BUCKETS`, `TO`, `BETWEEN`, `EXISTS`, `NOT EXISTS`, `IS EMPTY`, `IS NOT EMPTY`, `HAS`, `SEMVER`, `LENGTH`, `CHARS`, `ANY`, `ALL`, `_geoRadius`, `_geoPolygon`, `_geoRing` or `_geoDistance` at `)`."),
             ("channel = mv OR followers >= 1000)", "Found unexpected characters at the end of the filter: `)`. You probably forgot an `OR` or an `AND` rule."),
         ];
 
@@ -611,6 +1842,576 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn optimize_order() {
+        // (filter, expected order of the optimized filter)
+        let test_case = [
+            ("a = 1", "a = 1"),
+            ("a > 1 AND b = 2", "b = 2 AND a > 1"),
+            (
+                "_geoRadius(1, 2, 3) AND a 1 TO 2 AND b = 2 AND c != 3",
+                "b = 2 AND c != 3 AND a 1 TO 2 AND _geoRadius(1, 2, 3)",
+            ),
+            // the OR operands are left untouched but their inner ANDs are optimized
+            ("a > 1 OR b = 2", "a > 1 OR b = 2"),
+            ("(a > 1 AND b = 2) OR c = 3", "(b = 2 AND a > 1) OR c = 3"),
+            // an OR costs as much as its most expensive operand
+            ("(a > 1 OR b = 2) AND c EXISTS", "c EXISTS AND (a > 1 OR b = 2)"),
+            // the two radii of a ring stay together
+            (
+                "_geoRing(1, 2, 3, 4) AND _geoRadius(5, 6, 7) AND a = 1",
+                "a = 1 AND _geoRing(1, 2, 3, 4) AND _geoRadius(5, 6, 7)",
+            ),
+        ];
+
+        for (input, expected) in test_case {
+            let optimized = FilterCondition::parse(input).unwrap().unwrap().optimize_order();
+            let expected = FilterCondition::parse(expected).unwrap().unwrap();
+            // the tokens only compare their fragments so we can compare two different inputs
+            assert_eq!(optimized, expected, "Filter `{}` was badly optimized.", input);
+        }
+    }
+
+    #[test]
+    fn quoted_wildcard() {
+        let parse = |input| FilterCondition::parse(input).unwrap().unwrap();
+        let operation = |input| match parse(input) {
+            FilterCondition::Condition { fid, op } => (fid, op),
+            condition => panic!("`{}` was parsed as {:?}", input, condition),
+        };
+
+        // a quoted `*` is a value like any other, not an unbounded side of the range
+        let (_, op) = operation("price \"*\" TO 10");
+        assert!(matches!(&op, Condition::Between { from, .. } if !from.is_wildcard()));
+        assert!(op.numeric_bounds().is_err());
+        let (_, op) = operation("price * TO 10");
+        assert!(matches!(&op, Condition::Between { from, .. } if from.is_wildcard()));
+        assert!(op.numeric_bounds().unwrap().is_some());
+        assert!(FilterCondition::parse("price \"*\" TO \"*\"").is_ok());
+
+        // nor all the attributes
+        let (fid, _) = operation("\"*\" EXISTS");
+        assert!(!fid.is_wildcard());
+        assert_eq!(fid.wildcard_prefix(), None);
+        let (fid, _) = operation("* EXISTS");
+        assert!(fid.is_wildcard());
+        let (fid, _) = operation("size* EXISTS");
+        assert_eq!(fid.wildcard_prefix(), Some("size"));
+        let (fid, _) = operation("'size*' EXISTS");
+        assert_eq!(fid.wildcard_prefix(), None);
+    }
+
+    #[test]
+    fn comments() {
+        let parse = |input| FilterCondition::parse(input).unwrap().unwrap();
+
+        let expected = parse("channel = a AND price > 10");
+        for input in [
+            "channel = a /* main channel */ AND price > 10",
+            "/* the channel */ channel = a AND price > 10 /* in euros */",
+            "channel = a # main channel\nAND price > 10",
+            "channel = a AND # the price\n price > 10 # in euros",
+            "channel/**/=/**/a/**/AND/**/price/**/>/**/10",
+        ] {
+            assert_eq!(parse(input), expected, "Filter `{}` was badly parsed.", input);
+        }
+        assert_eq!(parse("colour IN [red, /* not blue */ green]"), parse("colour IN [red, green]"));
+        assert_eq!(parse("NOT /* no */ a = 1"), parse("NOT a = 1"));
+
+        // the comments inside a quoted value are part of the value
+        let filter = parse("channel = \"#1 /* first */\" # the first one");
+        assert!(matches!(
+            filter,
+            FilterCondition::Condition { op: Condition::Equal(value), .. } if value.value() == "#1 /* first */"
+        ));
+
+        // a `#` within a word doesn't start a comment
+        assert!(FilterCondition::parse("tag = a#b AND price > 10").is_err());
+        assert!(FilterCondition::parse("language = C#").is_err());
+        assert_eq!(parse("language = \"C#\" # a quoted value"), parse("language = \"C#\""));
+
+        // a filter only made of comments is empty
+        assert_eq!(FilterCondition::parse("/* nothing */ # to see").unwrap(), None);
+        // an unterminated block comment isn't a comment
+        assert!(FilterCondition::parse("channel = a /* main channel").is_err());
+    }
+
+    #[test]
+    fn error_offsets() {
+        let test_case = [
+            // (filter, expected erroneous part)
+            ("channel = 🦀", "🦀"),
+            ("channel = Ponce AND 🐻 = bear", "🐻 = bear"),
+            ("colour IN [green", "colour IN [green"),
+            ("channel = Ponce OR", ""),
+        ];
+
+        for (input, expected) in test_case {
+            let error = FilterCondition::parse(input).unwrap_err();
+            let (start, end) = error.byte_offsets();
+            assert_eq!(&input[start..end], expected, "Filter `{}` failed.", input);
+        }
+
+        // the errors raised on a token, like an unknown attribute, point to this token
+        let filter = FilterCondition::parse("channel = Ponce AND dog = bernese").unwrap().unwrap();
+        let token = filter.token_at_depth(1).unwrap();
+        let error = token.as_external_error(std::fmt::Error);
+        assert_eq!(error.byte_offsets(), (20, 23));
+    }
+
+    #[test]
+    fn error_expression() {
+        let input = "channel = Ponce AND (dog = bernese";
+        let error = FilterCondition::parse(input).unwrap_err();
+        assert_eq!(error.expression(), input);
+        assert!(error.to_string().ends_with(input));
+
+        // the errors raised on a token outlive the filter
+        let input = String::from("channel = Ponce AND dog = bernese");
+        let error = match FilterCondition::parse(&input).unwrap().unwrap() {
+            FilterCondition::And(_, rhs) => match *rhs {
+                FilterCondition::Condition { fid, .. } => fid.as_external_error(std::fmt::Error),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        assert_eq!(error.expression(), input);
+        let (start, end) = error.byte_offsets();
+        assert_eq!(&error.expression()[start..end], "dog");
+    }
+
+    #[test]
+    fn geo_radius_point() {
+        let flat = FilterCondition::parse("_geoRadius(12, -13.5, 2000)").unwrap().unwrap();
+        let bracketed =
+            FilterCondition::parse("_geoRadius( [ 12,-13.5 ] , 2000)").unwrap().unwrap();
+        assert_eq!(flat, bracketed);
+
+        let flat = FilterCondition::parse("NOT _geoRadius(12, 13, 2000)").unwrap().unwrap();
+        let bracketed = FilterCondition::parse("NOT _geoRadius([12, 13], 2000)").unwrap().unwrap();
+        assert_eq!(flat, bracketed);
+
+        // several points are an `OR` of the radii around each of them
+        let points =
+            FilterCondition::parse("_geoRadius([12, 13], [-1, 2.5],2000)").unwrap().unwrap();
+        let ors = FilterCondition::parse("_geoRadius(12, 13, 2000) OR _geoRadius(-1, 2.5, 2000)")
+            .unwrap()
+            .unwrap();
+        assert_eq!(points, ors);
+
+        let points = FilterCondition::parse("NOT _geoRadius([12, 13], [-1, 2.5], [3, 4], 2000)")
+            .unwrap()
+            .unwrap();
+        let ands = FilterCondition::parse(
+            "NOT _geoRadius(12, 13, 2000) AND NOT _geoRadius(-1, 2.5, 2000) AND NOT _geoRadius(3, 4, 2000)",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(points, ands);
+
+        // a ring is the outer radius without the inner one
+        let ring = FilterCondition::parse("_geoRing(12, 13, 10, 100)").unwrap().unwrap();
+        let radii =
+            FilterCondition::parse("_geoRadius(12, 13, 100) AND NOT _geoRadius(12, 13, 10)")
+                .unwrap()
+                .unwrap();
+        assert_eq!(ring, radii);
+
+        let ring = FilterCondition::parse("NOT _geoRing(12, 13, 0, 100)").unwrap().unwrap();
+        let radii = FilterCondition::parse("NOT _geoRadius(12, 13, 100) OR _geoRadius(12, 13, 0)")
+            .unwrap()
+            .unwrap();
+        assert_eq!(ring, radii);
+
+        for input in ["_geoRadius([12, 13], [14, 15])", "_geoRadius([12, 13], 14, 15, 16)"] {
+            let error = FilterCondition::parse(input).unwrap_err();
+            assert!(matches!(error.kind(), ErrorKind::Geo), "{}", input);
+        }
+    }
+
+    #[test]
+    fn contains() {
+        use std::cell::Cell;
+
+        let test_case = [
+            ("channel = mv", false),
+            ("channel = mv OR subscribers 1 TO 10", false),
+            ("_geo EXISTS", false),
+            ("_geoRadius(12, 13, 14)", true),
+            ("NOT _geoRadius(12, 13, 14)", true),
+            ("channel = mv AND (subscribers > 10 OR _geoPolygon(1, 2, 3, 4, 5, 6))", true),
+            ("channel = mv AND NOT _geoPolygon(1, 2, 3, 4, 5, 6)", true),
+            ("channel = mv OR _geoDistance(1, 2) < 3", true),
+        ];
+        for (input, expected) in test_case {
+            let filter = FilterCondition::parse(input).unwrap().unwrap();
+            assert_eq!(filter.contains_geo(), expected, "Filter `{}` failed.", input);
+        }
+
+        let is_range = |op: &Condition| matches!(op, Condition::Between { .. });
+        let filter =
+            FilterCondition::parse("a = 1 OR (b 1 TO 2 AND _geoRadius(1, 2, 3))").unwrap().unwrap();
+        assert!(filter.contains_operator(is_range));
+        assert!(!filter.contains_operator(|op| matches!(op, Condition::Exists)));
+
+        // the exploration stops on the first match
+        let calls = Cell::new(0);
+        let filter = FilterCondition::parse("a EXISTS OR b = 1 OR c = 2").unwrap().unwrap();
+        assert!(filter.contains_operator(|op| {
+            calls.set(calls.get() + 1);
+            matches!(op, Condition::Exists)
+        }));
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn and_all_or_all() {
+        let parse = |input| FilterCondition::parse(input).unwrap().unwrap();
+
+        assert_eq!(FilterCondition::and_all(None), None);
+        assert_eq!(FilterCondition::or_all(Vec::new()), None);
+
+        let filter = parse("channel = mv");
+        assert_eq!(FilterCondition::and_all([filter.clone()]), Some(filter.clone()));
+        assert_eq!(FilterCondition::or_all([filter.clone()]), Some(filter));
+
+        let conditions = ["channel = mv", "subscribers > 10", "(a = 1 OR b = 2)"].map(parse);
+        assert_eq!(
+            FilterCondition::and_all(conditions.clone()),
+            Some(parse("channel = mv AND subscribers > 10 AND (a = 1 OR b = 2)"))
+        );
+        assert_eq!(
+            FilterCondition::or_all(conditions),
+            Some(parse("channel = mv OR subscribers > 10 OR (a = 1 OR b = 2)"))
+        );
+    }
+
+    #[test]
+    fn parse_partial() {
+        let parse = |input| FilterCondition::parse(input).unwrap();
+
+        // complete or empty filters
+        let (condition, error) = FilterCondition::parse_partial("channel = mv AND price > 10");
+        assert_eq!(condition, parse("channel = mv AND price > 10"));
+        assert!(error.is_none());
+        let (condition, error) = FilterCondition::parse_partial("  ");
+        assert!(condition.is_none());
+        assert!(error.is_none());
+
+        // the filters being written
+        let test_case = [
+            ("channel =", None, "Was expecting a value but instead got nothing."),
+            ("channel = mv AND", Some("channel = mv"), "Was expecting an operation"),
+            ("channel = mv AND price >", Some("channel = mv"), "Was expecting a value"),
+            (
+                "channel = mv AND price > 10 OR tit",
+                Some("channel = mv AND price > 10"),
+                "Was expecting an operation",
+            ),
+            ("channel = mv AND (price > 10 OR", Some("channel = mv"), "Was expecting an operation"),
+            (
+                "channel = mv AND price > 10 AND tag IN [red, blu",
+                Some("channel = mv AND price > 10"),
+                "Expression `tag IN [red, blu` is missing",
+            ),
+            (
+                "channel = mv AND title = 'new yo",
+                Some("channel = mv"),
+                "Expression `\\'new yo` is missing",
+            ),
+        ];
+        for (input, expected, error) in test_case {
+            let (condition, found) = FilterCondition::parse_partial(input);
+            assert_eq!(condition, expected.and_then(parse), "{}", input);
+            let found = found.unwrap().to_string();
+            assert!(found.starts_with(error), "{}: {}", input, found);
+        }
+
+        // a long filter is only parsed a few times
+        let input = format!("({}a = 1", "a = 1 AND ".repeat(4_000));
+        let (condition, error) = FilterCondition::parse_partial(&input);
+        assert!(condition.is_none());
+        assert!(error.is_some());
+        let input = format!("{}b =", "a = 1 AND ".repeat(4_000));
+        let (condition, error) = FilterCondition::parse_partial(&input);
+        let expected = format!("{}a = 1", "a = 1 AND ".repeat(3_999));
+        assert_eq!(condition, parse(&expected));
+        assert!(error.is_some());
+    }
+
+    #[test]
+    fn map_operators() {
+        let parse = |input| FilterCondition::parse(input).unwrap().unwrap();
+        let numeric = |fid, op| match op {
+            Condition::Equal(value) => (fid, Condition::NumericEqual(value)),
+            Condition::NotEqual(value) => (fid, Condition::NumericNotEqual(value)),
+            op => (fid, op),
+        };
+
+        assert_eq!(
+            parse("a = 1 AND (b != 2 OR NOT c = 3) AND d > 4 AND _geoRadius(1, 2, 3)")
+                .map_operators(numeric),
+            parse("a =# 1 AND (b !=# 2 OR c !=# 3) AND d > 4 AND _geoRadius(1, 2, 3)")
+        );
+        assert_eq!(
+            parse("a = 1 OR b IN [1, 2]").map_operators(|fid, _| (fid, Condition::Exists)),
+            parse("a EXISTS OR b EXISTS")
+        );
+    }
+
+    #[test]
+    fn any_all() {
+        let parse = |input| FilterCondition::parse(input).unwrap().unwrap();
+
+        assert_eq!(parse("ALL(tags = a, tags = b)"), parse("tags = a AND tags = b"));
+        assert_eq!(parse("ANY(tags = a, tags = b, c > 1)"), parse("tags = a OR tags = b OR c > 1"));
+        assert_eq!(parse("ALL (tags = a)"), parse("tags = a"));
+        // the operands are whole expressions
+        assert_eq!(
+            parse("ALL(tags = a OR tags = c, NOT tags = b) AND d EXISTS"),
+            parse("(tags = a OR tags = c) AND NOT tags = b AND d EXISTS")
+        );
+        assert_eq!(parse("ANY(ALL(a = 1, b = 2), c = 3)"), parse("(a = 1 AND b = 2) OR c = 3"));
+        // they can be negated
+        assert_eq!(parse("NOT ALL(tags = a, tags = b)"), parse("tags != a OR tags != b"));
+        assert_eq!(parse("NOT ANY(tags = a, tags = b)"), parse("tags != a AND tags != b"));
+        // the keywords are still valid attribute names
+        assert_eq!(
+            parse("ANY = 1 AND ALLOWED = 2"),
+            FilterCondition::And(
+                FilterCondition::Condition {
+                    fid: rtok("", "ANY"),
+                    op: Condition::Equal(rtok("ANY = ", "1"))
+                }
+                .into(),
+                FilterCondition::Condition {
+                    fid: rtok("ANY = 1 AND ", "ALLOWED"),
+                    op: Condition::Equal(rtok("ANY = 1 AND ALLOWED = ", "2"))
+                }
+                .into(),
+            )
+        );
+    }
+
+    #[test]
+    fn swap_coordinates() {
+        let parse = |input| FilterCondition::parse(input).unwrap().unwrap();
+
+        let lng_lat =
+            parse("_geoRadius(13, 12, 14) AND NOT _geoRadius(3, 1, 2)").swap_coordinates();
+        assert_eq!(lng_lat, parse("_geoRadius(12, 13, 14) AND NOT _geoRadius(1, 3, 2)"));
+
+        let lng_lat = parse("_geoPolygon(2, 1, 4, 3, 6, 5) OR channel = 12").swap_coordinates();
+        assert_eq!(lng_lat, parse("_geoPolygon(1, 2, 3, 4, 5, 6) OR channel = 12"));
+
+        // swapping twice gives back the original filter
+        let lng_lat = parse("_geoDistance(2, 1) < 3").swap_coordinates();
+        assert_eq!(lng_lat, parse("_geoDistance(1, 2) < 3"));
+
+        let filter = parse("_geoRadius(1, 2, 3) OR NOT _geoPolygon(1, 2, 3, 4, 5, 6)");
+        assert_eq!(filter.clone().swap_coordinates().swap_coordinates(), filter);
+    }
+
+    #[test]
+    fn split_by_field() {
+        let parse = |input| FilterCondition::parse(input).unwrap().unwrap();
+        let split = |input, fields: &[&str]| parse(input).split_by_field(|f| fields.contains(&f));
+
+        assert_eq!(split("a = 1 AND b = 2", &["a"]), (Some(parse("a = 1")), Some(parse("b = 2"))));
+        assert_eq!(split("a = 1 AND b = 2", &["b"]), (Some(parse("b = 2")), Some(parse("a = 1"))));
+        assert_eq!(
+            split("a = 1 AND b = 2 AND c > 3", &["a", "c"]),
+            (Some(parse("a = 1 AND c > 3")), Some(parse("b = 2")))
+        );
+        assert_eq!(split("a = 1 AND b = 2", &["a", "b"]), (Some(parse("a = 1 AND b = 2")), None));
+        assert_eq!(split("a = 1 AND b = 2", &["c"]), (None, Some(parse("a = 1 AND b = 2"))));
+
+        // an `OR` is only split away whole
+        assert_eq!(
+            split("(a = 1 OR a = 2) AND (a = 3 OR b = 4)", &["a"]),
+            (Some(parse("a = 1 OR a = 2")), Some(parse("a = 3 OR b = 4")))
+        );
+        assert_eq!(split("a = 1 OR b = 2", &["a"]), (None, Some(parse("a = 1 OR b = 2"))));
+
+        // the geo filters use `_geo`, the field comparisons both attributes
+        assert_eq!(
+            split("_geoRadius(1, 2, 3) AND _geoDistance(1, 2) > 4 AND a = 1", &["_geo"]),
+            (Some(parse("_geoRadius(1, 2, 3) AND _geoDistance(1, 2) > 4")), Some(parse("a = 1")))
+        );
+        assert_eq!(
+            split("a > @b AND a = 1", &["a"]),
+            (Some(parse("a = 1")), Some(parse("a > @b")))
+        );
+    }
+
+    #[test]
+    fn canonicalize() {
+        let canonical = |input| FilterCondition::parse(input).unwrap().unwrap().canonicalize();
+
+        assert_eq!(canonical("a = 1 AND b = 2"), canonical("b = 2 AND a = 1"));
+        assert_eq!(canonical("a = 1 OR b = 2"), canonical("b = 2 OR a = 1"));
+        assert_eq!(
+            canonical("a = 1 AND b = 2 AND c = 3"),
+            canonical("c = 3 AND (a = 1 AND b = 2)")
+        );
+        assert_eq!(canonical("a IN [1, 2, 3]"), canonical("a IN [3, 1, 2]"));
+        assert_eq!(canonical("a NOT IN [1, 2]"), canonical("NOT a IN [2, 1]"));
+        assert_eq!(
+            canonical("(a = 1 OR _geoRadius(1, 2, 3)) AND b > 2"),
+            canonical("b > 2 AND (_geoRadius(1, 2, 3) OR a = 1)")
+        );
+        // the operations on the same attribute are ordered on the operation
+        assert_eq!(canonical("a > 1 AND a = 2"), canonical("a = 2 AND a > 1"));
+
+        // the operands are only sorted inside of an `AND` or an `OR`
+        assert_ne!(canonical("a = 1 AND b = 2 OR c = 3"), canonical("a = 1 AND (b = 2 OR c = 3)"));
+        assert_ne!(canonical("a = 1 AND b = 2"), canonical("a = 2 AND b = 1"));
+        assert_eq!(
+            canonical("b = 2 AND a = 1"),
+            FilterCondition::parse("a = 1 AND b = 2").unwrap().unwrap()
+        );
+    }
+
+    #[test]
+    fn hash_and_ord() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::collections::HashSet;
+
+        let condition = |input| match FilterCondition::parse(input).unwrap().unwrap() {
+            FilterCondition::Condition { op, .. } => op,
+            filter => panic!("{:?}", filter),
+        };
+        let hash = |condition: &Condition| {
+            let mut hasher = DefaultHasher::new();
+            condition.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        // the equal conditions hash equal wherever their values are in the filter
+        let lhs = condition("price > 10");
+        let rhs = condition("  price   >   10");
+        assert_eq!(lhs, rhs);
+        assert_eq!(hash(&lhs), hash(&rhs));
+        assert_eq!(lhs.cmp(&rhs), Ordering::Equal);
+        assert_eq!(hash(&condition("a IN [1, 2]")), hash(&condition("a IN [1,2]")));
+
+        // the values are compared as written, `NaN` is a value like any other
+        let values = ["NaN", "nan", "0.0", "-0.0", "0", "inf", "-inf", "1e308"];
+        let inputs: Vec<_> = values.iter().map(|value| format!("price < {}", value)).collect();
+        let mut sorted_inputs = inputs.clone();
+        sorted_inputs.sort();
+        let mut conditions: Vec<_> = inputs.iter().map(|input| condition(input)).collect();
+        conditions.sort();
+        let sorted: Vec<_> = sorted_inputs.iter().map(|input| condition(input)).collect();
+        assert_eq!(conditions, sorted);
+        assert_eq!(condition("price = NaN"), condition("price = NaN"));
+        assert_ne!(condition("price = 0.0"), condition("price = -0.0"));
+
+        let set: HashSet<_> = conditions.into_iter().chain(sorted).collect();
+        assert_eq!(set.len(), values.len());
+    }
+
+    #[test]
+    fn to_dnf() {
+        let parse = |input| FilterCondition::parse(input).unwrap().unwrap();
+        let dnf = |input| parse(input).to_dnf(16).unwrap();
+
+        assert_eq!(dnf("a = 1"), parse("a = 1"));
+        assert_eq!(dnf("a = 1 AND b = 2 OR c = 3"), parse("a = 1 AND b = 2 OR c = 3"));
+        assert_eq!(
+            dnf("a = 1 AND (b = 2 OR c = 3)"),
+            parse("(a = 1 AND b = 2) OR (a = 1 AND c = 3)")
+        );
+        assert_eq!(
+            dnf("(a = 1 OR b = 2) AND (c = 3 OR d = 4)"),
+            parse("a = 1 AND c = 3 OR a = 1 AND d = 4 OR b = 2 AND c = 3 OR b = 2 AND d = 4")
+        );
+        // the negations are pushed down to the operations
+        assert_eq!(dnf("NOT (a = 1 AND (b = 2 OR c = 3))"), parse("a != 1 OR (b != 2 AND c != 3)"));
+        assert_eq!(
+            dnf("NOT (a = 1 OR _geoRadius(1, 2, 3)) AND d 1 TO 2"),
+            parse("a != 1 AND NOT _geoRadius(1, 2, 3) AND d 1 TO 2")
+        );
+
+        // (a OR b) AND (c OR d) AND (e OR f) has eight terms
+        let filter = parse("(a = 1 OR b = 2) AND (c = 3 OR d = 4) AND (e = 5 OR f = 6)");
+        assert_eq!(filter.clone().to_dnf(8).unwrap(), filter.clone().to_dnf(100).unwrap());
+        let error = filter.to_dnf(7).unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::TooManyTerms(7)));
+        assert_eq!(
+            error.to_string(),
+            "The filter has more than 7 terms once rewritten as an `OR` of `AND`s.\n1:59 (a = 1 OR b = 2) AND (c = 3 OR d = 4) AND (e = 5 OR f = 6)"
+        );
+    }
+
+    #[test]
+    fn is_trivially_empty() {
+        let test_case = [
+            // contradictions
+            ("tag = red AND tag != red", true),
+            ("tag = Red AND NOT tag = 'red '", true),
+            ("tag EXISTS AND tag NOT EXISTS", true),
+            ("tag IS EMPTY AND tag IS NOT EMPTY", true),
+            ("price =# 10 AND price !=# 10", true),
+            ("name ~ '^po' AND NOT name ~ '^po'", true),
+            ("tag = red AND tag NOT IN [blue, red]", true),
+            ("tag IN [red, blue] AND tag NOT IN [blue, red, green]", true),
+            ("a = 1 AND (tag = red AND b = 2) AND tag != red", true),
+            ("_geoRadius(1, 2, 3) AND NOT _geoRadius(1, 2, 3)", true),
+            ("_geoPolygon(1, 2, 3, 4, 5, 6) AND NOT _geoPolygon(1, 2, 3, 4, 5, 6)", true),
+            ("_geoRadius(1, 2, 100) AND NOT _geoRadius(1.0, 2.00, 1e2)", true),
+            ("_geoRadius(1, 2, 0)", true),
+            ("_geoRadius(1, 2, -10) OR _geoRadius(1, 2, 0.0)", true),
+            ("tag = red AND _geoRadius(1, 2, 0)", true),
+            ("(tag = red AND tag != red) OR (a EXISTS AND a NOT EXISTS)", true),
+            // not provably empty
+            ("tag = red", false),
+            ("tag = red AND tag != blue", false),
+            ("tag = red AND color != red", false),
+            ("tag = red OR tag != red", false),
+            ("(tag = red AND tag != red) OR a = 1", false),
+            ("tag IN [red, blue] AND tag NOT IN [red]", false),
+            ("price = 1.0 AND price != 1", false),
+            ("_geoRadius(1, 2, 3) AND NOT _geoRadius(1, 2, 4)", false),
+            ("_geoRadius(1, 2, 100) AND NOT _geoRadius(1.0000001, 2, 100)", false),
+            ("_geoRadius(1, 2, 100) AND NOT _geoRadius(1, 2, 100.00001)", false),
+            ("_geoRadius(1, 2, 0.001)", false),
+            ("NOT _geoRadius(1, 2, 0)", false),
+            // a document can contain both a 3 and a 20
+            ("price > 10 AND price < 5", false),
+        ];
+        for (input, expected) in test_case {
+            let filter = FilterCondition::parse(input).unwrap().unwrap();
+            assert_eq!(filter.is_trivially_empty(), expected, "Filter `{}` failed.", input);
+        }
+    }
+
+    #[test]
+    fn list_values_limit() {
+        let values = |n| (0..n).map(|i| i.to_string()).collect::<Vec<_>>().join(", ");
+
+        let input = format!("id IN [{}]", values(MAX_LIST_VALUES));
+        let filter = FilterCondition::parse(&input).unwrap().unwrap();
+        assert!(matches!(
+            filter,
+            FilterCondition::Condition { op: Condition::In(v), .. } if v.len() == MAX_LIST_VALUES
+        ));
+
+        for input in [
+            format!("id IN [{}]", values(MAX_LIST_VALUES + 1)),
+            format!("id NOT IN [{},]", values(MAX_LIST_VALUES + 1)),
+        ] {
+            let error = FilterCondition::parse(&input).unwrap_err();
+            assert!(matches!(error.kind(), ErrorKind::TooManyValues));
+            assert!(error.to_string().starts_with(
+                "The list contains too many values, at most 10000 values are allowed."
+            ));
+            // the error points at the list
+            let (start, end) = error.byte_offsets();
+            assert_eq!(&input[start..end], &input[input.find('[').unwrap() + 1..]);
+        }
+    }
+
     #[test]
     fn depth() {
         let filter = FilterCondition::parse("account_ids=1 OR account_ids=2 OR account_ids=3 OR account_ids=4 OR account_ids=5 OR account_ids=6").unwrap().unwrap();
@@ -1,12 +1,12 @@
 use nom::branch::alt;
-use nom::bytes::complete::{take_till, take_while, take_while1};
-use nom::character::complete::{char, multispace0};
+use nom::bytes::complete::{take_till, take_while1};
+use nom::character::complete::char;
 use nom::combinator::cut;
 use nom::sequence::{delimited, terminated};
 use nom::{InputIter, InputLength, InputTake, Slice};
 
 use crate::error::NomErrorExt;
-use crate::{parse_geo_point, parse_geo_radius, Error, ErrorKind, IResult, Span, Token};
+use crate::{blank, parse_geo_point, parse_geo_radius, Error, ErrorKind, IResult, Span, Token};
 
 /// This function goes through all characters in the [Span] if it finds any escaped character (`\`).
 /// It generates a new string with all `\` removed from the [Span].
@@ -19,7 +19,7 @@ fn unescape(buf: Span, char_to_escape: char) -> String {
 fn quoted_by(quote: char, input: Span) -> IResult<Token> {
     // empty fields / values are valid in json
     if input.is_empty() {
-        return Ok((input.slice(input.input_len()..), input.into()));
+        return Ok((input.slice(input.input_len()..), Token::new_quoted(input, None)));
     }
 
     let mut escaped = false;
@@ -28,7 +28,7 @@ fn quoted_by(quote: char, input: Span) -> IResult<Token> {
     while let Some((idx, c)) = i.next() {
         if c == quote {
             let (rem, output) = input.take_split(idx);
-            return Ok((rem, Token::new(output, escaped.then(|| unescape(output, quote)))));
+            return Ok((rem, Token::new_quoted(output, escaped.then(|| unescape(output, quote)))));
         } else if c == '\\' {
             if let Some((_, c)) = i.next() {
                 escaped |= c == quote;
@@ -44,14 +44,14 @@ fn quoted_by(quote: char, input: Span) -> IResult<Token> {
 
     Ok((
         input.slice(input.input_len()..),
-        Token::new(input, escaped.then(|| unescape(input, quote))),
+        Token::new_quoted(input, escaped.then(|| unescape(input, quote))),
     ))
 }
 
-/// value          = WS* ~ ( word | singleQuoted | doubleQuoted) ~ WS*
+/// value          = WS* ~ ( word | singleQuoted | doubleQuoted | backQuoted) ~ WS*
 pub fn parse_value<'a>(input: Span<'a>) -> IResult<Token<'a>> {
-    // to get better diagnostic message we are going to strip the left whitespaces from the input right now
-    let (input, _) = take_while(char::is_whitespace)(input)?;
+    // to get better diagnostic message we are going to strip the left whitespaces and comments from the input right now
+    let (input, _) = blank(input)?;
 
     // then, we want to check if the user is misusing a geo expression
     // This expression can’t finish without error.
@@ -71,7 +71,7 @@ pub fn parse_value<'a>(input: Span<'a>) -> IResult<Token<'a>> {
         _ => (),
     }
 
-    // word           = (alphanumeric | _ | - | .)+
+    // word           = (alphanumeric | _ | - | . | : | + | * | % | $ | € | £ | ¥)+
     let word = |input: Span<'a>| -> IResult<Token<'a>> {
         take_while1(is_value_component)(input).map(|(s, t)| (s, t.into()))
     };
@@ -89,9 +89,10 @@ pub fn parse_value<'a>(input: Span<'a>) -> IResult<Token<'a>> {
         alt((
             delimited(char('\''), cut(|input| quoted_by('\'', input)), cut(char('\''))),
             delimited(char('"'), cut(|input| quoted_by('"', input)), cut(char('"'))),
+            delimited(char('`'), cut(|input| quoted_by('`', input)), cut(char('`'))),
             word,
         )),
-        multispace0,
+        blank,
     )(input)
     // if we found nothing in the alt it means the user specified something that was not recognized as a value
     .map_err(|e: nom::Err<Error>| {
@@ -110,8 +111,10 @@ pub fn parse_value<'a>(input: Span<'a>) -> IResult<Token<'a>> {
     })
 }
 
-fn is_value_component(c: char) -> bool {
-    c.is_alphanumeric() || ['_', '-', '.'].contains(&c)
+pub(crate) fn is_value_component(c: char) -> bool {
+    c.is_alphanumeric()
+        || ['_', '-', '.', ':', '+', '*', '%'].contains(&c)
+        || crate::CURRENCY_SYMBOLS.contains(&c)
 }
 
 fn is_syntax_component(c: char) -> bool {
@@ -148,6 +151,7 @@ pub mod test {
             ("\"cha'nnel\"", rtok("'", "cha'nnel")),
             ("I'm tamo", rtok("'m tamo", "I")),
             ("\"I'm \\\"super\\\" tamo\"", rtok("\"", "I'm \\\"super\\\" tamo")),
+            ("`release date`", rtok("`", "release date")),
         ];
 
         for (input, expected) in test_case {
@@ -224,6 +228,9 @@ pub mod test {
             ("parens(", "parens", false),
             ("parens)", "parens", false),
             ("not!", "not", false),
+            ("2024-01-01T00:00:00+01:00", "2024-01-01T00:00:00+01:00", false),
+            ("*", "*", false),
+            ("price*", "price*", false),
             ("    channel", "channel", false),
             ("channel     ", "channel", false),
             ("    channel     ", "channel", false),
@@ -250,6 +257,10 @@ pub mod test {
             (r#"'Hello \\\'world\\\''"#, r#"Hello \\'world\\'"#, true),
             (r#"'I\'m "super" tamo'"#, r#"I'm "super" tamo"#, true),
             (r#"'\'\''"#, r#"''"#, true),
+            // with backquote
+            ("`release date`", "release date", false),
+            ("`it's \"quoted\"`", "it's \"quoted\"", false),
+            (r#"`back\`quote`"#, "back`quote", true),
         ];
 
         for (input, expected, escaped) in test_case {
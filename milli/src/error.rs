@@ -18,6 +18,9 @@ pub fn is_reserved_keyword(keyword: &str) -> bool {
 #[derive(Debug)]
 pub enum Error {
     InternalError(InternalError),
+    /// The operation was stopped by its caller before its end, i.e. the evaluation
+    /// of a filter with a stop flag, see [`crate::Filter::with_stop_flag`].
+    Interrupted,
     IoError(io::Error),
     UserError(UserError),
 }
@@ -59,7 +62,7 @@ pub enum UserError {
     InvalidDocumentId { document_id: Value },
     InvalidFacetsDistribution { invalid_facets_name: BTreeSet<String> },
     InvalidGeoField { document_id: Value, object: Value },
-    InvalidFilter(String),
+    InvalidFilter { code: &'static str, message: String, byte_offsets: Option<(usize, usize)> },
     InvalidSortableAttribute { field: String, valid_fields: BTreeSet<String> },
     SortRankingRuleMissing,
     InvalidStoreFile,
@@ -167,6 +170,7 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Self::InternalError(error) => write!(f, "internal: {}.", error),
+            Self::Interrupted => f.write_str("The operation was interrupted."),
             Self::IoError(error) => error.fmt(f),
             Self::UserError(error) => error.fmt(f),
         }
@@ -205,7 +209,7 @@ impl StdError for InternalError {}
 impl fmt::Display for UserError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::InvalidFilter(error) => f.write_str(error),
+            Self::InvalidFilter { message, .. } => f.write_str(message),
             Self::AttributeLimitReached => f.write_str("A document cannot contain more than 65,535 fields."),
             Self::CriterionError(error) => write!(f, "{}", error),
             Self::DocumentLimitReached => f.write_str("Maximum number of documents reached."),
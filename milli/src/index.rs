@@ -491,9 +491,20 @@ impl Index {
             .unwrap_or_default())
     }
 
-    /// Identical to `filterable_fields`, but returns ids instead.
+    /// Returns the filterable fields names, a `*` makes all the fields of the index filterable
+    /// and is replaced by the names of all the fields known by the fields ids map.
+    pub fn resolved_filterable_fields(&self, rtxn: &RoTxn) -> Result<HashSet<String>> {
+        let mut fields = self.filterable_fields(rtxn)?;
+        if fields.remove("*") {
+            let fields_ids_map = self.fields_ids_map(rtxn)?;
+            fields.extend(fields_ids_map.names().map(String::from));
+        }
+        Ok(fields)
+    }
+
+    /// Identical to `resolved_filterable_fields`, but returns ids instead.
     pub fn filterable_fields_ids(&self, rtxn: &RoTxn) -> Result<HashSet<FieldId>> {
-        let fields = self.filterable_fields(rtxn)?;
+        let fields = self.resolved_filterable_fields(rtxn)?;
         let fields_ids_map = self.fields_ids_map(rtxn)?;
 
         let mut fields_ids = HashSet::new();
@@ -543,7 +554,7 @@ impl Index {
     ///
     /// Faceted fields are the union of all the filterable, sortable, distinct, and Asc/Desc fields.
     pub fn faceted_fields(&self, rtxn: &RoTxn) -> Result<HashSet<String>> {
-        let filterable_fields = self.filterable_fields(rtxn)?;
+        let filterable_fields = self.resolved_filterable_fields(rtxn)?;
         let sortable_fields = self.sortable_fields(rtxn)?;
         let distinct_field = self.distinct_field(rtxn)?;
         let asc_desc_fields =
@@ -887,8 +898,13 @@ pub(crate) mod tests {
         /// Creates a temporary index, with a default `4096 * 100` size. This should be enough for
         /// most tests.
         pub fn new() -> Self {
+            Self::new_with_map_size(100 * 4096)
+        }
+
+        /// Creates a temporary index with the given map size.
+        pub fn new_with_map_size(map_size: usize) -> Self {
             let mut options = EnvOpenOptions::new();
-            options.map_size(100 * 4096);
+            options.map_size(map_size);
             let _tempdir = TempDir::new_in(".").unwrap();
             let inner = Index::new(options, _tempdir.path()).unwrap();
             Self { inner, _tempdir }
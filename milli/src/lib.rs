@@ -36,7 +36,11 @@ pub use self::heed_codec::{
     RoaringBitmapLenCodec, StrBEU32Codec, StrStrU8Codec,
 };
 pub use self::index::Index;
-pub use self::search::{FacetDistribution, Filter, MatchingWords, Search, SearchResult};
+pub use self::search::{
+    CompiledFilter, FacetDistribution, Filter, FilterError, FilterWarning, Highlight,
+    MatchingWords, OperationStats, PrunedFilter, Search, SearchResult, UnresolvedFieldPolicy,
+    EVALUATION_LOG_TARGET, INVALID_FILTER_CODE,
+};
 
 pub type Result<T> = std::result::Result<T, error::Error>;
 
@@ -164,7 +168,10 @@ where
 }
 
 /// Return the distance between two points in meters. Each points are composed of two f64,
-/// one latitude and one longitude.
+/// one latitude and one longitude, in degrees.
+///
+/// It is the great-circle distance on a sphere of the mean earth radius (haversine formula),
+/// it differs from the distance on the WGS 84 ellipsoid by at most about 0.5%.
 pub fn distance_between_two_points(a: &[f64; 2], b: &[f64; 2]) -> f64 {
     let a = geoutils::Location::new(a[0], a[1]);
     let b = geoutils::Location::new(b[0], b[1]);
@@ -183,12 +190,52 @@ pub fn lat_lng_to_xyz(coord: &[f64; 2]) -> [f64; 3] {
     [x, y, z]
 }
 
+/// Extracts the latitude and the longitude of a `_geo` field, the coordinates can be numbers
+/// or strings containing a number, i.e. `{ "lat": "45.3", "lng": 3 }`.
+pub fn geo_point_from_value(point: &Value) -> Option<[f64; 2]> {
+    let coordinate = |value: &Value| match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(string) => string.trim().parse::<f64>().ok().filter(|f| f.is_finite()),
+        _ => None,
+    };
+    Some([coordinate(&point["lat"])?, coordinate(&point["lng"])?])
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::json;
 
     use super::*;
 
+    #[test]
+    fn distance_between_two_points_accuracy() {
+        // the reference distances are computed on the WGS 84 ellipsoid
+        let references = [
+            ([48.8566, 2.3522], [51.5074, -0.1278], 343_923.), // Paris - London
+            ([40.7128, -74.0060], [34.0522, -118.2437], 3_944_422.), // New York - Los Angeles
+            ([0., 0.], [0., 1.], 111_319.),
+            ([0., 0.], [0., 180.], 20_003_931.),
+        ];
+        for (a, b, reference) in references {
+            let distance = distance_between_two_points(&a, &b);
+            let error = (distance - reference).abs() / reference;
+            assert!(error < 0.005, "{:?} {:?}: {} instead of {}", a, b, distance, reference);
+            assert!((distance - distance_between_two_points(&b, &a)).abs() < 1e-6);
+        }
+
+        assert_eq!(distance_between_two_points(&[45., 3.], &[45., 3.]), 0.);
+    }
+
+    #[test]
+    fn geo_point_from_value_strings() {
+        assert_eq!(geo_point_from_value(&json!({ "lat": 45.5, "lng": 3 })), Some([45.5, 3.]));
+        assert_eq!(geo_point_from_value(&json!({ "lat": " 45.5", "lng": "3" })), Some([45.5, 3.]));
+        assert_eq!(geo_point_from_value(&json!({ "lat": "45.5", "lng": "east" })), None);
+        assert_eq!(geo_point_from_value(&json!({ "lat": "NaN", "lng": 3 })), None);
+        assert_eq!(geo_point_from_value(&json!({ "lat": 45.5 })), None);
+        assert_eq!(geo_point_from_value(&json!([45.5, 3])), None);
+    }
+
     #[test]
     fn json_to_string_object() {
         let value = json!({
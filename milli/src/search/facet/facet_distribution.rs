@@ -219,7 +219,7 @@ impl<'a> FacetDistribution<'a> {
 
     pub fn execute(&self) -> Result<BTreeMap<String, BTreeMap<String, u64>>> {
         let fields_ids_map = self.index.fields_ids_map(self.rtxn)?;
-        let filterable_fields = self.index.filterable_fields(self.rtxn)?;
+        let filterable_fields = self.index.resolved_filterable_fields(self.rtxn)?;
         let fields = match self.facets {
             Some(ref facets) => {
                 let invalid_fields: HashSet<_> = facets.difference(&filterable_fields).collect();
@@ -25,7 +25,9 @@ impl<'t> FacetNumberRange<'t> {
     ) -> heed::Result<FacetNumberRange<'t>> {
         let left_bound = match left {
             Included(left) => Included((field_id, level, left, f64::MIN)),
-            Excluded(left) => Excluded((field_id, level, left, f64::MIN)),
+            // The groups of the upper levels starting with the excluded value must also be
+            // skipped, we use the biggest right bound to exclude all of them.
+            Excluded(left) => Excluded((field_id, level, left, f64::MAX)),
             Unbounded => Included((field_id, level, f64::MIN, f64::MIN)),
         };
         let right_bound = Included((field_id, level, f64::MAX, f64::MAX));
@@ -76,7 +78,9 @@ impl<'t> FacetNumberRevRange<'t> {
     ) -> heed::Result<FacetNumberRevRange<'t>> {
         let left_bound = match left {
             Included(left) => Included((field_id, level, left, f64::MIN)),
-            Excluded(left) => Excluded((field_id, level, left, f64::MIN)),
+            // The groups of the upper levels starting with the excluded value must also be
+            // skipped, we use the biggest right bound to exclude all of them.
+            Excluded(left) => Excluded((field_id, level, left, f64::MAX)),
             Unbounded => Included((field_id, level, f64::MIN, f64::MIN)),
         };
         let right_bound = Included((field_id, level, f64::MAX, f64::MAX));
@@ -1,41 +1,169 @@
-use std::fmt::{Debug, Display};
-use std::ops::Bound::{self, Excluded, Included};
-use std::ops::Deref;
+use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Display, Write};
+use std::ops::Bound::{self, Excluded, Included, Unbounded};
+use std::ops::{Deref, Range, RangeBounds};
+use std::sync::atomic::{self, AtomicBool};
+use std::time::Instant;
 
 use either::Either;
+use filter_parser::NumericBounds;
 pub use filter_parser::{Condition, Error as FPError, FilterCondition, Span, Token};
 use heed::types::DecodeIgnore;
-use log::debug;
+use log::{debug, log_enabled, Level};
+use regex::RegexBuilder;
 use roaring::RoaringBitmap;
+use rstar::{RTree, AABB};
+use semver::{Prerelease, Version};
+use serde_json::Value;
 
+use super::facet_string::FacetStringLevelZeroRange;
 use super::FacetNumberRange;
-use crate::error::{Error, UserError};
+use crate::error::{Error, InternalError, UserError};
 use crate::heed_codec::facet::{
     FacetLevelValueF64Codec, FacetStringLevelZeroCodec, FacetStringLevelZeroValueCodec,
 };
 use crate::{
-    distance_between_two_points, lat_lng_to_xyz, CboRoaringBitmapCodec, FieldId, Index, Result,
+    distance_between_two_points, geo_point_from_value, lat_lng_to_xyz, CboRoaringBitmapCodec,
+    DocumentId, FieldId, FieldsIdsMap, GeoPoint, Index, Result,
 };
 
 /// The maximum number of filters the filter AST can process.
 const MAX_FILTER_DEPTH: usize = 2000;
 
+/// The maximum size, in bytes, of a compiled regular expression.
+const MAX_REGEX_SIZE: usize = 1024 * 1024;
+
+/// The code of the filters with an invalid syntax or a value that can't be parsed,
+/// see [`FilterError::error_code`].
+pub const INVALID_FILTER_CODE: &str = "invalid_filter";
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Filter<'a> {
     condition: FilterCondition<'a>,
+    unresolved_fields: UnresolvedFieldPolicy,
+    numeric_strings: bool,
+    lng_lat: bool,
+    strict_types: bool,
+    stop: Option<StopFlag<'a>>,
+}
+
+/// The flag interrupting the evaluation of a filter, see [`Filter::with_stop_flag`].
+/// The filters are compared by content, the flags by address.
+#[derive(Debug, Clone, Copy)]
+struct StopFlag<'a>(&'a AtomicBool);
+
+impl PartialEq for StopFlag<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for StopFlag<'_> {}
+
+/// What the operations on a filterable attribute that no document ever contained match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnresolvedFieldPolicy {
+    /// The operation matches no document, except for `NOT EXISTS` and `IS NOT EMPTY`
+    /// that match all the documents. This is the default.
+    MatchNothing,
+    /// The operation, whatever it is, matches all the documents. It is the identity of
+    /// the `AND`s, the rest of the filter is applied as if the operation wasn't there.
+    MatchEverything,
+}
+
+impl Default for UnresolvedFieldPolicy {
+    fn default() -> Self {
+        Self::MatchNothing
+    }
+}
+
+/// What is left of a filter once the operations on the attributes that are not filterable
+/// are removed, see [`Filter::prune_unfilterable`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrunedFilter<'a> {
+    /// The operations that were kept.
+    Filter(Filter<'a>),
+    /// All the operations were removed from `AND`s, every document matches.
+    Everything,
+    /// All the operations of an `OR` were removed, no document matches.
+    Nothing,
 }
 
 #[derive(Debug)]
-enum FilterError<'a> {
+pub enum FilterError<'a> {
     AttributeNotFilterable { attribute: &'a str, filterable: String },
     BadGeo(&'a str),
     BadGeoLat(f64),
     BadGeoLng(f64),
+    BadInternalId(&'a str),
+    BadInternalIdOperation,
+    BadJsonRule { pointer: String, value: &'a Value },
+    CorruptedFacetValue { field: String, field_id: FieldId, value: &'a str },
+    MismatchedRangeBounds { from: &'a str, to: &'a str },
+    NonFiniteBucketEdge(&'a str),
+    NoFilterableFields(&'a str),
+    NotANumber { attribute: &'a str, value: &'a str },
+    NumericOperationOnStrings(&'a str),
     Reserved(&'a str),
     TooDeep,
+    UnsortedBucketEdges { previous: &'a str, edge: &'a str },
 }
 impl<'a> std::error::Error for FilterError<'a> {}
 
+impl<'a> FilterError<'a> {
+    /// A stable code identifying the kind of error, it can be used to
+    /// translate the message instead of relying on the english `Display`.
+    /// It is the code of the [`UserError::InvalidFilter`] returned by the filters,
+    /// the syntax errors and the values that can't be parsed use [`INVALID_FILTER_CODE`].
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::AttributeNotFilterable { .. } => "attribute_not_filterable",
+            Self::BadGeo(_) => "reserved_geo_keyword",
+            Self::BadGeoLat(_) => "bad_geo_latitude",
+            Self::BadGeoLng(_) => "bad_geo_longitude",
+            Self::BadInternalId(_) => "bad_internal_id",
+            Self::BadInternalIdOperation => "bad_internal_id_operation",
+            Self::BadJsonRule { .. } => "bad_json_filter",
+            Self::CorruptedFacetValue { .. } => "corrupted_facet_value",
+            Self::MismatchedRangeBounds { .. } => "mismatched_range_bounds",
+            Self::NonFiniteBucketEdge(_) => "non_finite_bucket_edge",
+            Self::NoFilterableFields(_) => "no_filterable_attributes",
+            Self::NotANumber { .. } => "not_a_number",
+            Self::NumericOperationOnStrings(_) => "numeric_operation_on_strings",
+            Self::Reserved(_) => "reserved_keyword",
+            Self::TooDeep => "filter_too_deep",
+            Self::UnsortedBucketEdges { .. } => "unsorted_bucket_edges",
+        }
+    }
+
+    /// Reports the error on the erroneous part of the filter.
+    fn at(self, token: &Token<'a>) -> Error {
+        let code = self.error_code();
+        located_error(code, token.as_external_error(self))
+    }
+
+    /// Reports the error on the part of the filter going from `from` to `to`, included.
+    fn between(self, from: &Token<'a>, to: &Token<'a>) -> Error {
+        let code = self.error_code();
+        located_error(code, from.as_external_error_until(to, self))
+    }
+
+    /// Reports the attribute as not filterable, using a dedicated error
+    /// when no attribute at all has been declared filterable yet.
+    fn not_filterable(attribute: &'a str, filterable_fields: HashSet<String>) -> Self {
+        if filterable_fields.is_empty() {
+            Self::NoFilterableFields(attribute)
+        } else {
+            Self::AttributeNotFilterable {
+                attribute,
+                filterable: filterable_fields.into_iter().collect::<Vec<_>>().join(" "),
+            }
+        }
+    }
+}
+
 impl<'a> Display for FilterError<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -45,6 +173,11 @@ impl<'a> Display for FilterError<'a> {
                 attribute,
                 filterable,
             ),
+            Self::NoFilterableFields(attribute) => write!(
+                f,
+                "Attribute `{}` is not filterable. No attribute is filterable yet, you must configure the filterable attributes of the index first.",
+                attribute,
+            ),
             Self::TooDeep => write!(f,
                 "Too many filter conditions, can't process more than {} filters.",
                 MAX_FILTER_DEPTH
@@ -57,13 +190,111 @@ impl<'a> Display for FilterError<'a> {
             Self::BadGeo(keyword) => write!(f, "`{}` is a reserved keyword and thus can't be used as a filter expression. Use the _geoRadius(latitude, longitude, distance) built-in rule to filter on _geo field coordinates.", keyword),
             Self::BadGeoLat(lat) => write!(f, "Bad latitude `{}`. Latitude must be contained between -90 and 90 degrees. ", lat),
             Self::BadGeoLng(lng) => write!(f, "Bad longitude `{}`. Longitude must be contained between -180 and 180 degrees. ", lng),
+            Self::BadInternalId(id) => write!(f, "Bad internal document id `{}`. The `_id` attribute only accepts positive integers.", id),
+            Self::BadInternalIdOperation => write!(f, "The `_id` attribute only supports the `=`, `!=`, `IN`, `NOT IN`, `EXISTS` and `NOT EXISTS` operations."),
+            Self::BadJsonRule { pointer, value } => write!(
+                f,
+                "Invalid filter at `{}`: expected a string or an array of strings but instead got `{}`.",
+                pointer,
+                value,
+            ),
+            Self::CorruptedFacetValue { field, field_id, value } => write!(
+                f,
+                "The documents ids of the value `{}` of the attribute `{}` (field id {}) could not be decoded, the index may be corrupted.",
+                value,
+                field,
+                field_id,
+            ),
+            Self::MismatchedRangeBounds { from, to } => write!(
+                f,
+                "The bounds of a range must both be numbers but `{}` and `{}` are not of the same type.",
+                from,
+                to,
+            ),
+            Self::NotANumber { attribute, value } => write!(
+                f,
+                "Attribute `{}` only contains numbers but `{}` is not a number.",
+                attribute,
+                value,
+            ),
+            Self::NumericOperationOnStrings(attribute) => write!(
+                f,
+                "Attribute `{}` only contains strings and can't be compared with numbers.",
+                attribute,
+            ),
+            Self::NonFiniteBucketEdge(edge) => {
+                write!(f, "The edges of the buckets must be finite numbers but got `{}`.", edge)
+            }
+            Self::UnsortedBucketEdges { previous, edge } => write!(
+                f,
+                "The edges of the buckets must be sorted in ascending order without duplicates but `{}` comes after `{}`.",
+                edge,
+                previous,
+            ),
+        }
+    }
+}
+
+/// A non-fatal notice about a filter relying on a confusing syntax, the filter
+/// is still valid and evaluated the same way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterWarning<'a> {
+    /// An `=` or `!=` on a number that also matches the strings of the same value.
+    ImplicitStringUnion { attribute: &'a str, operator: &'static str, value: &'a str },
+    /// A `NOT x TO y` that only matches the numbers outside of the range.
+    NegatedRange { attribute: &'a str, from: &'a str, to: &'a str },
+}
+
+impl<'a> FilterWarning<'a> {
+    /// A stable code identifying the kind of warning, see [`FilterError::error_code`].
+    pub fn warning_code(&self) -> &'static str {
+        match self {
+            Self::ImplicitStringUnion { .. } => "implicit_string_union",
+            Self::NegatedRange { .. } => "negated_range",
+        }
+    }
+}
+
+impl<'a> Display for FilterWarning<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ImplicitStringUnion { attribute, operator, value } => write!(
+                f,
+                "`{attribute} {operator} {value}` also matches the `\"{value}\"` strings. Use `{attribute} {operator}# {value}` to only match the numbers.",
+                attribute = attribute,
+                operator = operator,
+                value = value,
+            ),
+            Self::NegatedRange { attribute, from, to } => write!(
+                f,
+                "`NOT {attribute} {from} TO {to}` only matches the numbers outside of the range. Prefer the explicit `{attribute} < {from} OR {attribute} > {to}`.",
+                attribute = attribute,
+                from = from,
+                to = to,
+            ),
         }
     }
 }
 
+/// The error of a filter along with the byte offsets of its erroneous part, see
+/// [`FPError::byte_offsets`].
+fn located_error(code: &'static str, error: FPError) -> Error {
+    let byte_offsets = Some(error.byte_offsets());
+    let message = error.to_string();
+    Error::UserError(UserError::InvalidFilter { code, message, byte_offsets })
+}
+
 impl<'a> From<FPError<'a>> for Error {
     fn from(error: FPError<'a>) -> Self {
-        Self::UserError(UserError::InvalidFilter(error.to_string()))
+        located_error(INVALID_FILTER_CODE, error)
+    }
+}
+
+impl<'a> From<FilterError<'a>> for Error {
+    fn from(error: FilterError<'a>) -> Self {
+        let code = error.error_code();
+        let message = error.to_string();
+        Self::UserError(UserError::InvalidFilter { code, message, byte_offsets: None })
     }
 }
 
@@ -74,71 +305,234 @@ impl<'a> From<Filter<'a>> for FilterCondition<'a> {
 }
 
 impl<'a> Filter<'a> {
+    /// Chooses what the operations on the filterable attributes that no document
+    /// ever contained match, see [`UnresolvedFieldPolicy`].
+    pub fn with_unresolved_field_policy(mut self, policy: UnresolvedFieldPolicy) -> Self {
+        self.unresolved_fields = policy;
+        self
+    }
+
+    /// Makes the numeric ranges (`>`, `<=`, `TO`, `NOT TO`...) also match the string
+    /// values that can be parsed as numbers, i.e. `"12"` for `price > 10`.
+    /// By default the ranges only match the numbers.
+    pub fn with_numeric_strings(mut self, enabled: bool) -> Self {
+        self.numeric_strings = enabled;
+        self
+    }
+
+    /// Reads the points of the geo filters in the `(longitude, latitude)` order of GeoJSON,
+    /// i.e. `_geoRadius(longitude, latitude, radius)`, instead of the default
+    /// `(latitude, longitude)` order. The out of range coordinates are still reported
+    /// as bad latitudes or longitudes according to the chosen order.
+    pub fn with_lng_lat_order(mut self, enabled: bool) -> Self {
+        if self.lng_lat != enabled {
+            self.condition = self.condition.swap_coordinates();
+            self.lng_lat = enabled;
+        }
+        self
+    }
+
+    /// Rejects the operations that don't match the type of the values of the attribute
+    /// instead of silently matching nothing: the `=`, `!=`, `IN` and `NOT IN` with a value
+    /// that isn't a number on an attribute only containing numbers, i.e. `price = abc`,
+    /// and the numeric operations (`>`, `TO`, `=#`...) on an attribute only containing
+    /// strings. The attributes containing both numbers and strings accept any operation.
+    /// By default the operations are never rejected.
+    pub fn with_strict_types(mut self, enabled: bool) -> Self {
+        self.strict_types = enabled;
+        self
+    }
+
+    /// Interrupts the evaluation with an [`Error::Interrupted`] as soon as the flag is set,
+    /// i.e. by another thread when the filter takes too long to be evaluated. The flag is
+    /// checked before each node of the filter, `AND`s and `OR`s included, and for each value
+    /// or document scanned by the operations reading all the values of an attribute.
+    pub fn with_stop_flag(mut self, stop: &'a AtomicBool) -> Self {
+        self.stop = Some(StopFlag(stop));
+        self
+    }
+
+    /// Returns an error if the evaluation must be interrupted.
+    fn check_stop_flag(stop: Option<StopFlag>) -> Result<()> {
+        match stop {
+            Some(StopFlag(stop)) if stop.load(atomic::Ordering::Relaxed) => Err(Error::Interrupted),
+            _ => Ok(()),
+        }
+    }
+
+    /// Creates a filter from a part of this filter, with the same options.
+    fn sub_filter(&self, condition: &FilterCondition<'a>) -> Self {
+        Self {
+            condition: condition.clone(),
+            unresolved_fields: self.unresolved_fields,
+            numeric_strings: self.numeric_strings,
+            lng_lat: self.lng_lat,
+            strict_types: self.strict_types,
+            stop: self.stop,
+        }
+    }
+
     pub fn from_array<I, J>(array: I) -> Result<Option<Self>>
     where
         I: IntoIterator<Item = Either<J, &'a str>>,
         J: IntoIterator<Item = &'a str>,
     {
-        let mut ands: Option<FilterCondition> = None;
+        let mut ands = Vec::new();
 
         for either in array {
             match either {
                 Either::Left(array) => {
-                    let mut ors = None;
+                    let mut ors = Vec::new();
                     for rule in array {
                         if let Some(filter) = Self::from_str(rule.as_ref())? {
-                            let condition = filter.condition;
-                            ors = match ors.take() {
-                                Some(ors) => {
-                                    Some(FilterCondition::Or(Box::new(ors), Box::new(condition)))
-                                }
-                                None => Some(condition),
-                            };
+                            ors.push(filter.condition);
                         }
                     }
-
-                    if let Some(rule) = ors {
-                        ands = match ands.take() {
-                            Some(ands) => {
-                                Some(FilterCondition::And(Box::new(ands), Box::new(rule)))
-                            }
-                            None => Some(rule),
-                        };
-                    }
+                    ands.extend(FilterCondition::or_all(ors));
                 }
                 Either::Right(rule) => {
                     if let Some(filter) = Self::from_str(rule.as_ref())? {
-                        let condition = filter.condition;
-                        ands = match ands.take() {
-                            Some(ands) => {
-                                Some(FilterCondition::And(Box::new(ands), Box::new(condition)))
-                            }
-                            None => Some(condition),
-                        };
+                        ands.push(filter.condition);
                     }
                 }
             }
         }
 
+        Self::from_ands(ands)
+    }
+
+    /// ANDs the rules of an array together, the rules of the arrays nested in it
+    /// having already been ORed together.
+    fn from_ands(ands: Vec<FilterCondition<'a>>) -> Result<Option<Self>> {
+        let ands = FilterCondition::and_all(ands);
         if let Some(token) = ands.as_ref().and_then(|fc| fc.token_at_depth(MAX_FILTER_DEPTH)) {
-            return Err(token.as_external_error(FilterError::TooDeep).into());
+            return Err(FilterError::TooDeep.at(token));
         }
 
-        Ok(ands.map(|ands| Self { condition: ands }))
+        Ok(ands.map(Self::from))
+    }
+
+    /// Like [`Filter::from_array`] but the rules using an attribute that is not filterable
+    /// are skipped instead of making the evaluation fail. The skipped rules are reported
+    /// as warnings, the syntax errors are still returned as errors.
+    pub fn from_array_lenient<I, J>(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        array: I,
+    ) -> Result<(Option<Self>, Vec<FilterError<'a>>)>
+    where
+        I: IntoIterator<Item = Either<J, &'a str>>,
+        J: IntoIterator<Item = &'a str>,
+    {
+        let filterable_fields = index.resolved_filterable_fields(rtxn)?;
+        let mut warnings = Vec::new();
+        // the rules are parsed once, only the kept ones are returned
+        let mut kept_rule = |rule: &'a str| -> Result<Option<FilterCondition<'a>>> {
+            let condition = match Self::from_str(rule)? {
+                Some(filter) => filter.condition,
+                None => return Ok(None),
+            };
+            match unfilterable_attribute(&condition, &filterable_fields) {
+                Some(attribute) => {
+                    warnings
+                        .push(FilterError::not_filterable(attribute, filterable_fields.clone()));
+                    Ok(None)
+                }
+                None => Ok(Some(condition)),
+            }
+        };
+
+        let mut ands = Vec::new();
+        for either in array {
+            match either {
+                Either::Left(array) => {
+                    let mut ors = Vec::new();
+                    for rule in array {
+                        ors.extend(kept_rule(rule)?);
+                    }
+                    ands.extend(FilterCondition::or_all(ors));
+                }
+                Either::Right(rule) => ands.extend(kept_rule(rule)?),
+            }
+        }
+
+        Ok((Self::from_ands(ands)?, warnings))
+    }
+
+    /// Builds a filter from its JSON representation, as received by Meilisearch.
+    ///
+    /// A string is parsed as a single rule, the elements of a top-level array are ANDed
+    /// together, and the strings of an array nested in it are ORed together. Any other
+    /// value is rejected with an error pointing at it with a JSON pointer.
+    pub fn from_json(value: &'a Value) -> Result<Option<Self>> {
+        let bad_rule =
+            |pointer: String, value| Error::from(FilterError::BadJsonRule { pointer, value });
+
+        match value {
+            Value::Null => Ok(None),
+            Value::String(rule) => Self::from_str(rule),
+            Value::Array(array) => {
+                let mut rules = Vec::with_capacity(array.len());
+                for (i, value) in array.iter().enumerate() {
+                    match value {
+                        Value::String(rule) => rules.push(Either::Right(rule.as_str())),
+                        Value::Array(array) => {
+                            let mut ors = Vec::with_capacity(array.len());
+                            for (j, value) in array.iter().enumerate() {
+                                match value {
+                                    Value::String(rule) => ors.push(rule.as_str()),
+                                    value => return Err(bad_rule(format!("/{}/{}", i, j), value)),
+                                }
+                            }
+                            rules.push(Either::Left(ors));
+                        }
+                        value => return Err(bad_rule(format!("/{}", i), value)),
+                    }
+                }
+                Self::from_array(rules)
+            }
+            value => Err(bad_rule(String::new(), value)),
+        }
     }
 
     pub fn from_str(expression: &'a str) -> Result<Option<Self>> {
         let condition = match FilterCondition::parse(expression) {
             Ok(Some(fc)) => Ok(fc),
             Ok(None) => return Ok(None),
-            Err(e) => Err(Error::UserError(UserError::InvalidFilter(e.to_string()))),
+            Err(e) => Err(Error::from(e)),
         }?;
 
         if let Some(token) = condition.token_at_depth(MAX_FILTER_DEPTH) {
-            return Err(token.as_external_error(FilterError::TooDeep).into());
+            return Err(FilterError::TooDeep.at(token));
+        }
+
+        Ok(Some(Self::from(condition)))
+    }
+
+    /// Parses the filter like [`Filter::from_str`] and also returns the warnings about
+    /// the parts of the filter that are valid but could be written more clearly.
+    pub fn from_str_with_warnings(
+        expression: &'a str,
+    ) -> Result<(Option<Self>, Vec<FilterWarning<'a>>)> {
+        let filter = Self::from_str(expression)?;
+        let mut warnings = Vec::new();
+        if let Some(filter) = &filter {
+            collect_warnings(&filter.condition, &mut warnings);
+        }
+        Ok((filter, warnings))
+    }
+
+    /// Parses as much of a filter that is still being written as possible, the error
+    /// is returned along with the longest valid prefix of the filter, if any.
+    /// See [`FilterCondition::parse_partial`].
+    pub fn try_from_str(expression: &'a str) -> (Option<Self>, Option<Error>) {
+        let (condition, error) = FilterCondition::parse_partial(expression);
+        if let Some(token) = condition.as_ref().and_then(|fc| fc.token_at_depth(MAX_FILTER_DEPTH)) {
+            return (None, Some(FilterError::TooDeep.at(token)));
         }
 
-        Ok(Some(Self { condition }))
+        let error = error.map(Error::from);
+        (condition.map(Self::from), error)
     }
 }
 
@@ -153,12 +547,13 @@ impl<'a> Filter<'a> {
         left: Bound<f64>,
         right: Bound<f64>,
         output: &mut RoaringBitmap,
+        stats: &mut OperationStats,
     ) -> Result<()> {
         match (left, right) {
             // If the request is an exact value we must go directly to the deepest level.
             (Included(l), Included(r)) if l == r && level > 0 => {
                 return Self::explore_facet_number_levels(
-                    rtxn, db, field_id, 0, left, right, output,
+                    rtxn, db, field_id, 0, left, right, output, stats,
                 );
             }
             // lower TO upper when lower > upper must return no result
@@ -175,11 +570,13 @@ impl<'a> Filter<'a> {
         // We must create a custom iterator to be able to iterate over the
         // requested range as the range iterator cannot express some conditions.
         let iter = FacetNumberRange::new(rtxn, db, field_id, level, left, right)?;
+        stats.numbers_db = true;
 
         debug!("Iterating between {:?} and {:?} (level {})", left, right, level);
 
         for (i, result) in iter.enumerate() {
             let ((_fid, level, l, r), docids) = result?;
+            stats.scanned_keys += 1;
             debug!("{:?} to {:?} (level {}) found {} documents", l, r, level, docids.len());
             *output |= docids;
             // We save the leftest and rightest bounds we actually found at this level.
@@ -214,6 +611,7 @@ impl<'a> Filter<'a> {
                         left,
                         sub_right,
                         output,
+                        stats,
                     )?;
                 }
                 if !matches!(right, Included(r) if r == right_found) {
@@ -230,6 +628,7 @@ impl<'a> Filter<'a> {
                         sub_left,
                         right,
                         output,
+                        stats,
                     )?;
                 }
             }
@@ -244,6 +643,7 @@ impl<'a> Filter<'a> {
                     left,
                     right,
                     output,
+                    stats,
                 )?;
             }
         }
@@ -258,35 +658,43 @@ impl<'a> Filter<'a> {
         strings_db: heed::Database<FacetStringLevelZeroCodec, FacetStringLevelZeroValueCodec>,
         field_id: FieldId,
         operator: &Condition<'a>,
+        biggest_levels: &mut HashMap<FieldId, Option<u8>>,
+        stats: &mut OperationStats,
+        stop: Option<StopFlag>,
     ) -> Result<RoaringBitmap> {
         // Make sure we always bound the ranges with the field id and the level,
         // as the facets values are all in the same database and prefixed by the
-        // field id and the level.
-
-        let (left, right) = match operator {
-            Condition::GreaterThan(val) => (Excluded(val.parse()?), Included(f64::MAX)),
-            Condition::GreaterThanOrEqual(val) => (Included(val.parse()?), Included(f64::MAX)),
-            Condition::LowerThan(val) => (Included(f64::MIN), Excluded(val.parse()?)),
-            Condition::LowerThanOrEqual(val) => (Included(f64::MIN), Included(val.parse()?)),
-            Condition::Between { from, to } => (Included(from.parse()?), Included(to.parse()?)),
+        // field id and the level. The values have already been checked by
+        // `validate_operator`.
+
+        let ranges = match operator {
+            // The strings are matched on the lowercased value as written while the numbers
+            // are matched on its parsed value, `05` matches `"05"` and `5` but not `"5"`.
             Condition::Equal(val) => {
-                let (_original_value, string_docids) =
-                    strings_db.get(rtxn, &(field_id, &val.to_lowercase()))?.unwrap_or_default();
+                let (_original_value, string_docids) = match strings_db
+                    .get(rtxn, &(field_id, &val.to_lowercase()))
+                {
+                    Ok(entry) => entry.unwrap_or_default(),
+                    Err(heed::Error::Decoding) => {
+                        let field = index.fields_ids_map(rtxn)?.name(field_id).map(String::from);
+                        let error = FilterError::CorruptedFacetValue {
+                            field: field.unwrap_or_default(),
+                            field_id,
+                            value: **val,
+                        };
+                        return Err(error.at(val));
+                    }
+                    Err(error) => return Err(error.into()),
+                };
+                stats.strings_db = true;
+                stats.scanned_keys += 1;
                 let number = val.parse::<f64>().ok();
+                // an exact number is a single key of the level 0, no level has to be walked
                 let number_docids = match number {
                     Some(n) => {
-                        let n = Included(n);
-                        let mut output = RoaringBitmap::new();
-                        Self::explore_facet_number_levels(
-                            rtxn,
-                            numbers_db,
-                            field_id,
-                            0,
-                            n,
-                            n,
-                            &mut output,
-                        )?;
-                        output
+                        stats.numbers_db = true;
+                        stats.scanned_keys += 1;
+                        numbers_db.get(rtxn, &(field_id, 0, n, n))?.unwrap_or_default()
                     }
                     None => RoaringBitmap::new(),
                 };
@@ -300,346 +708,5407 @@ impl<'a> Filter<'a> {
                     RoaringBitmap::new()
                 };
                 let all_strings_ids = index.string_faceted_documents_ids(rtxn, field_id)?;
+                stats.faceted_documents = true;
                 let operator = Condition::Equal(val.clone());
                 let docids = Self::evaluate_operator(
-                    rtxn, index, numbers_db, strings_db, field_id, &operator,
+                    rtxn,
+                    index,
+                    numbers_db,
+                    strings_db,
+                    field_id,
+                    &operator,
+                    biggest_levels,
+                    stats,
+                    stop,
                 )?;
-                return Ok((all_numbers_ids | all_strings_ids) - docids);
+                return Ok(complement(all_numbers_ids | all_strings_ids, docids));
             }
-        };
-
-        // Ask for the biggest value that can exist for this specific field, if it exists
-        // that's fine if it don't, the value just before will be returned instead.
-        let biggest_level = numbers_db
-            .remap_data_type::<DecodeIgnore>()
-            .get_lower_than_or_equal_to(rtxn, &(field_id, u8::MAX, f64::MAX, f64::MAX))?
-            .and_then(|((id, level, _, _), _)| if id == field_id { Some(level) } else { None });
-
-        match biggest_level {
-            Some(level) => {
-                let mut output = RoaringBitmap::new();
-                Self::explore_facet_number_levels(
+            // Unlike `!=`, the documents with only string values are never matched.
+            Condition::NumericNotEqual(val) => {
+                let all_numbers_ids = index.number_faceted_documents_ids(rtxn, field_id)?;
+                stats.faceted_documents = true;
+                let docids = Self::evaluate_operator(
                     rtxn,
+                    index,
                     numbers_db,
+                    strings_db,
                     field_id,
-                    level,
-                    left,
-                    right,
-                    &mut output,
+                    &Condition::NumericEqual(val.clone()),
+                    biggest_levels,
+                    stats,
+                    stop,
                 )?;
-                Ok(output)
+                return Ok(complement(all_numbers_ids, docids));
             }
-            None => Ok(RoaringBitmap::new()),
-        }
-    }
-
-    pub fn evaluate(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<RoaringBitmap> {
-        let numbers_db = index.facet_id_f64_docids;
-        let strings_db = index.facet_id_string_docids;
-
-        match &self.condition {
-            FilterCondition::Condition { fid, op } => {
-                let filterable_fields = index.filterable_fields(rtxn)?;
-                if filterable_fields.contains(fid.value()) {
-                    let field_ids_map = index.fields_ids_map(rtxn)?;
-                    if let Some(fid) = field_ids_map.id(fid.value()) {
-                        Self::evaluate_operator(rtxn, index, numbers_db, strings_db, fid, &op)
-                    } else {
-                        return Ok(RoaringBitmap::new());
-                    }
+            Condition::Exists => {
+                let all_numbers_ids = index.number_faceted_documents_ids(rtxn, field_id)?;
+                let all_strings_ids = index.string_faceted_documents_ids(rtxn, field_id)?;
+                stats.faceted_documents = true;
+                return Ok(all_numbers_ids | all_strings_ids);
+            }
+            Condition::NotExists => {
+                let all_ids = index.documents_ids(rtxn)?;
+                let docids = Self::evaluate_operator(
+                    rtxn,
+                    index,
+                    numbers_db,
+                    strings_db,
+                    field_id,
+                    &Condition::Exists,
+                    biggest_levels,
+                    stats,
+                    stop,
+                )?;
+                return Ok(complement(all_ids, docids));
+            }
+            Condition::In(values) => {
+                let mut docids = RoaringBitmap::new();
+                for val in values {
+                    docids |= Self::evaluate_operator(
+                        rtxn,
+                        index,
+                        numbers_db,
+                        strings_db,
+                        field_id,
+                        &Condition::Equal(val.clone()),
+                        biggest_levels,
+                        stats,
+                        stop,
+                    )?;
+                }
+                return Ok(docids);
+            }
+            Condition::NotIn(values) => {
+                // like `!=`, the numbers are only considered if one of the values is a number
+                let any_number = values.iter().any(|val| val.parse::<f64>().is_ok());
+                let all_numbers_ids = if any_number {
+                    index.number_faceted_documents_ids(rtxn, field_id)?
                 } else {
-                    match *fid.deref() {
-                        attribute @ "_geo" => {
-                            return Err(fid.as_external_error(FilterError::BadGeo(attribute)))?;
-                        }
-                        attribute if attribute.starts_with("_geoPoint(") => {
-                            return Err(fid.as_external_error(FilterError::BadGeo("_geoPoint")))?;
-                        }
-                        attribute @ "_geoDistance" => {
-                            return Err(fid.as_external_error(FilterError::Reserved(attribute)))?;
-                        }
-                        attribute => {
-                            return Err(fid.as_external_error(
-                                FilterError::AttributeNotFilterable {
-                                    attribute,
-                                    filterable: filterable_fields
-                                        .into_iter()
-                                        .collect::<Vec<_>>()
-                                        .join(" "),
-                                },
-                            ))?;
+                    RoaringBitmap::new()
+                };
+                let all_strings_ids = index.string_faceted_documents_ids(rtxn, field_id)?;
+                stats.faceted_documents = true;
+                let docids = Self::evaluate_operator(
+                    rtxn,
+                    index,
+                    numbers_db,
+                    strings_db,
+                    field_id,
+                    &Condition::In(values.clone()),
+                    biggest_levels,
+                    stats,
+                    stop,
+                )?;
+                return Ok(complement(all_numbers_ids | all_strings_ids, docids));
+            }
+            // The empty strings are not indexed in the facet databases, we must read the
+            // documents that don't have any facet value for this field. They are read one
+            // by one, the evaluation can be stopped between two of them.
+            Condition::IsEmpty => {
+                let faceted_ids = index.number_faceted_documents_ids(rtxn, field_id)?
+                    | index.string_faceted_documents_ids(rtxn, field_id)?;
+                stats.faceted_documents = true;
+                let candidates = index.documents_ids(rtxn)? - faceted_ids;
+                let mut docids = RoaringBitmap::new();
+                for docid in candidates {
+                    Self::check_stop_flag(stop)?;
+                    let document = match index.documents(rtxn, Some(docid))?.pop() {
+                        Some((_, document)) => document,
+                        None => continue,
+                    };
+                    if let Some(value) = document.get(field_id) {
+                        let value: Value =
+                            serde_json::from_slice(value).map_err(InternalError::SerdeJson)?;
+                        if is_empty_string(&value) {
+                            docids.insert(docid);
                         }
                     }
                 }
+                return Ok(docids);
             }
-            FilterCondition::Or(lhs, rhs) => {
-                let lhs = Self::evaluate(&(lhs.as_ref().clone()).into(), rtxn, index)?;
-                let rhs = Self::evaluate(&(rhs.as_ref().clone()).into(), rtxn, index)?;
-                Ok(lhs | rhs)
-            }
-            FilterCondition::And(lhs, rhs) => {
-                let lhs = Self::evaluate(&(lhs.as_ref().clone()).into(), rtxn, index)?;
-                let rhs = Self::evaluate(&(rhs.as_ref().clone()).into(), rtxn, index)?;
-                Ok(lhs & rhs)
+            Condition::IsNotEmpty => {
+                let all_ids = index.documents_ids(rtxn)?;
+                let docids = Self::evaluate_operator(
+                    rtxn,
+                    index,
+                    numbers_db,
+                    strings_db,
+                    field_id,
+                    &Condition::IsEmpty,
+                    biggest_levels,
+                    stats,
+                    stop,
+                )?;
+                return Ok(complement(all_ids, docids));
             }
-            FilterCondition::GeoLowerThan { point, radius } => {
-                let filterable_fields = index.filterable_fields(rtxn)?;
-                if filterable_fields.contains("_geo") {
-                    let base_point: [f64; 2] = [point[0].parse()?, point[1].parse()?];
-                    if !(-90.0..=90.0).contains(&base_point[0]) {
-                        return Err(
-                            point[0].as_external_error(FilterError::BadGeoLat(base_point[0]))
-                        )?;
-                    }
-                    if !(-180.0..=180.0).contains(&base_point[1]) {
-                        return Err(
-                            point[1].as_external_error(FilterError::BadGeoLng(base_point[1]))
-                        )?;
+            // The regex crate guarantees a matching in linear time,
+            // we also limit the size of the compiled expression.
+            Condition::Regex(pattern) => {
+                let regex = RegexBuilder::new(pattern.value())
+                    .size_limit(MAX_REGEX_SIZE)
+                    .build()
+                    .map_err(|e| pattern.as_external_error(e))?;
+                // the expression is matched against the normalized values
+                let mut docids = RoaringBitmap::new();
+                let strings = FacetStringLevelZeroRange::new(
+                    rtxn, strings_db, field_id, Unbounded, Unbounded,
+                )?;
+                stats.strings_db = true;
+                for result in strings {
+                    Self::check_stop_flag(stop)?;
+                    let (normalized, _original, values) = result?;
+                    stats.scanned_keys += 1;
+                    if regex.is_match(normalized) {
+                        docids |= values;
                     }
-                    let radius = radius.parse()?;
-                    let rtree = match index.geo_rtree(rtxn)? {
-                        Some(rtree) => rtree,
-                        None => return Ok(RoaringBitmap::new()),
-                    };
-
-                    let xyz_base_point = lat_lng_to_xyz(&base_point);
-
-                    let result = rtree
-                        .nearest_neighbor_iter(&xyz_base_point)
-                        .take_while(|point| {
-                            distance_between_two_points(&base_point, &point.data.1) < radius
-                        })
-                        .map(|point| point.data.0)
-                        .collect();
-
-                    Ok(result)
-                } else {
-                    return Err(point[0].as_external_error(FilterError::AttributeNotFilterable {
-                        attribute: "_geo",
-                        filterable: filterable_fields.into_iter().collect::<Vec<_>>().join(" "),
-                    }))?;
                 }
+                return Ok(docids);
             }
-            FilterCondition::GeoGreaterThan { point, radius } => {
-                let result = Self::evaluate(
-                    &FilterCondition::GeoLowerThan { point: point.clone(), radius: radius.clone() }
-                        .into(),
+            Condition::NotRegex(pattern) => {
+                let all_strings_ids = index.string_faceted_documents_ids(rtxn, field_id)?;
+                stats.faceted_documents = true;
+                let docids = Self::evaluate_operator(
                     rtxn,
                     index,
+                    numbers_db,
+                    strings_db,
+                    field_id,
+                    &Condition::Regex(pattern.clone()),
+                    biggest_levels,
+                    stats,
+                    stop,
                 )?;
-                let geo_faceted_doc_ids = index.geo_faceted_documents_ids(rtxn)?;
-                Ok(geo_faceted_doc_ids - result)
+                return Ok(complement(all_strings_ids, docids));
             }
-        }
-    }
-}
+            // The stored values that are not valid semantic versions are ignored,
+            // the comparison is done on the normalized (lowercased) values.
+            Condition::SemVer(comparison) => {
+                let (token, accept): (_, fn(Ordering) -> bool) = match comparison.as_ref() {
+                    Condition::GreaterThan(v) => (v, |o| o == Ordering::Greater),
+                    Condition::GreaterThanOrEqual(v) => (v, |o| o != Ordering::Less),
+                    Condition::Equal(v) => (v, |o| o == Ordering::Equal),
+                    Condition::NotEqual(v) => (v, |o| o != Ordering::Equal),
+                    Condition::LowerThan(v) => (v, |o| o == Ordering::Less),
+                    Condition::LowerThanOrEqual(v) => (v, |o| o != Ordering::Greater),
+                    _ => unreachable!("the semver only supports simple comparisons"),
+                };
+                let version = Version::parse(&token.value().trim().to_lowercase())
+                    .map_err(|e| token.as_external_error(e))?;
 
-impl<'a> From<FilterCondition<'a>> for Filter<'a> {
-    fn from(fc: FilterCondition<'a>) -> Self {
-        Self { condition: fc }
-    }
-}
+                let mut docids = RoaringBitmap::new();
+                let strings = FacetStringLevelZeroRange::new(
+                    rtxn, strings_db, field_id, Unbounded, Unbounded,
+                )?;
+                stats.strings_db = true;
+                for result in strings {
+                    Self::check_stop_flag(stop)?;
+                    let (normalized, _original, values) = result?;
+                    stats.scanned_keys += 1;
+                    if let Ok(value) = Version::parse(normalized) {
+                        if accept(semver_precedence(&value).cmp(&semver_precedence(&version))) {
+                            docids |= values;
+                        }
+                    }
+                }
+                return Ok(docids);
+            }
+            // The words are separated by whitespaces and the characters are counted on the
+            // trimmed original value, the documents match if any of their strings matches.
+            Condition::WordsCount(comparison) | Condition::CharsCount(comparison) => {
+                let mut docids = RoaringBitmap::new();
+                let strings = FacetStringLevelZeroRange::new(
+                    rtxn, strings_db, field_id, Unbounded, Unbounded,
+                )?;
+                stats.strings_db = true;
+                for result in strings {
+                    Self::check_stop_flag(stop)?;
+                    let (normalized, original, values) = result?;
+                    stats.scanned_keys += 1;
+                    let count = match operator {
+                        Condition::WordsCount(_) => normalized.split_whitespace().count(),
+                        _ => original.trim().chars().count(),
+                    };
+                    if Self::compare_values_count(comparison, count as u64)? {
+                        docids |= values;
+                    }
+                }
+                return Ok(docids);
+            }
+            // The numbers of the two attributes are read document by document, it costs
+            // a scan over the documents having numbers for both, at most the smallest set.
+            Condition::FieldComparison(comparison) => {
+                let (other, accept): (_, fn(Ordering) -> bool) = match comparison.as_ref() {
+                    Condition::GreaterThan(v) => (v, |o| o == Ordering::Greater),
+                    Condition::GreaterThanOrEqual(v) => (v, |o| o != Ordering::Less),
+                    Condition::Equal(v) => (v, |o| o == Ordering::Equal),
+                    Condition::NotEqual(v) => (v, |o| o != Ordering::Equal),
+                    Condition::LowerThan(v) => (v, |o| o == Ordering::Less),
+                    Condition::LowerThanOrEqual(v) => (v, |o| o != Ordering::Greater),
+                    _ => unreachable!("the field comparisons only support simple comparisons"),
+                };
+                let other_id = match index.fields_ids_map(rtxn)?.id(other.value()) {
+                    Some(other_id) => other_id,
+                    None => return Ok(RoaringBitmap::new()),
+                };
 
-#[cfg(test)]
+                let numbers = |field_id, docid| -> Result<Vec<f64>> {
+                    let range = (field_id, docid, f64::MIN)..=(field_id, docid, f64::MAX);
+                    let mut values = Vec::new();
+                    for result in index.field_id_docid_facet_f64s.range(rtxn, &range)? {
+                        let ((_, _, value), ()) = result?;
+                        values.push(value);
+                    }
+                    Ok(values)
+                };
+
+                let candidates = index.number_faceted_documents_ids(rtxn, field_id)?
+                    & index.number_faceted_documents_ids(rtxn, other_id)?;
+                stats.faceted_documents = true;
+                let mut docids = RoaringBitmap::new();
+                for docid in candidates {
+                    let others = numbers(other_id, docid)?;
+                    // like the other operations, a single pair of values is enough to match
+                    let matches = numbers(field_id, docid)?.iter().any(|value| {
+                        others.iter().any(|other| value.partial_cmp(other).map_or(false, accept))
+                    });
+                    if matches {
+                        docids.insert(docid);
+                    }
+                }
+                return Ok(docids);
+            }
+            // The positions of the values aren't indexed, the arrays are read from the
+            // documents. Only the documents having the value somewhere are read for `=`.
+            Condition::Position { index: position, comparison } => {
+                let (value, accept): (_, fn(Ordering) -> bool) = match comparison.as_ref() {
+                    Condition::GreaterThan(v) => (v, |o| o == Ordering::Greater),
+                    Condition::GreaterThanOrEqual(v) => (v, |o| o != Ordering::Less),
+                    Condition::Equal(v) => (v, |o| o == Ordering::Equal),
+                    Condition::NotEqual(v) => (v, |o| o != Ordering::Equal),
+                    Condition::LowerThan(v) => (v, |o| o == Ordering::Less),
+                    Condition::LowerThanOrEqual(v) => (v, |o| o != Ordering::Greater),
+                    _ => unreachable!("the positions only support simple comparisons"),
+                };
+                let position: usize = position.parse()?;
+                // like `=` and `!=`, the strings are compared once normalized, the
+                // other comparisons are numeric
+                let equality = matches!(**comparison, Condition::Equal(_) | Condition::NotEqual(_));
+                let (string, number) = if equality {
+                    (Some(value.value().trim().to_lowercase()), value.parse::<f64>().ok())
+                } else {
+                    (None, Some(value.parse_number()?))
+                };
+
+                let candidates = match comparison.as_ref() {
+                    Condition::Equal(_) => Self::evaluate_operator(
+                        rtxn,
+                        index,
+                        numbers_db,
+                        strings_db,
+                        field_id,
+                        comparison,
+                        biggest_levels,
+                        stats,
+                        stop,
+                    )?,
+                    _ => {
+                        stats.faceted_documents = true;
+                        index.number_faceted_documents_ids(rtxn, field_id)?
+                            | index.string_faceted_documents_ids(rtxn, field_id)?
+                    }
+                };
+                let mut docids = RoaringBitmap::new();
+                for (docid, document) in index.documents(rtxn, candidates)? {
+                    let value: Value = match document.get(field_id) {
+                        Some(value) => {
+                            serde_json::from_slice(value).map_err(InternalError::SerdeJson)?
+                        }
+                        None => continue,
+                    };
+                    let element = match &value {
+                        Value::Array(values) => values.get(position),
+                        value => Some(value).filter(|_| position == 0),
+                    };
+                    let ordering = match element {
+                        Some(Value::String(s)) => {
+                            string.as_ref().map(|string| s.trim().to_lowercase().cmp(string))
+                        }
+                        Some(Value::Number(n)) => {
+                            n.as_f64().zip(number).and_then(|(n, number)| n.partial_cmp(&number))
+                        }
+                        _ => None,
+                    };
+                    if ordering.map_or(false, accept) {
+                        docids.insert(docid);
+                    }
+                }
+                return Ok(docids);
+            }
+            Condition::ValuesCount(comparison) => {
+                // This is costly as we must go through all the values of the field
+                // to count the number of distinct values of each document.
+                let mut counts = HashMap::<DocumentId, u64>::new();
+                let numbers =
+                    FacetNumberRange::new(rtxn, numbers_db, field_id, 0, Unbounded, Unbounded)?;
+                stats.numbers_db = true;
+                for result in numbers {
+                    Self::check_stop_flag(stop)?;
+                    let (_, docids) = result?;
+                    stats.scanned_keys += 1;
+                    docids.iter().for_each(|docid| *counts.entry(docid).or_default() += 1);
+                }
+                let strings = FacetStringLevelZeroRange::new(
+                    rtxn, strings_db, field_id, Unbounded, Unbounded,
+                )?;
+                stats.strings_db = true;
+                for result in strings {
+                    Self::check_stop_flag(stop)?;
+                    let (_, _, docids) = result?;
+                    stats.scanned_keys += 1;
+                    docids.iter().for_each(|docid| *counts.entry(docid).or_default() += 1);
+                }
+
+                // the documents without any value are also compared, with a count of zero
+                let mut docids = RoaringBitmap::new();
+                for docid in index.documents_ids(rtxn)? {
+                    let count = counts.get(&docid).copied().unwrap_or(0);
+                    if Self::compare_values_count(comparison, count)? {
+                        docids.insert(docid);
+                    }
+                }
+                return Ok(docids);
+            }
+            // A range is always numeric, even when both bounds are the same value:
+            // `price 10 TO 10` only matches the numbers while `price = 10` also
+            // matches the `"10"` strings. The level exploration takes care of
+            // the equal bounds by directly looking at the level 0.
+            // The values outside of the range are collected in the same bitmap,
+            // without having to union the results of a `<` and a `>`.
+            // The open side of a range (`*`) has no value outside of it.
+            Condition::NotBetween { from, to } => {
+                let mut ranges = Vec::new();
+                if !from.is_wildcard() {
+                    ranges.push((Included(f64::MIN), Excluded(from.parse_number()?)));
+                }
+                if !to.is_wildcard() {
+                    ranges.push((Excluded(to.parse_number()?), Included(f64::MAX)));
+                }
+                ranges
+            }
+            operator => match operator.numeric_bounds()? {
+                Some(bounds) => vec![bounds],
+                None => unreachable!("all the non numeric operators are handled above"),
+            },
+        };
+
+        Self::explore_ranges(rtxn, numbers_db, field_id, ranges, biggest_levels, stats)
+    }
+
+    /// Walks the facet levels of the field once for each of the numeric ranges,
+    /// starting from the biggest level of the field.
+    fn explore_ranges(
+        rtxn: &heed::RoTxn,
+        numbers_db: heed::Database<FacetLevelValueF64Codec, CboRoaringBitmapCodec>,
+        field_id: FieldId,
+        ranges: Vec<NumericBounds>,
+        biggest_levels: &mut HashMap<FieldId, Option<u8>>,
+        stats: &mut OperationStats,
+    ) -> Result<RoaringBitmap> {
+        let biggest_level = match biggest_levels.entry(field_id) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => *entry.insert(Self::biggest_level(rtxn, numbers_db, field_id)?),
+        };
+
+        match biggest_level {
+            Some(level) => {
+                let mut output = RoaringBitmap::new();
+                for (left, right) in ranges {
+                    Self::explore_facet_number_levels(
+                        rtxn,
+                        numbers_db,
+                        field_id,
+                        level,
+                        left,
+                        right,
+                        &mut output,
+                        stats,
+                    )?;
+                }
+                Ok(output)
+            }
+            None => Ok(RoaringBitmap::new()),
+        }
+    }
+
+    /// Evaluates an `OR` of numeric ranges on the same attribute, i.e. `price 0 TO 10 OR
+    /// price > 20`, by walking the facet levels once per disjoint range, the overlapping
+    /// and adjacent ranges being merged beforehand. Such `OR`s are detected once from the
+    /// root of the filter, see [`RangeUnions::detect`].
+    fn evaluate_ranges_union(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        field_id: FieldId,
+        operands: &[(&Token<'a>, &Condition<'a>)],
+        biggest_levels: &mut HashMap<FieldId, Option<u8>>,
+        stats: &mut OperationStats,
+    ) -> Result<RoaringBitmap> {
+        let mut ranges = Vec::with_capacity(operands.len());
+        for (fid, op) in operands {
+            if self.strict_types {
+                Self::check_strict_type(rtxn, index, fid, field_id, op)?;
+            }
+            if let Condition::Between { from, to } = op {
+                Self::check_range_bounds(from, to)?;
+            }
+            ranges.extend(op.numeric_bounds()?);
+        }
+        let ranges = merge_ranges(ranges);
+        let numbers_db = index.facet_id_f64_docids;
+        Self::explore_ranges(rtxn, numbers_db, field_id, ranges, biggest_levels, stats)
+    }
+
+    /// Evaluates the `AND` of a radius and of the outside of a smaller radius around the
+    /// same point, i.e. a `_geoRing`, with a single walk of the rtree: the points are visited
+    /// from the nearest to the farthest. Returns `None` if the filter isn't such an `AND`.
+    fn evaluate_geo_ring(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        geo_limit: Option<usize>,
+        stats: &mut OperationStats,
+    ) -> Result<Option<RoaringBitmap>> {
+        let (point, inner, outer) = match &self.condition {
+            FilterCondition::And(lhs, rhs) => match (lhs.as_ref(), rhs.as_ref()) {
+                (
+                    FilterCondition::GeoLowerThan { point, radius: outer },
+                    FilterCondition::GeoGreaterThan { point: other, radius: inner },
+                ) if point == other => (point, inner, outer),
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        let filterable_fields = index.resolved_filterable_fields(rtxn)?;
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        Self::validate_node(&self.condition, &filterable_fields, &fields_ids_map)?;
+        let base_point = Self::parse_geo_point(point)?;
+        let (inner, outer): (f64, f64) = (inner.parse()?, outer.parse()?);
+        // the documents must be strictly within the outer radius
+        if outer <= 0. || outer <= inner {
+            return Ok(Some(RoaringBitmap::new()));
+        }
+
+        stats.geo_rtree = true;
+        let rtree = match index.geo_rtree(rtxn)? {
+            Some(rtree) => rtree,
+            None => return Ok(Some(RoaringBitmap::new())),
+        };
+        let limit = geo_limit.unwrap_or(usize::MAX);
+        let mut radii = documents_within_radii(&rtree, &base_point, &[inner, outer], limit);
+        let mut docids = radii.pop().unwrap_or_default();
+        docids -= &radii[0];
+        Ok(Some(docids))
+    }
+
+    /// Returns an error if the operation doesn't match the type of the values of the
+    /// attribute, see [`Filter::with_strict_types`].
+    fn check_strict_type(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        fid: &Token<'a>,
+        field_id: FieldId,
+        op: &Condition<'a>,
+    ) -> Result<()> {
+        let has_numbers = !index.number_faceted_documents_ids(rtxn, field_id)?.is_empty();
+        let has_strings = !index.string_faceted_documents_ids(rtxn, field_id)?.is_empty();
+        let values = match op {
+            Condition::Equal(value) | Condition::NotEqual(value) => std::slice::from_ref(value),
+            Condition::In(values) | Condition::NotIn(values) => values.as_slice(),
+            Condition::GreaterThan(_)
+            | Condition::GreaterThanOrEqual(_)
+            | Condition::LowerThan(_)
+            | Condition::LowerThanOrEqual(_)
+            | Condition::NumericEqual(_)
+            | Condition::NumericNotEqual(_)
+            | Condition::Between { .. }
+            | Condition::NotBetween { .. }
+            | Condition::InBuckets(_)
+                if has_strings && !has_numbers =>
+            {
+                let error = FilterError::NumericOperationOnStrings(**fid);
+                return Err(error.at(fid));
+            }
+            _ => return Ok(()),
+        };
+        if has_numbers && !has_strings {
+            if let Some(value) = values.iter().find(|value| value.parse::<f64>().is_err()) {
+                let error = FilterError::NotANumber { attribute: **fid, value: **value };
+                return Err(error.at(value));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns an error highlighting both bounds of the range when only one of them is
+    /// a number, an invalid value on both sides is reported on the first one when parsed.
+    fn check_range_bounds(from: &Token<'a>, to: &Token<'a>) -> Result<()> {
+        let is_number = |bound: &Token| bound.is_wildcard() || bound.parse_number().is_ok();
+        if is_number(from) != is_number(to) {
+            let error = FilterError::MismatchedRangeBounds { from: **from, to: **to };
+            return Err(error.between(from, to));
+        }
+        Ok(())
+    }
+
+    /// Parses the edges of the buckets, they must be finite and sorted in ascending order.
+    fn parse_bucket_edges(edges: &[Token<'a>]) -> Result<Vec<f64>> {
+        let mut numbers: Vec<f64> = Vec::with_capacity(edges.len());
+        for (i, edge) in edges.iter().enumerate() {
+            let number = edge.parse_number()?;
+            if !number.is_finite() {
+                let error = FilterError::NonFiniteBucketEdge(**edge);
+                return Err(error.at(edge));
+            }
+            if numbers.last().map_or(false, |previous| *previous >= number) {
+                let previous = &edges[i - 1];
+                let error = FilterError::UnsortedBucketEdges { previous: **previous, edge: **edge };
+                return Err(error.between(previous, edge));
+            }
+            numbers.push(number);
+        }
+        Ok(numbers)
+    }
+
+    /// Returns the documents with a string value that can be parsed as a number
+    /// within the numeric range of the operator, nothing if it is not a range.
+    fn evaluate_numeric_strings(
+        rtxn: &heed::RoTxn,
+        strings_db: heed::Database<FacetStringLevelZeroCodec, FacetStringLevelZeroValueCodec>,
+        field_id: FieldId,
+        operator: &Condition<'a>,
+        stats: &mut OperationStats,
+    ) -> Result<RoaringBitmap> {
+        let ranges = match operator {
+            Condition::GreaterThan(_)
+            | Condition::GreaterThanOrEqual(_)
+            | Condition::LowerThan(_)
+            | Condition::LowerThanOrEqual(_)
+            | Condition::Between { .. }
+            | Condition::InBuckets(_) => operator.numeric_bounds()?.into_iter().collect(),
+            Condition::NotBetween { from, to } => {
+                let mut ranges = Vec::new();
+                if !from.is_wildcard() {
+                    ranges.push((Included(f64::MIN), Excluded(from.parse_number()?)));
+                }
+                if !to.is_wildcard() {
+                    ranges.push((Excluded(to.parse_number()?), Included(f64::MAX)));
+                }
+                ranges
+            }
+            _ => return Ok(RoaringBitmap::new()),
+        };
+
+        let mut docids = RoaringBitmap::new();
+        let strings =
+            FacetStringLevelZeroRange::new(rtxn, strings_db, field_id, Unbounded, Unbounded)?;
+        stats.strings_db = true;
+        for result in strings {
+            let (normalized, _original, values) = result?;
+            stats.scanned_keys += 1;
+            match normalized.parse::<f64>() {
+                Ok(number) if ranges.iter().any(|range| range.contains(&number)) => {
+                    docids |= values
+                }
+                _ => (),
+            }
+        }
+        Ok(docids)
+    }
+
+    /// Selects the documents by their internal ids when the `_id` attribute
+    /// is not a filterable attribute of the documents.
+    fn evaluate_internal_ids(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        fid: &Token<'a>,
+        op: &Condition<'a>,
+    ) -> Result<RoaringBitmap> {
+        let ids = Self::parse_internal_ids(fid, op)?;
+        let documents_ids = index.documents_ids(rtxn)?;
+        match op {
+            Condition::Exists => Ok(documents_ids),
+            Condition::NotExists => Ok(RoaringBitmap::new()),
+            Condition::NotEqual(_) | Condition::NotIn(_) => Ok(complement(documents_ids, ids)),
+            _ => Ok(documents_ids & ids),
+        }
+    }
+
+    /// Parses the internal ids an operation on `_id` compares to, `EXISTS` and
+    /// `NOT EXISTS` don't compare to any id.
+    fn parse_internal_ids(fid: &Token<'a>, op: &Condition<'a>) -> Result<RoaringBitmap> {
+        let parse = |token: &Token<'a>| {
+            token.parse::<DocumentId>().map_err(|_| FilterError::BadInternalId(**token).at(token))
+        };
+
+        match op {
+            Condition::Equal(id) | Condition::NotEqual(id) => {
+                let mut ids = RoaringBitmap::new();
+                ids.insert(parse(id)?);
+                Ok(ids)
+            }
+            Condition::In(ids) | Condition::NotIn(ids) => ids.iter().map(parse).collect(),
+            Condition::Exists | Condition::NotExists => Ok(RoaringBitmap::new()),
+            _ => Err(FilterError::BadInternalIdOperation.at(fid)),
+        }
+    }
+
+    fn biggest_level(
+        rtxn: &heed::RoTxn,
+        numbers_db: heed::Database<FacetLevelValueF64Codec, CboRoaringBitmapCodec>,
+        field_id: FieldId,
+    ) -> Result<Option<u8>> {
+        // Ask for the biggest value that can exist for this specific field, if it exists
+        // that's fine if it don't, the value just before will be returned instead.
+        let biggest_level = numbers_db
+            .remap_data_type::<DecodeIgnore>()
+            .get_lower_than_or_equal_to(rtxn, &(field_id, u8::MAX, f64::MAX, f64::MAX))?
+            .and_then(|((id, level, _, _), _)| if id == field_id { Some(level) } else { None });
+        Ok(biggest_level)
+    }
+
+    fn compare_values_count(comparison: &Condition<'a>, count: u64) -> Result<bool> {
+        Ok(match comparison {
+            Condition::GreaterThan(n) => count > n.parse::<u64>()?,
+            Condition::GreaterThanOrEqual(n) => count >= n.parse::<u64>()?,
+            Condition::Equal(n) => count == n.parse::<u64>()?,
+            Condition::NotEqual(n) => count != n.parse::<u64>()?,
+            Condition::LowerThan(n) => count < n.parse::<u64>()?,
+            Condition::LowerThanOrEqual(n) => count <= n.parse::<u64>()?,
+            _ => unreachable!("the values count only supports simple comparisons"),
+        })
+    }
+
+    /// Returns the documents matching the filter. When the debug level of the
+    /// [`EVALUATION_LOG_TARGET`] target is enabled, the number of documents matched by
+    /// each operation and the evaluation time are logged once the filter is evaluated.
+    pub fn evaluate(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<RoaringBitmap> {
+        self.logged_evaluate(rtxn, index, None)
+    }
+
+    /// Evaluates the filter but only keeps the `limit` nearest documents of each `_geoRadius`,
+    /// the `NOT _geoRadius` are not limited.
+    pub fn evaluate_with_geo_limit(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        limit: usize,
+    ) -> Result<RoaringBitmap> {
+        self.logged_evaluate(rtxn, index, Some(limit))
+    }
+
+    /// Returns the ids of the documents matching the filter in ascending order, the
+    /// `offset` first ids are skipped and at most `limit` ids are collected.
+    pub fn evaluate_sorted_vec(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<Vec<DocumentId>> {
+        let docids = self.evaluate(rtxn, index)?;
+        let limit = limit.unwrap_or(usize::MAX);
+        Ok(docids.into_iter().skip(offset).take(limit).collect())
+    }
+
+    /// Evaluates the filter and returns, along with the matching documents, the databases
+    /// read by each operation of the filter, described and in the order of the filter.
+    pub fn evaluate_with_stats(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+    ) -> Result<(RoaringBitmap, Vec<(String, OperationStats)>)> {
+        let mut counts = MatchCounts::default();
+        let docids =
+            self.root_evaluate(rtxn, index, &mut HashMap::new(), None, Some(&mut counts))?;
+        Ok((docids, counts.operations))
+    }
+
+    /// Evaluates the filter and returns, along with the matching documents, the number of
+    /// documents matched by the operations of each attribute, summed when an attribute is
+    /// used by several operations. The geo operations are counted under the `_geo` attribute,
+    /// the attributes that aren't in the fields ids map are not returned.
+    pub fn evaluate_with_field_stats(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+    ) -> Result<(RoaringBitmap, HashMap<FieldId, u64>)> {
+        let mut counts = MatchCounts::default();
+        let docids =
+            self.root_evaluate(rtxn, index, &mut HashMap::new(), None, Some(&mut counts))?;
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let fields = counts
+            .attributes
+            .into_iter()
+            .filter_map(|(name, count)| Some((fields_ids_map.id(&name)?, count)))
+            .collect();
+        Ok((docids, fields))
+    }
+
+    /// Evaluates the filter and returns, along with the matching documents, the occurrences
+    /// of the patterns of its `~` operations in each facet value of the matching documents,
+    /// as byte ranges of the original value to highlight. The negated `NOT ~` are ignored.
+    ///
+    /// A facet value is stored once with one of its original spellings, i.e. `Ponce` or
+    /// `PONCE`, the highlights are made on this spelling.
+    pub fn evaluate_with_highlights(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+    ) -> Result<(RoaringBitmap, Vec<Highlight>)> {
+        let docids = self.evaluate(rtxn, index)?;
+
+        let mut operations = Vec::new();
+        Self::regex_operations(&self.condition, &mut operations);
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let filterable_fields = index.resolved_filterable_fields(rtxn)?;
+        let mut highlights = Vec::new();
+        for (fid, pattern) in operations {
+            let field_id = match fields_ids_map.id(fid.value()) {
+                Some(field_id) if filterable_fields.contains(fid.value()) => field_id,
+                _ => continue,
+            };
+            let regex = RegexBuilder::new(pattern.value())
+                .size_limit(MAX_REGEX_SIZE)
+                .build()
+                .map_err(|e| pattern.as_external_error(e))?;
+            let strings = FacetStringLevelZeroRange::new(
+                rtxn,
+                index.facet_id_string_docids,
+                field_id,
+                Unbounded,
+                Unbounded,
+            )?;
+            for result in strings {
+                let (normalized, original, values) = result?;
+                let values = values & &docids;
+                if values.is_empty() {
+                    continue;
+                }
+                // the empty occurrences, i.e. of `^`, have nothing to highlight
+                let offsets: Vec<_> = regex
+                    .find_iter(normalized)
+                    .filter(|found| found.start() != found.end())
+                    .map(|found| original_range(original, found.range()))
+                    .collect();
+                if !offsets.is_empty() {
+                    highlights.push(Highlight {
+                        field: fid.value().to_string(),
+                        original: original.to_string(),
+                        offsets,
+                        docids: values,
+                    });
+                }
+            }
+        }
+
+        Ok((docids, highlights))
+    }
+
+    /// Evaluates the filter and splits the matching documents among the buckets of its first
+    /// `IN_BUCKETS` operation, i.e. `price IN_BUCKETS [0, 10, 100]` splits them between the
+    /// prices from 0 to 10, excluded, and the prices from 10 to 100, included. The numbers
+    /// of the attribute within the edges are read in a single walk of the facet values.
+    ///
+    /// The bitmaps are returned in the order of the buckets, there is none when the filter
+    /// has no `IN_BUCKETS` operation.
+    pub fn evaluate_buckets(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+    ) -> Result<Vec<RoaringBitmap>> {
+        let docids = self.evaluate(rtxn, index)?;
+        let (fid, edges) = match Self::first_buckets(&self.condition) {
+            Some(buckets) => buckets,
+            None => return Ok(Vec::new()),
+        };
+        let edges = Self::parse_bucket_edges(edges)?;
+        let (first, last) = (edges[0], edges[edges.len() - 1]);
+
+        let mut buckets = vec![RoaringBitmap::new(); edges.len() - 1];
+        let field_id = match index.fields_ids_map(rtxn)?.id(fid.value()) {
+            Some(field_id) => field_id,
+            None => return Ok(buckets),
+        };
+        let numbers = FacetNumberRange::new(
+            rtxn,
+            index.facet_id_f64_docids,
+            field_id,
+            0,
+            Included(first),
+            Included(last),
+        )?;
+        for result in numbers {
+            let ((_, _, value, _), values) = result?;
+            // the last edge is part of the last bucket
+            let bucket = edges.partition_point(|edge| *edge <= value).min(edges.len() - 1) - 1;
+            buckets[bucket] |= values & &docids;
+        }
+        Ok(buckets)
+    }
+
+    /// Returns the attribute and the edges of the first `IN_BUCKETS` operation of the filter.
+    fn first_buckets<'f>(
+        condition: &'f FilterCondition<'a>,
+    ) -> Option<(&'f Token<'a>, &'f [Token<'a>])> {
+        match condition {
+            FilterCondition::Condition { fid, op: Condition::InBuckets(edges) } => {
+                Some((fid, edges))
+            }
+            FilterCondition::Or(lhs, rhs) | FilterCondition::And(lhs, rhs) => {
+                Self::first_buckets(lhs).or_else(|| Self::first_buckets(rhs))
+            }
+            _ => None,
+        }
+    }
+
+    /// Collects the attribute and the pattern of the `~` operations of the filter.
+    fn regex_operations<'f>(
+        condition: &'f FilterCondition<'a>,
+        operations: &mut Vec<(&'f Token<'a>, &'f Token<'a>)>,
+    ) {
+        match condition {
+            FilterCondition::Condition { fid, op: Condition::Regex(pattern) } => {
+                operations.push((fid, pattern))
+            }
+            FilterCondition::Or(lhs, rhs) | FilterCondition::And(lhs, rhs) => {
+                Self::regex_operations(lhs, operations);
+                Self::regex_operations(rhs, operations);
+            }
+            _ => (),
+        }
+    }
+
+    fn logged_evaluate(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        geo_limit: Option<usize>,
+    ) -> Result<RoaringBitmap> {
+        if !log_enabled!(target: EVALUATION_LOG_TARGET, Level::Debug) {
+            return self.root_evaluate(rtxn, index, &mut HashMap::new(), geo_limit, None);
+        }
+
+        let before = Instant::now();
+        let mut counts = MatchCounts::default();
+        let docids =
+            self.root_evaluate(rtxn, index, &mut HashMap::new(), geo_limit, Some(&mut counts))?;
+        let mut summary = String::new();
+        for (depth, label, count) in counts.nodes {
+            let _ = write!(
+                summary,
+                "\n{:indent$}{} ({} documents)",
+                "",
+                label,
+                count,
+                indent = depth * 2
+            );
+        }
+        debug!(target: EVALUATION_LOG_TARGET, "filter evaluated in {:.2?}:{}", before.elapsed(), summary);
+        Ok(docids)
+    }
+
+    /// Prepares the filter to be evaluated many times on the given index,
+    /// see [`CompiledFilter`].
+    pub fn compile(self, rtxn: &heed::RoTxn, index: &'a Index) -> Result<CompiledFilter<'a>> {
+        let filterable_fields = index.resolved_filterable_fields(rtxn)?;
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let mut biggest_levels = HashMap::new();
+
+        let mut conditions = vec![&self.condition];
+        while let Some(condition) = conditions.pop() {
+            match condition {
+                FilterCondition::Condition { fid, .. } => {
+                    if !filterable_fields.contains(fid.value()) {
+                        continue;
+                    }
+                    if let Some(field_id) = fields_ids_map.id(fid.value()) {
+                        if let Entry::Vacant(entry) = biggest_levels.entry(field_id) {
+                            let numbers_db = index.facet_id_f64_docids;
+                            entry.insert(Self::biggest_level(rtxn, numbers_db, field_id)?);
+                        }
+                    }
+                }
+                FilterCondition::Or(lhs, rhs) | FilterCondition::And(lhs, rhs) => {
+                    conditions.push(lhs);
+                    conditions.push(rhs);
+                }
+                _ => (),
+            }
+        }
+
+        Ok(CompiledFilter { filter: self, index, biggest_levels })
+    }
+
+    /// Estimates the fraction of the documents, between `0.0` and `1.0`, matched by the filter
+    /// without evaluating it. It only relies on the number of documents of each attribute,
+    /// on the exact lookups of the `=` and `IN` values and on the smallest and biggest numbers
+    /// of the attribute for the ranges, assuming the numbers are uniformly distributed.
+    ///
+    /// This is a rough estimate meant to compare filters, the operands of the `AND`s and
+    /// `OR`s are assumed to be independent and the operations that must go through all the
+    /// values (`~`, `HAS`, `IS EMPTY`, the geo filters...) are assumed to match half of the
+    /// documents they can match.
+    pub fn estimate_selectivity(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<f64> {
+        let total = index.number_of_documents(rtxn)? as f64;
+        if total == 0. {
+            return Ok(0.);
+        }
+        Self::estimate_node(&self.condition, rtxn, index, total)
+    }
+
+    fn estimate_node(
+        condition: &FilterCondition<'a>,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        total: f64,
+    ) -> Result<f64> {
+        let numbers_db = index.facet_id_f64_docids;
+        let strings_db = index.facet_id_string_docids;
+        let ratio = |count: u64| count as f64 / total;
+
+        let estimate = match condition {
+            FilterCondition::Or(lhs, rhs) => {
+                let lhs = Self::estimate_node(lhs, rtxn, index, total)?;
+                let rhs = Self::estimate_node(rhs, rtxn, index, total)?;
+                lhs + rhs - lhs * rhs
+            }
+            FilterCondition::And(lhs, rhs) => {
+                let lhs = Self::estimate_node(lhs, rtxn, index, total)?;
+                lhs * Self::estimate_node(rhs, rtxn, index, total)?
+            }
+            FilterCondition::GeoLowerThan { .. }
+            | FilterCondition::GeoGreaterThan { .. }
+            | FilterCondition::GeoInsidePolygon { .. }
+            | FilterCondition::GeoOutsidePolygon { .. }
+            | FilterCondition::Condition { op: Condition::GeoDistance { .. }, .. } => {
+                ratio(index.geo_faceted_documents_ids(rtxn)?.len()) / 2.
+            }
+            FilterCondition::Condition { fid, op } if fid.value() == "_geo" => {
+                let geo = ratio(index.geo_faceted_documents_ids(rtxn)?.len());
+                match op {
+                    Condition::NotExists => 1. - geo,
+                    _ => geo,
+                }
+            }
+            FilterCondition::Condition { fid, op } => {
+                let field_id = match index.fields_ids_map(rtxn)?.id(fid.value()) {
+                    Some(field_id) => field_id,
+                    // no document contains this attribute
+                    None if matches!(op, Condition::NotExists | Condition::IsNotEmpty) => {
+                        return Ok(1.)
+                    }
+                    None => return Ok(0.),
+                };
+                let numbers = ratio(index.number_faceted_documents_ids(rtxn, field_id)?.len());
+                let strings = ratio(index.string_faceted_documents_ids(rtxn, field_id)?.len());
+                let exists = (numbers + strings).min(1.);
+
+                // the exact number of documents containing one of the values
+                let equal = |values: &[Token<'a>], numeric_only: bool| -> Result<f64> {
+                    let mut count = 0;
+                    for value in values {
+                        if !numeric_only {
+                            let normalized = value.to_lowercase();
+                            if let Some((_, docids)) =
+                                strings_db.get(rtxn, &(field_id, &normalized))?
+                            {
+                                count += docids.len();
+                            }
+                        }
+                        if let Ok(n) = value.parse::<f64>() {
+                            if let Some(docids) = numbers_db.get(rtxn, &(field_id, 0, n, n))? {
+                                count += docids.len();
+                            }
+                        }
+                    }
+                    Ok(ratio(count))
+                };
+                let range = |condition: &Condition<'a>| -> Result<f64> {
+                    let bounds = condition.numeric_bounds()?;
+                    match bounds.expect("only called with numeric ranges") {
+                        // a range of a single value, i.e. `price 10 TO 10`
+                        (Included(from), Included(to)) if from == to => {
+                            let docids = numbers_db.get(rtxn, &(field_id, 0, from, to))?;
+                            Ok(ratio(docids.map_or(0, |docids| docids.len())))
+                        }
+                        (left, right) => {
+                            let fraction =
+                                numeric_range_fraction(rtxn, numbers_db, field_id, left, right)?;
+                            Ok(fraction * numbers)
+                        }
+                    }
+                };
+
+                match op {
+                    Condition::Exists => exists,
+                    Condition::NotExists => 1. - exists,
+                    Condition::Equal(value) => equal(std::slice::from_ref(value), false)?,
+                    Condition::NumericEqual(value) => equal(std::slice::from_ref(value), true)?,
+                    Condition::In(values) => equal(values, false)?,
+                    Condition::NotEqual(value) => {
+                        exists - equal(std::slice::from_ref(value), false)?
+                    }
+                    Condition::NumericNotEqual(value) => {
+                        numbers - equal(std::slice::from_ref(value), true)?
+                    }
+                    Condition::NotIn(values) => exists - equal(values, false)?,
+                    Condition::NotBetween { from, to } => {
+                        numbers - range(&Condition::Between { from: from.clone(), to: to.clone() })?
+                    }
+                    op @ (Condition::GreaterThan(_)
+                    | Condition::GreaterThanOrEqual(_)
+                    | Condition::LowerThan(_)
+                    | Condition::LowerThanOrEqual(_)
+                    | Condition::Between { .. }
+                    | Condition::InBuckets(_)) => range(op)?,
+                    Condition::NotRegex(_) => strings / 2.,
+                    Condition::IsNotEmpty => 1. - exists / 2.,
+                    Condition::Regex(_)
+                    | Condition::SemVer(_)
+                    | Condition::WordsCount(_)
+                    | Condition::CharsCount(_)
+                    | Condition::FieldComparison(_)
+                    | Condition::Position { .. }
+                    | Condition::GeoDistance { .. }
+                    | Condition::ValuesCount(_)
+                    | Condition::IsEmpty => exists / 2.,
+                }
+            }
+        };
+
+        Ok(estimate.max(0.).min(1.))
+    }
+
+    /// Evaluates a batch of filters against the same index, the biggest facet level
+    /// of the fields is only retrieved once for all the filters of the batch.
+    ///
+    /// The bitmaps are returned in the same order as the filters.
+    pub fn evaluate_many(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        filters: &[Self],
+    ) -> Result<Vec<RoaringBitmap>> {
+        let mut biggest_levels = HashMap::new();
+        filters
+            .iter()
+            .map(|filter| filter.root_evaluate(rtxn, index, &mut biggest_levels, None, None))
+            .collect()
+    }
+
+    /// Evaluates the filter but takes the documents matched by its operations from the
+    /// cache when they are there, and caches the ones it evaluates, the operations shared
+    /// by several filters are only evaluated once. The operations are keyed by their field
+    /// id and condition, the geo filters and the unknown attributes are not cached.
+    ///
+    /// The cache is only valid for the read transaction it was filled with and must only
+    /// be shared by filters having the same options.
+    pub fn evaluate_with_external_cache(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        cache: &mut HashMap<(FieldId, Condition<'a>), RoaringBitmap>,
+    ) -> Result<RoaringBitmap> {
+        if self.is_trivially_empty(rtxn, index)? {
+            return Ok(RoaringBitmap::new());
+        }
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        self.cached_evaluate(rtxn, index, &fields_ids_map, &mut HashMap::new(), cache)
+    }
+
+    /// Returns the documents located within each of the radii around the base point, the
+    /// `_geo` rtree is loaded once and traversed a single time for all the radii.
+    ///
+    /// The bitmaps are returned in the same order as the radii.
+    pub fn evaluate_geo_radii(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        base_point: [f64; 2],
+        radii: &[f64],
+    ) -> Result<Vec<RoaringBitmap>> {
+        let filterable_fields = index.resolved_filterable_fields(rtxn)?;
+        let error = if !filterable_fields.contains("_geo") {
+            Some(FilterError::not_filterable("_geo", filterable_fields))
+        } else if !(-90.0..=90.0).contains(&base_point[0]) {
+            Some(FilterError::BadGeoLat(base_point[0]))
+        } else if !(-180.0..=180.0).contains(&base_point[1]) {
+            Some(FilterError::BadGeoLng(base_point[1]))
+        } else {
+            None
+        };
+        if let Some(error) = error {
+            return Err(error.into());
+        }
+
+        match index.geo_rtree(rtxn)? {
+            Some(rtree) => Ok(documents_within_radii(&rtree, &base_point, radii, usize::MAX)),
+            None => Ok(vec![RoaringBitmap::new(); radii.len()]),
+        }
+    }
+
+    /// Returns the documents located in the box going from the `top_left` to the `bottom_right`
+    /// corner with their distance, in meters, to the reference point (i.e. the center of the
+    /// box), from the nearest to the farthest document.
+    ///
+    /// A box whose left longitude is greater than its right one crosses the 180th meridian.
+    pub fn evaluate_geo_bounding_box(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        top_left: [f64; 2],
+        bottom_right: [f64; 2],
+        reference: [f64; 2],
+    ) -> Result<Vec<(DocumentId, f64)>> {
+        let filterable_fields = index.resolved_filterable_fields(rtxn)?;
+        let error = if !filterable_fields.contains("_geo") {
+            Some(FilterError::not_filterable("_geo", filterable_fields))
+        } else {
+            [top_left, bottom_right, reference].iter().find_map(|[lat, lng]| {
+                if !(-90.0..=90.0).contains(lat) {
+                    Some(FilterError::BadGeoLat(*lat))
+                } else if !(-180.0..=180.0).contains(lng) {
+                    Some(FilterError::BadGeoLng(*lng))
+                } else {
+                    None
+                }
+            })
+        };
+        if let Some(error) = error {
+            return Err(error.into());
+        }
+
+        let rtree = match index.geo_rtree(rtxn)? {
+            Some(rtree) => rtree,
+            None => return Ok(Vec::new()),
+        };
+
+        let ([top, left], [bottom, right]) = (top_left, bottom_right);
+        let crosses_antimeridian = left > right;
+        let contains = |[lat, lng]: [f64; 2]| {
+            let within_lng = if crosses_antimeridian {
+                left <= lng || lng <= right
+            } else {
+                left <= lng && lng <= right
+            };
+            bottom <= lat && lat <= top && within_lng
+        };
+        // The envelope of a box crossing the 180th meridian would cover the other side of the earth.
+        let points: Box<dyn Iterator<Item = &GeoPoint>> = if crosses_antimeridian {
+            Box::new(rtree.iter())
+        } else {
+            Box::new(rtree.locate_in_envelope(&polygon_envelope(&[top_left, bottom_right])))
+        };
+
+        let mut documents: Vec<_> = points
+            .filter(|point| contains(point.data.1))
+            .map(|point| (point.data.0, distance_between_two_points(&reference, &point.data.1)))
+            .collect();
+        documents.sort_by(|(a, a_distance), (b, b_distance)| {
+            a_distance.partial_cmp(b_distance).unwrap_or(Ordering::Equal).then(a.cmp(b))
+        });
+        Ok(documents)
+    }
+
+    /// Checks that all the attributes of the filter are still filterable, a filter parsed
+    /// before a settings update may reference an attribute that is not filterable anymore.
+    pub fn revalidate(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<()> {
+        let filterable_fields = index.resolved_filterable_fields(rtxn)?;
+        match unfilterable_attribute(&self.condition, &filterable_fields) {
+            Some(attribute) => {
+                let error = FilterError::not_filterable(attribute, filterable_fields);
+                Err(error.into())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Removes the operations using an attribute that is not filterable anymore instead
+    /// of making the evaluation fail, the rest of the filter is kept.
+    ///
+    /// A removed operation is replaced by the identity of its parent: it matches every
+    /// document in an `AND` and no document in an `OR`, the other operands are applied as
+    /// if it wasn't there. It isn't symmetric, an `OR` whose operations are all removed
+    /// matches nothing, and so does the `AND` containing it, but an `AND` whose operations
+    /// are all removed matches everything. The whole filter is considered to be in an `AND`.
+    pub fn prune_unfilterable(self, rtxn: &heed::RoTxn, index: &Index) -> Result<PrunedFilter<'a>> {
+        let filterable_fields = index.resolved_filterable_fields(rtxn)?;
+        Ok(match prune_unfilterable(self.condition, &filterable_fields, false) {
+            PrunedFilter::Filter(filter) => {
+                PrunedFilter::Filter(Self { condition: filter.condition, ..self })
+            }
+            pruned => pruned,
+        })
+    }
+
+    /// Returns, for each distinct string value of the filterable field, the original value
+    /// and the `field = value` filter matching the documents with this value.
+    ///
+    /// The filters borrow the values from the transaction, they are in the facet values order.
+    pub fn equals_each(
+        rtxn: &'a heed::RoTxn,
+        index: &Index,
+        field: &'a str,
+    ) -> Result<Vec<(String, Self)>> {
+        let filterable_fields = index.resolved_filterable_fields(rtxn)?;
+        if !filterable_fields.contains(field) {
+            let error = FilterError::not_filterable(field, filterable_fields);
+            return Err(error.into());
+        }
+        let field_id = match index.fields_ids_map(rtxn)?.id(field) {
+            Some(field_id) => field_id,
+            None => return Ok(Vec::new()),
+        };
+
+        let strings = FacetStringLevelZeroRange::new(
+            rtxn,
+            index.facet_id_string_docids,
+            field_id,
+            Unbounded,
+            Unbounded,
+        )?;
+        let mut filters = Vec::new();
+        for result in strings {
+            let (normalized, original, _) = result?;
+            let condition = FilterCondition::Condition {
+                fid: Span::new_extra(field, field).into(),
+                op: Condition::Equal(Span::new_extra(normalized, normalized).into()),
+            };
+            filters.push((original.to_string(), Self::from(condition)));
+        }
+        Ok(filters)
+    }
+
+    /// Renders the filter as an indented tree, one node per line, like a query plan.
+    /// Each node is followed by the number of documents it matched.
+    ///
+    /// The facet levels explored to answer the numeric ranges are logged at the debug level.
+    pub fn explain(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<String> {
+        let mut output = String::new();
+        let mut evaluation = Some((rtxn, index, HashMap::new()));
+        self.explain_node(&self.condition, 0, &mut evaluation, &mut output)?;
+        Ok(output)
+    }
+
+    /// Renders the filter as an indented tree, one node per line, without evaluating it.
+    pub fn explain_structure(&self) -> String {
+        let mut output = String::new();
+        // Nothing can fail when the filter is not evaluated.
+        let _ = self.explain_node(&self.condition, 0, &mut None, &mut output);
+        output
+    }
+
+    /// Writes the node and its operands in the output and returns the documents
+    /// matched by the node if an evaluation context is provided.
+    fn explain_node(
+        &self,
+        condition: &FilterCondition<'a>,
+        depth: usize,
+        evaluation: &mut Option<(&heed::RoTxn, &Index, HashMap<FieldId, Option<u8>>)>,
+        output: &mut String,
+    ) -> Result<Option<RoaringBitmap>> {
+        let line = output.len();
+        let (label, documents) = match condition {
+            FilterCondition::Or(..) | FilterCondition::And(..) => {
+                let is_and = matches!(condition, FilterCondition::And(..));
+                let mut operands = Vec::new();
+                flatten_operands(condition, is_and, &mut operands);
+
+                let mut documents: Option<RoaringBitmap> = None;
+                for operand in operands {
+                    let operand = self.explain_node(operand, depth + 1, evaluation, output)?;
+                    documents = match (documents, operand) {
+                        (Some(acc), Some(operand)) if is_and => Some(acc & operand),
+                        (Some(acc), Some(operand)) => Some(acc | operand),
+                        (_, operand) => operand,
+                    };
+                }
+                (if is_and { "AND" } else { "OR" }.to_string(), documents)
+            }
+            condition => {
+                let documents = match evaluation {
+                    Some((rtxn, index, biggest_levels)) => {
+                        Some(self.sub_filter(condition).inner_evaluate(
+                            rtxn,
+                            index,
+                            biggest_levels,
+                            None,
+                        )?)
+                    }
+                    None => None,
+                };
+                (describe_condition(condition), documents)
+            }
+        };
+
+        let mut node = format!("{:indent$}{}", "", label, indent = depth * 2);
+        if let Some(documents) = &documents {
+            let _ = write!(node, " ({} documents)", documents.len());
+        }
+        node.push('\n');
+        output.insert_str(line, &node);
+
+        Ok(documents)
+    }
+
+    /// Evaluates the filter from its root, a filter that can't match any document
+    /// is only validated and none of its operations is evaluated nor counted.
+    ///
+    /// The operands of the `AND`s are evaluated from the cheapest to the most expensive,
+    /// see [`FilterCondition::optimize_order`], unless the operations are counted in which
+    /// case they are evaluated in the order they are written.
+    fn root_evaluate(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        biggest_levels: &mut HashMap<FieldId, Option<u8>>,
+        geo_limit: Option<usize>,
+        counts: Option<&mut MatchCounts>,
+    ) -> Result<RoaringBitmap> {
+        if self.is_trivially_empty(rtxn, index)? {
+            return Ok(RoaringBitmap::new());
+        }
+        match counts {
+            // the operations are evaluated one by one when the documents they match are counted
+            Some(counts) => self.counted_evaluate(
+                rtxn,
+                index,
+                biggest_levels,
+                geo_limit,
+                Some(counts),
+                &RangeUnions::Nothing,
+            ),
+            None => {
+                let optimized = self.sub_filter(&self.condition.clone().optimize_order());
+                let unions = optimized.range_unions(rtxn, index)?;
+                optimized.counted_evaluate(rtxn, index, biggest_levels, geo_limit, None, &unions)
+            }
+        }
+    }
+
+    /// Detects the `OR`s of numeric ranges on a single attribute in a single walk of the
+    /// filter, see [`Filter::evaluate_ranges_union`]. When the numbers are also searched in
+    /// the strings, see [`Filter::with_numeric_strings`], the ranges are evaluated one by one.
+    fn range_unions(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<RangeUnions<'_, 'a>> {
+        if self.numeric_strings
+            || !matches!(self.condition, FilterCondition::Or(..) | FilterCondition::And(..))
+        {
+            return Ok(RangeUnions::Nothing);
+        }
+        let filterable_fields = index.resolved_filterable_fields(rtxn)?;
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        Ok(RangeUnions::detect(&self.condition, &filterable_fields, &fields_ids_map))
+    }
+
+    /// Returns `true` if the filter can't match any document, see
+    /// [`FilterCondition::is_trivially_empty`], once its operations are validated like
+    /// when they are evaluated. The unresolved attributes can match every document and the
+    /// strict mode must check the values, these filters are always evaluated.
+    fn is_trivially_empty(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<bool> {
+        if self.unresolved_fields != UnresolvedFieldPolicy::MatchNothing
+            || self.strict_types
+            || !self.condition.is_trivially_empty()
+        {
+            return Ok(false);
+        }
+        let filterable_fields = index.resolved_filterable_fields(rtxn)?;
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        Self::validate_node(&self.condition, &filterable_fields, &fields_ids_map)?;
+        Ok(true)
+    }
+
+    /// Checks the attributes and parses the values of the operations in the order they are
+    /// evaluated, every operation goes through it before being evaluated and the filters
+    /// that can't match any document are only validated, without reading the facet databases.
+    fn validate_node(
+        condition: &FilterCondition<'a>,
+        filterable_fields: &HashSet<String>,
+        fields_ids_map: &FieldsIdsMap,
+    ) -> Result<()> {
+        let geo_filterable = |token: &Token<'a>| -> Result<()> {
+            if filterable_fields.contains("_geo") {
+                Ok(())
+            } else {
+                let error = FilterError::not_filterable("_geo", filterable_fields.clone());
+                Err(error.at(token))
+            }
+        };
+
+        match condition {
+            FilterCondition::Or(lhs, rhs) | FilterCondition::And(lhs, rhs) => {
+                Self::validate_node(lhs, filterable_fields, fields_ids_map)?;
+                Self::validate_node(rhs, filterable_fields, fields_ids_map)
+            }
+            FilterCondition::GeoLowerThan { point, radius }
+            | FilterCondition::GeoGreaterThan { point, radius } => {
+                geo_filterable(&point[0])?;
+                Self::parse_geo_point(point)?;
+                radius.parse::<f64>()?;
+                Ok(())
+            }
+            FilterCondition::GeoInsidePolygon { points }
+            | FilterCondition::GeoOutsidePolygon { points } => {
+                geo_filterable(&points[0][0])?;
+                points.iter().try_for_each(|point| Self::parse_geo_point(point).map(drop))
+            }
+            FilterCondition::Condition {
+                fid,
+                op: Condition::GeoDistance { point, comparison },
+            } => {
+                geo_filterable(fid)?;
+                Self::parse_geo_point(point)?;
+                Self::comparison_value(comparison).parse::<f64>()?;
+                Ok(())
+            }
+            FilterCondition::Condition { fid, op: Condition::Exists | Condition::NotExists }
+                if fid.value() == "_geo" =>
+            {
+                geo_filterable(fid)
+            }
+            FilterCondition::Condition { fid, .. } if fid.value() == "_geo" => {
+                Err(FilterError::BadGeo("_geo").at(fid))
+            }
+            FilterCondition::Condition { fid, op: Condition::Exists | Condition::NotExists }
+                if fid.wildcard_prefix().is_some() =>
+            {
+                Ok(())
+            }
+            FilterCondition::Condition { fid, op } if filterable_fields.contains(fid.value()) => {
+                // the operations on an attribute that no document contains are not evaluated
+                match fields_ids_map.id(fid.value()) {
+                    Some(_) => Self::validate_operator(op, filterable_fields),
+                    None => Ok(()),
+                }
+            }
+            FilterCondition::Condition { fid, op } if fid.value() == "_id" => {
+                Self::parse_internal_ids(fid, op).map(drop)
+            }
+            FilterCondition::Condition { fid, .. } => {
+                Err(Self::unfilterable_error(fid, filterable_fields.clone()))
+            }
+        }
+    }
+
+    /// Parses the values of an operation, [`Filter::evaluate_operator`] expects them to be valid.
+    fn validate_operator(op: &Condition<'a>, filterable_fields: &HashSet<String>) -> Result<()> {
+        match op {
+            Condition::Between { from, to } | Condition::NotBetween { from, to } => {
+                Self::check_range_bounds(from, to)?;
+                for bound in [from, to].iter().filter(|bound| !bound.is_wildcard()) {
+                    bound.parse_number()?;
+                }
+            }
+            Condition::InBuckets(edges) => {
+                Self::parse_bucket_edges(edges)?;
+            }
+            Condition::GreaterThan(value)
+            | Condition::GreaterThanOrEqual(value)
+            | Condition::LowerThan(value)
+            | Condition::LowerThanOrEqual(value)
+            | Condition::NumericEqual(value)
+            | Condition::NumericNotEqual(value) => {
+                value.parse_number()?;
+            }
+            Condition::Regex(pattern) | Condition::NotRegex(pattern) => {
+                RegexBuilder::new(pattern.value())
+                    .size_limit(MAX_REGEX_SIZE)
+                    .build()
+                    .map_err(|e| pattern.as_external_error(e))?;
+            }
+            Condition::SemVer(comparison) => {
+                let token = Self::comparison_value(comparison);
+                Version::parse(&token.value().trim().to_lowercase())
+                    .map_err(|e| token.as_external_error(e))?;
+            }
+            Condition::ValuesCount(comparison)
+            | Condition::WordsCount(comparison)
+            | Condition::CharsCount(comparison) => {
+                Self::compare_values_count(comparison, 0)?;
+            }
+            Condition::FieldComparison(comparison) => {
+                let other = Self::comparison_value(comparison);
+                if !filterable_fields.contains(other.value()) {
+                    let error = FilterError::not_filterable(**other, filterable_fields.clone());
+                    return Err(error.at(other));
+                }
+            }
+            Condition::Position { index: position, comparison } => {
+                position.parse::<usize>()?;
+                if !matches!(**comparison, Condition::Equal(_) | Condition::NotEqual(_)) {
+                    Self::comparison_value(comparison).parse_number()?;
+                }
+            }
+            Condition::Equal(_)
+            | Condition::NotEqual(_)
+            | Condition::Exists
+            | Condition::NotExists
+            | Condition::In(_)
+            | Condition::NotIn(_)
+            | Condition::IsEmpty
+            | Condition::IsNotEmpty
+            | Condition::GeoDistance { .. } => (),
+        }
+        Ok(())
+    }
+
+    /// Returns the value a simple comparison (`=`, `!=`, `>`, `>=`, `<` or `<=`) compares to.
+    fn comparison_value<'c>(comparison: &'c Condition<'a>) -> &'c Token<'a> {
+        match comparison {
+            Condition::GreaterThan(value)
+            | Condition::GreaterThanOrEqual(value)
+            | Condition::Equal(value)
+            | Condition::NotEqual(value)
+            | Condition::LowerThan(value)
+            | Condition::LowerThanOrEqual(value) => value,
+            _ => unreachable!("only the simple comparisons compare to a value"),
+        }
+    }
+
+    /// The error of an operation on an attribute that isn't filterable, the geo
+    /// attributes can only be used through the geo built-ins.
+    fn unfilterable_error(fid: &Token<'a>, filterable_fields: HashSet<String>) -> Error {
+        let error = match *fid.deref() {
+            attribute @ "_geo" => FilterError::BadGeo(attribute),
+            attribute if attribute.starts_with("_geoPoint(") => FilterError::BadGeo("_geoPoint"),
+            attribute @ "_geoDistance" => FilterError::Reserved(attribute),
+            attribute => FilterError::not_filterable(attribute, filterable_fields),
+        };
+        error.at(fid)
+    }
+
+    fn inner_evaluate(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        biggest_levels: &mut HashMap<FieldId, Option<u8>>,
+        geo_limit: Option<usize>,
+    ) -> Result<RoaringBitmap> {
+        self.counted_evaluate(rtxn, index, biggest_levels, geo_limit, None, &RangeUnions::Nothing)
+    }
+
+    fn cached_evaluate(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        fields_ids_map: &FieldsIdsMap,
+        biggest_levels: &mut HashMap<FieldId, Option<u8>>,
+        cache: &mut HashMap<(FieldId, Condition<'a>), RoaringBitmap>,
+    ) -> Result<RoaringBitmap> {
+        match &self.condition {
+            FilterCondition::Or(lhs, rhs) => {
+                let lhs = self.sub_filter(lhs);
+                let rhs = self.sub_filter(rhs);
+                Ok(lhs.cached_evaluate(rtxn, index, fields_ids_map, biggest_levels, cache)?
+                    | rhs.cached_evaluate(rtxn, index, fields_ids_map, biggest_levels, cache)?)
+            }
+            FilterCondition::And(lhs, rhs) => {
+                let lhs = self.sub_filter(lhs);
+                let rhs = self.sub_filter(rhs);
+                Ok(lhs.cached_evaluate(rtxn, index, fields_ids_map, biggest_levels, cache)?
+                    & rhs.cached_evaluate(rtxn, index, fields_ids_map, biggest_levels, cache)?)
+            }
+            FilterCondition::Condition { fid, op } => match fields_ids_map.id(fid.value()) {
+                Some(field_id) => match cache.entry((field_id, op.clone())) {
+                    Entry::Occupied(entry) => Ok(entry.get().clone()),
+                    Entry::Vacant(entry) => {
+                        let docids = self.inner_evaluate(rtxn, index, biggest_levels, None)?;
+                        Ok(entry.insert(docids).clone())
+                    }
+                },
+                None => self.inner_evaluate(rtxn, index, biggest_levels, None),
+            },
+            _ => self.inner_evaluate(rtxn, index, biggest_levels, None),
+        }
+    }
+
+    /// Evaluates the filter and, if asked to, records the number of documents
+    /// matched by each node of the filter during the same traversal.
+    fn counted_evaluate(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        biggest_levels: &mut HashMap<FieldId, Option<u8>>,
+        geo_limit: Option<usize>,
+        counts: Option<&mut MatchCounts>,
+        unions: &RangeUnions<'_, 'a>,
+    ) -> Result<RoaringBitmap> {
+        let mut stats = OperationStats::default();
+        let counts = match counts {
+            Some(counts) => counts,
+            None => {
+                return self.evaluate_node(
+                    rtxn,
+                    index,
+                    biggest_levels,
+                    geo_limit,
+                    None,
+                    unions,
+                    &mut stats,
+                )
+            }
+        };
+
+        let position = counts.nodes.len();
+        let description = describe_condition(&self.condition);
+        counts.nodes.push((counts.depth, description.clone(), 0));
+        counts.depth += 1;
+        let result = self.evaluate_node(
+            rtxn,
+            index,
+            biggest_levels,
+            geo_limit,
+            Some(&mut *counts),
+            unions,
+            &mut stats,
+        );
+        counts.depth -= 1;
+        let docids = result?;
+        counts.nodes[position].2 = docids.len();
+        if !matches!(self.condition, FilterCondition::Or(..) | FilterCondition::And(..)) {
+            counts.operations.push((description, stats));
+        }
+        if let Some(attribute) = operation_attribute(&self.condition) {
+            *counts.attributes.entry(attribute.to_string()).or_default() += docids.len();
+        }
+        Ok(docids)
+    }
+
+    fn evaluate_node(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        biggest_levels: &mut HashMap<FieldId, Option<u8>>,
+        geo_limit: Option<usize>,
+        mut counts: Option<&mut MatchCounts>,
+        unions: &RangeUnions<'_, 'a>,
+        stats: &mut OperationStats,
+    ) -> Result<RoaringBitmap> {
+        Self::check_stop_flag(self.stop)?;
+
+        if let (FilterCondition::Or(..), RangeUnions::Ranges { field_id, operands }) =
+            (&self.condition, unions)
+        {
+            return self.evaluate_ranges_union(
+                rtxn,
+                index,
+                *field_id,
+                operands,
+                biggest_levels,
+                stats,
+            );
+        }
+        // the operations are evaluated one by one when the documents they match are counted
+        if counts.is_none() && !self.numeric_strings {
+            if let Some(docids) = self.evaluate_geo_ring(rtxn, index, geo_limit, stats)? {
+                return Ok(docids);
+            }
+        }
+
+        let (lhs_unions, rhs_unions) = match unions {
+            RangeUnions::Node(lhs, rhs) => (&**lhs, &**rhs),
+            _ => (unions, unions),
+        };
+        match &self.condition {
+            FilterCondition::Or(lhs, rhs) => {
+                let lhs = self.sub_filter(lhs).counted_evaluate(
+                    rtxn,
+                    index,
+                    biggest_levels,
+                    geo_limit,
+                    counts.as_deref_mut(),
+                    lhs_unions,
+                )?;
+                let rhs = self.sub_filter(rhs).counted_evaluate(
+                    rtxn,
+                    index,
+                    biggest_levels,
+                    geo_limit,
+                    counts.as_deref_mut(),
+                    rhs_unions,
+                )?;
+                Ok(lhs | rhs)
+            }
+            FilterCondition::And(lhs, rhs) => {
+                let lhs = self.sub_filter(lhs).counted_evaluate(
+                    rtxn,
+                    index,
+                    biggest_levels,
+                    geo_limit,
+                    counts.as_deref_mut(),
+                    lhs_unions,
+                )?;
+                // Nothing can be added to an empty intersection, the other operand is only
+                // validated. It is still evaluated when the operations are counted or the
+                // types of their values checked.
+                if lhs.is_empty() && counts.is_none() && !self.strict_types {
+                    let filterable_fields = index.resolved_filterable_fields(rtxn)?;
+                    let fields_ids_map = index.fields_ids_map(rtxn)?;
+                    Self::validate_node(rhs, &filterable_fields, &fields_ids_map)?;
+                    return Ok(lhs);
+                }
+                let rhs = self.sub_filter(rhs).counted_evaluate(
+                    rtxn,
+                    index,
+                    biggest_levels,
+                    geo_limit,
+                    counts.as_deref_mut(),
+                    rhs_unions,
+                )?;
+                Ok(lhs & rhs)
+            }
+            condition => {
+                // the operations are validated by the same function as the filters that
+                // can't match any document, see `is_trivially_empty`, then evaluated
+                let filterable_fields = index.resolved_filterable_fields(rtxn)?;
+                let fields_ids_map = index.fields_ids_map(rtxn)?;
+                Self::validate_node(condition, &filterable_fields, &fields_ids_map)?;
+                self.evaluate_leaf(
+                    rtxn,
+                    index,
+                    &filterable_fields,
+                    &fields_ids_map,
+                    biggest_levels,
+                    geo_limit,
+                    stats,
+                )
+            }
+        }
+    }
+
+    /// Evaluates an operation of the filter, i.e. anything but an `AND` or an `OR`, that
+    /// has already been validated.
+    fn evaluate_leaf(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        filterable_fields: &HashSet<String>,
+        fields_ids_map: &FieldsIdsMap,
+        biggest_levels: &mut HashMap<FieldId, Option<u8>>,
+        geo_limit: Option<usize>,
+        stats: &mut OperationStats,
+    ) -> Result<RoaringBitmap> {
+        let numbers_db = index.facet_id_f64_docids;
+        let strings_db = index.facet_id_string_docids;
+
+        match &self.condition {
+            // The documents are visited from the nearest to the farthest of the point,
+            // the farthest ones are the complement of the nearest ones.
+            FilterCondition::Condition {
+                op: Condition::GeoDistance { point, comparison }, ..
+            } => {
+                let base_point = Self::parse_geo_point(point)?;
+                let (distance, inclusive, nearest) = match comparison.as_ref() {
+                    Condition::LowerThan(v) => (v, false, true),
+                    Condition::LowerThanOrEqual(v) => (v, true, true),
+                    Condition::GreaterThan(v) => (v, true, false),
+                    Condition::GreaterThanOrEqual(v) => (v, false, false),
+                    _ => unreachable!("the geo distances only support simple comparisons"),
+                };
+                let distance: f64 = distance.parse()?;
+                stats.geo_rtree = true;
+                let rtree = match index.geo_rtree(rtxn)? {
+                    Some(rtree) => rtree,
+                    None => return Ok(RoaringBitmap::new()),
+                };
+
+                let docids: RoaringBitmap = rtree
+                    .nearest_neighbor_iter(&lat_lng_to_xyz(&base_point))
+                    .map(|point| {
+                        (point.data.0, distance_between_two_points(&base_point, &point.data.1))
+                    })
+                    .take_while(|(_, d)| if inclusive { *d <= distance } else { *d < distance })
+                    .map(|(docid, _)| docid)
+                    .collect();
+                if nearest {
+                    Ok(docids)
+                } else {
+                    stats.faceted_documents = true;
+                    Ok(complement(index.geo_faceted_documents_ids(rtxn)?, docids))
+                }
+            }
+            // The coordinates are not facets, the other operations on `_geo` are rejected
+            // by the validation, even when `_geo` is filterable.
+            FilterCondition::Condition { fid, op } if fid.value() == "_geo" => {
+                let geo_faceted_doc_ids = index.geo_faceted_documents_ids(rtxn)?;
+                stats.faceted_documents = true;
+                match op {
+                    Condition::Exists => Ok(geo_faceted_doc_ids),
+                    _ => Ok(index.documents_ids(rtxn)? - geo_faceted_doc_ids),
+                }
+            }
+            // `* EXISTS` checks all the filterable attributes, `prefix* EXISTS`
+            // only the ones starting with the prefix.
+            FilterCondition::Condition {
+                fid,
+                op: op @ (Condition::Exists | Condition::NotExists),
+            } if fid.wildcard_prefix().is_some() => {
+                let prefix = fid.wildcard_prefix().unwrap_or_default();
+                let mut docids = RoaringBitmap::new();
+                stats.faceted_documents = true;
+                for field in filterable_fields.iter().filter(|field| field.starts_with(prefix)) {
+                    if field == "_geo" {
+                        docids |= index.geo_faceted_documents_ids(rtxn)?;
+                    } else if let Some(field_id) = fields_ids_map.id(field) {
+                        docids |= index.number_faceted_documents_ids(rtxn, field_id)?;
+                        docids |= index.string_faceted_documents_ids(rtxn, field_id)?;
+                    }
+                }
+                match op {
+                    Condition::Exists => Ok(docids),
+                    _ => Ok(complement(index.documents_ids(rtxn)?, docids)),
+                }
+            }
+            FilterCondition::Condition { fid, op } => {
+                if filterable_fields.contains(fid.value()) {
+                    if let Some(field_id) = fields_ids_map.id(fid.value()) {
+                        if self.strict_types {
+                            Self::check_strict_type(rtxn, index, fid, field_id, op)?;
+                        }
+                        let mut docids = Self::evaluate_operator(
+                            rtxn,
+                            index,
+                            numbers_db,
+                            strings_db,
+                            field_id,
+                            &op,
+                            biggest_levels,
+                            stats,
+                            self.stop,
+                        )?;
+                        if self.numeric_strings {
+                            docids |= Self::evaluate_numeric_strings(
+                                rtxn, strings_db, field_id, op, stats,
+                            )?;
+                        }
+                        Ok(docids)
+                    } else if matches!(op, Condition::NotExists | Condition::IsNotEmpty)
+                        || self.unresolved_fields == UnresolvedFieldPolicy::MatchEverything
+                    {
+                        // no document ever contained this field
+                        Ok(index.documents_ids(rtxn)?)
+                    } else {
+                        return Ok(RoaringBitmap::new());
+                    }
+                } else {
+                    // the only attribute that isn't filterable and passes the validation
+                    Self::evaluate_internal_ids(rtxn, index, fid, op)
+                }
+            }
+            FilterCondition::GeoLowerThan { point, radius } => {
+                let base_point = Self::parse_geo_point(point)?;
+                let radius = radius.parse()?;
+                // the documents must be strictly within the radius
+                if radius <= 0. {
+                    return Ok(RoaringBitmap::new());
+                }
+                stats.geo_rtree = true;
+                let rtree = match index.geo_rtree(rtxn)? {
+                    Some(rtree) => rtree,
+                    None => return Ok(RoaringBitmap::new()),
+                };
+
+                let limit = geo_limit.unwrap_or(usize::MAX);
+                let mut result = documents_within_radii(&rtree, &base_point, &[radius], limit);
+                Ok(result.pop().unwrap_or_default())
+            }
+            // The geo faceted documents are expected to be the documents of the rtree, the
+            // complement is computed against them: when the rtree is missing or empty, all
+            // the geo faceted documents are outside of the radius.
+            FilterCondition::GeoGreaterThan { point, radius } => {
+                let result = self
+                    .sub_filter(&FilterCondition::GeoLowerThan {
+                        point: point.clone(),
+                        radius: radius.clone(),
+                    })
+                    .inner_evaluate(rtxn, index, biggest_levels, None)?;
+                let geo_faceted_doc_ids = index.geo_faceted_documents_ids(rtxn)?;
+                stats.geo_rtree = true;
+                stats.faceted_documents = true;
+                Ok(complement(geo_faceted_doc_ids, result))
+            }
+            FilterCondition::GeoInsidePolygon { points } => {
+                let polygon =
+                    points.iter().map(Self::parse_geo_point).collect::<Result<Vec<_>>>()?;
+                stats.geo_rtree = true;
+                let rtree = match index.geo_rtree(rtxn)? {
+                    Some(rtree) => rtree,
+                    None => return Ok(RoaringBitmap::new()),
+                };
+
+                // We only run the point-in-polygon test on the points
+                // located in the bounding envelope of the polygon.
+                let result = rtree
+                    .locate_in_envelope(&polygon_envelope(&polygon))
+                    .filter(|point| polygon_contains(&polygon, &point.data.1))
+                    .map(|point| point.data.0)
+                    .collect();
+
+                Ok(result)
+            }
+            FilterCondition::GeoOutsidePolygon { points } => {
+                let result = self
+                    .sub_filter(&FilterCondition::GeoInsidePolygon { points: points.clone() })
+                    .inner_evaluate(rtxn, index, biggest_levels, None)?;
+                let geo_faceted_doc_ids = index.geo_faceted_documents_ids(rtxn)?;
+                stats.geo_rtree = true;
+                stats.faceted_documents = true;
+                Ok(complement(geo_faceted_doc_ids, result))
+            }
+            FilterCondition::Or(..) | FilterCondition::And(..) => {
+                unreachable!("the AND and OR are evaluated by evaluate_node")
+            }
+        }
+    }
+
+    /// Returns `true` if the document matches the filter, without evaluating it on all the
+    /// documents of the index. The `AND` and `OR` stop as soon as their outcome is known.
+    pub fn matches_document(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        docid: DocumentId,
+    ) -> Result<bool> {
+        match &self.condition {
+            FilterCondition::Condition { .. } => Ok(self.evaluate(rtxn, index)?.contains(docid)),
+            FilterCondition::Or(lhs, rhs) => {
+                Ok(self.sub_filter(lhs).matches_document(rtxn, index, docid)?
+                    || self.sub_filter(rhs).matches_document(rtxn, index, docid)?)
+            }
+            FilterCondition::And(lhs, rhs) => {
+                Ok(self.sub_filter(lhs).matches_document(rtxn, index, docid)?
+                    && self.sub_filter(rhs).matches_document(rtxn, index, docid)?)
+            }
+            FilterCondition::GeoLowerThan { point, radius }
+            | FilterCondition::GeoGreaterThan { point, radius } => {
+                let document_point = Self::document_geo_point(rtxn, index, &point[0], docid)?;
+                let base_point = Self::parse_geo_point(point)?;
+                let radius = radius.parse()?;
+                let inside = match document_point {
+                    Some(document_point) => {
+                        distance_between_two_points(&base_point, &document_point) < radius
+                    }
+                    None => return Ok(false),
+                };
+                Ok(inside == matches!(self.condition, FilterCondition::GeoLowerThan { .. }))
+            }
+            FilterCondition::GeoInsidePolygon { points }
+            | FilterCondition::GeoOutsidePolygon { points } => {
+                let document_point = Self::document_geo_point(rtxn, index, &points[0][0], docid)?;
+                let polygon =
+                    points.iter().map(Self::parse_geo_point).collect::<Result<Vec<_>>>()?;
+                let inside = match document_point {
+                    Some(document_point) => polygon_contains(&polygon, &document_point),
+                    None => return Ok(false),
+                };
+                Ok(inside == matches!(self.condition, FilterCondition::GeoInsidePolygon { .. }))
+            }
+        }
+    }
+
+    /// Returns the minimal part of the filter that made the document match, `None` if the
+    /// document doesn't match. An `OR` is replaced by its first matching branch while
+    /// an `AND` keeps all its clauses, the leaves are checked with [`Self::matches_document`].
+    pub fn match_trace(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        docid: DocumentId,
+    ) -> Result<Option<FilterCondition<'a>>> {
+        match &self.condition {
+            FilterCondition::Or(lhs, rhs) => {
+                match self.sub_filter(lhs).match_trace(rtxn, index, docid)? {
+                    Some(trace) => Ok(Some(trace)),
+                    None => self.sub_filter(rhs).match_trace(rtxn, index, docid),
+                }
+            }
+            FilterCondition::And(lhs, rhs) => {
+                let lhs = match self.sub_filter(lhs).match_trace(rtxn, index, docid)? {
+                    Some(trace) => trace,
+                    None => return Ok(None),
+                };
+                let rhs = match self.sub_filter(rhs).match_trace(rtxn, index, docid)? {
+                    Some(trace) => trace,
+                    None => return Ok(None),
+                };
+                Ok(Some(FilterCondition::And(Box::new(lhs), Box::new(rhs))))
+            }
+            condition => {
+                let matches = self.matches_document(rtxn, index, docid)?;
+                Ok(matches.then(|| condition.clone()))
+            }
+        }
+    }
+
+    /// Retrieves the coordinates of a document directly from its `_geo` field,
+    /// the token is used to report the error if `_geo` is not filterable.
+    fn document_geo_point(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        token: &Token<'a>,
+        docid: DocumentId,
+    ) -> Result<Option<[f64; 2]>> {
+        let filterable_fields = index.resolved_filterable_fields(rtxn)?;
+        if !filterable_fields.contains("_geo") {
+            return Err(FilterError::not_filterable("_geo", filterable_fields).at(token));
+        }
+        if !index.geo_faceted_documents_ids(rtxn)?.contains(docid) {
+            return Ok(None);
+        }
+
+        let geo_field_id = match index.fields_ids_map(rtxn)?.id("_geo") {
+            Some(field_id) => field_id,
+            None => return Ok(None),
+        };
+        let point = match index.documents(rtxn, Some(docid))?.pop() {
+            Some((_, document)) => match document.get(geo_field_id) {
+                Some(point) => {
+                    serde_json::from_slice::<Value>(point).map_err(InternalError::SerdeJson)?
+                }
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+
+        // the coordinates are read like when the document was indexed
+        Ok(geo_point_from_value(&point))
+    }
+
+    /// Parses a latitude and a longitude and makes sure they describe a valid point.
+    fn parse_geo_point(point: &[Token<'a>; 2]) -> Result<[f64; 2]> {
+        let base_point: [f64; 2] = [point[0].parse()?, point[1].parse()?];
+        if !(-90.0..=90.0).contains(&base_point[0]) {
+            return Err(FilterError::BadGeoLat(base_point[0]).at(&point[0]));
+        }
+        if !(-180.0..=180.0).contains(&base_point[1]) {
+            return Err(FilterError::BadGeoLng(base_point[1]).at(&point[1]));
+        }
+        Ok(base_point)
+    }
+}
+
+/// Returns the fraction of the numbers of the attribute that are within the bounds,
+/// assuming they are uniformly distributed between the smallest and the biggest one.
+fn numeric_range_fraction(
+    rtxn: &heed::RoTxn,
+    numbers_db: heed::Database<FacetLevelValueF64Codec, CboRoaringBitmapCodec>,
+    field_id: FieldId,
+    left: Bound<f64>,
+    right: Bound<f64>,
+) -> Result<f64> {
+    let numbers_db = numbers_db.remap_data_type::<DecodeIgnore>();
+    let level_zero = |key: Option<((FieldId, u8, f64, f64), ())>| {
+        key.filter(|((id, level, _, _), _)| *id == field_id && *level == 0)
+            .map(|((_, _, value, _), _)| value)
+    };
+    let min = level_zero(
+        numbers_db.get_greater_than_or_equal_to(rtxn, &(field_id, 0, f64::MIN, f64::MIN))?,
+    );
+    let max = level_zero(
+        numbers_db.get_lower_than_or_equal_to(rtxn, &(field_id, 0, f64::MAX, f64::MAX))?,
+    );
+    let (min, max) = match min.zip(max) {
+        Some(span) => span,
+        None => return Ok(0.),
+    };
+
+    let bound = |bound: Bound<f64>| match bound {
+        Included(value) | Excluded(value) => value,
+        Unbounded => unreachable!("the numeric bounds are always bounded"),
+    };
+    let (from, to) = (bound(left).max(min), bound(right).min(max));
+    if from > to {
+        Ok(0.)
+    } else if max == min {
+        // all the numbers are the same and within the range
+        Ok(1.)
+    } else {
+        Ok((to - from) / (max - min))
+    }
+}
+
+/// A filter prepared to be evaluated many times on the same index, the biggest facet
+/// level of the fields it uses are retrieved once when it is compiled.
+///
+/// It is tied to the schema and to the content of the index it was compiled for and
+/// must be compiled again after the settings or the documents of the index are updated.
+#[derive(Clone)]
+pub struct CompiledFilter<'a> {
+    filter: Filter<'a>,
+    index: &'a Index,
+    biggest_levels: HashMap<FieldId, Option<u8>>,
+}
+
+impl<'a> CompiledFilter<'a> {
+    pub fn evaluate(&self, rtxn: &heed::RoTxn) -> Result<RoaringBitmap> {
+        let mut biggest_levels = self.biggest_levels.clone();
+        self.filter.root_evaluate(rtxn, self.index, &mut biggest_levels, None, None)
+    }
+
+    pub fn filter(&self) -> &Filter<'a> {
+        &self.filter
+    }
+}
+
+/// Returns the documents located within each of the radii around the base point, at most
+/// `limit` documents, the nearest ones, are kept for each radius.
+///
+/// The points are yielded from the nearest to the farthest, therefore the documents within
+/// a radius are also within all the bigger ones and we can stop once the biggest is reached.
+fn documents_within_radii(
+    rtree: &RTree<GeoPoint>,
+    base_point: &[f64; 2],
+    radii: &[f64],
+    limit: usize,
+) -> Vec<RoaringBitmap> {
+    let mut sorted: Vec<_> = (0..radii.len()).collect();
+    sorted.sort_by(|a, b| radii[*a].partial_cmp(&radii[*b]).unwrap_or(Ordering::Equal));
+    let mut sorted = sorted.into_iter().peekable();
+
+    let mut results = vec![RoaringBitmap::new(); radii.len()];
+    let mut documents = RoaringBitmap::new();
+    for point in rtree.nearest_neighbor_iter(&lat_lng_to_xyz(base_point)) {
+        if documents.len() as usize >= limit {
+            break;
+        }
+        let distance = distance_between_two_points(base_point, &point.data.1);
+        while let Some(i) =
+            sorted.next_if(|i| distance.partial_cmp(&radii[*i]) != Some(Ordering::Less))
+        {
+            results[i] = documents.clone();
+        }
+        if sorted.peek().is_none() {
+            break;
+        }
+        documents.insert(point.data.0);
+    }
+
+    for i in sorted {
+        results[i] = documents.clone();
+    }
+    results
+}
+
+/// Returns the documents of the universe that are not matched, the bitmaps are not
+/// gone through when nothing is matched or when the whole universe is matched.
+fn complement(universe: RoaringBitmap, matched: RoaringBitmap) -> RoaringBitmap {
+    if matched.is_empty() {
+        universe
+    } else if matched.len() >= universe.len() && universe.is_subset(&matched) {
+        RoaringBitmap::new()
+    } else {
+        universe - matched
+    }
+}
+
+/// Returns `true` if the value is a string, or an array of strings, that is empty
+/// once normalized like the facet values.
+fn is_empty_string(value: &Value) -> bool {
+    match value {
+        Value::String(string) => string.trim().is_empty(),
+        Value::Array(values) => {
+            !values.is_empty()
+                && values.iter().all(|v| matches!(v, Value::String(s) if s.trim().is_empty()))
+        }
+        _ => false,
+    }
+}
+
+/// Collects the operands of a chain of `AND` (or `OR`) in the order they appear in the filter.
+fn flatten_operands<'c, 'a>(
+    condition: &'c FilterCondition<'a>,
+    is_and: bool,
+    operands: &mut Vec<&'c FilterCondition<'a>>,
+) {
+    match condition {
+        FilterCondition::And(lhs, rhs) if is_and => {
+            flatten_operands(lhs, is_and, operands);
+            flatten_operands(rhs, is_and, operands);
+        }
+        FilterCondition::Or(lhs, rhs) if !is_and => {
+            flatten_operands(lhs, is_and, operands);
+            flatten_operands(rhs, is_and, operands);
+        }
+        condition => operands.push(condition),
+    }
+}
+
+/// The log target of the number of documents matched by each operation of the evaluated
+/// filters, see [`Filter::evaluate`].
+pub const EVALUATION_LOG_TARGET: &str = "milli::filter::evaluation";
+
+/// The number of documents matched by each node of a filter, in the order of the filter.
+#[derive(Default)]
+struct MatchCounts {
+    /// The depth of the node being evaluated.
+    depth: usize,
+    /// The depth, description and number of documents of each node.
+    nodes: Vec<(usize, String, u64)>,
+    /// The description and databases read of each operation, the `AND`s and `OR`s excluded.
+    operations: Vec<(String, OperationStats)>,
+    /// The number of documents matched by the operations of each attribute.
+    attributes: HashMap<String, u64>,
+}
+
+/// What an operation of a filter read to be evaluated, see [`Filter::evaluate_with_stats`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OperationStats {
+    /// Whether the facet numbers database was read.
+    pub numbers_db: bool,
+    /// Whether the facet strings database was read.
+    pub strings_db: bool,
+    /// Whether the geo rtree was read.
+    pub geo_rtree: bool,
+    /// Whether the sets of documents having a facet value of a field were read.
+    pub faceted_documents: bool,
+    /// The number of keys read from the facet databases, a lookup counts as a single key.
+    pub scanned_keys: u64,
+}
+
+/// The occurrences of the pattern of a `~` operation in a facet value,
+/// see [`Filter::evaluate_with_highlights`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Highlight {
+    /// The attribute of the operation.
+    pub field: String,
+    /// The facet value as it was written in the documents.
+    pub original: String,
+    /// The byte ranges of the occurrences in the original value.
+    pub offsets: Vec<Range<usize>>,
+    /// The matching documents having this value.
+    pub docids: RoaringBitmap,
+}
+
+/// Maps a byte range of the normalized, trimmed and lowercased, value back to the original
+/// value. The lowercase of a character isn't always of the same length (`İ` gives `i̇`),
+/// a range starting or ending within the lowercase of a character covers the whole character.
+fn original_range(original: &str, range: Range<usize>) -> Range<usize> {
+    let trimmed = original.trim_start();
+    let shift = original.len() - trimmed.len();
+    let mut start = None;
+    let mut normalized = 0;
+    for (i, c) in trimmed.char_indices() {
+        let next = normalized + c.to_lowercase().map(char::len_utf8).sum::<usize>();
+        if start.is_none() && range.start < next {
+            start = Some(shift + i);
+        }
+        if range.end <= next {
+            return start.unwrap_or(shift + i)..shift + i + c.len_utf8();
+        }
+        normalized = next;
+    }
+    start.unwrap_or(original.len())..original.len()
+}
+
+/// Returns the attribute an operation is evaluated on, `_geo` for the geo operations and
+/// `None` for the `AND`s and `OR`s.
+fn operation_attribute<'c>(condition: &'c FilterCondition) -> Option<&'c str> {
+    match condition {
+        FilterCondition::Condition { op: Condition::GeoDistance { .. }, .. } => Some("_geo"),
+        FilterCondition::Condition { fid, .. } => Some(fid.value()),
+        FilterCondition::GeoLowerThan { .. }
+        | FilterCondition::GeoGreaterThan { .. }
+        | FilterCondition::GeoInsidePolygon { .. }
+        | FilterCondition::GeoOutsidePolygon { .. } => Some("_geo"),
+        FilterCondition::Or(..) | FilterCondition::And(..) => None,
+    }
+}
+
+/// A human-friendly description of a node that is neither an `AND` nor an `OR`.
+fn describe_condition(condition: &FilterCondition) -> String {
+    let point = |point: &[Token; 2]| format!("{}, {}", point[0].value(), point[1].value());
+    let polygon = |points: &[[Token; 2]]| points.iter().map(point).collect::<Vec<_>>().join(", ");
+
+    match condition {
+        FilterCondition::Condition { fid, op: op @ Condition::GeoDistance { .. } } => {
+            format!("{}{}", fid.value(), describe_operation(op))
+        }
+        FilterCondition::Condition { fid, op } => {
+            format!("{} {}", fid.value(), describe_operation(op))
+        }
+        FilterCondition::GeoLowerThan { point: p, radius } => {
+            format!("_geoRadius({}, {})", point(p), radius.value())
+        }
+        FilterCondition::GeoGreaterThan { point: p, radius } => {
+            format!("NOT _geoRadius({}, {})", point(p), radius.value())
+        }
+        FilterCondition::GeoInsidePolygon { points } => format!("_geoPolygon({})", polygon(points)),
+        FilterCondition::GeoOutsidePolygon { points } => {
+            format!("NOT _geoPolygon({})", polygon(points))
+        }
+        FilterCondition::Or(..) => "OR".to_string(),
+        FilterCondition::And(..) => "AND".to_string(),
+    }
+}
+
+fn describe_operation(op: &Condition) -> String {
+    let list = |values: &[Token]| values.iter().map(|v| v.value()).collect::<Vec<_>>().join(", ");
+
+    match op {
+        Condition::GreaterThan(value) => format!("> {}", value.value()),
+        Condition::GreaterThanOrEqual(value) => format!(">= {}", value.value()),
+        Condition::Equal(value) => format!("= {}", value.value()),
+        Condition::NumericEqual(value) => format!("=# {}", value.value()),
+        Condition::NotEqual(value) => format!("!= {}", value.value()),
+        Condition::NumericNotEqual(value) => format!("!=# {}", value.value()),
+        Condition::LowerThan(value) => format!("< {}", value.value()),
+        Condition::LowerThanOrEqual(value) => format!("<= {}", value.value()),
+        Condition::Between { from, to } => format!("{} TO {}", from.value(), to.value()),
+        Condition::NotBetween { from, to } => format!("NOT {} TO {}", from.value(), to.value()),
+        Condition::Exists => "EXISTS".to_string(),
+        Condition::NotExists => "NOT EXISTS".to_string(),
+        Condition::In(values) => format!("IN [{}]", list(values)),
+        Condition::NotIn(values) => format!("NOT IN [{}]", list(values)),
+        Condition::InBuckets(edges) => format!("IN_BUCKETS [{}]", list(edges)),
+        Condition::IsEmpty => "IS EMPTY".to_string(),
+        Condition::IsNotEmpty => "IS NOT EMPTY".to_string(),
+        Condition::Regex(pattern) => format!("~ {}", pattern.value()),
+        Condition::NotRegex(pattern) => format!("NOT ~ {}", pattern.value()),
+        Condition::ValuesCount(comparison) => {
+            format!("HAS {} VALUES", describe_operation(comparison))
+        }
+        Condition::SemVer(comparison) => format!("SEMVER {}", describe_operation(comparison)),
+        Condition::WordsCount(comparison) => format!("LENGTH {}", describe_operation(comparison)),
+        Condition::CharsCount(comparison) => format!("CHARS {}", describe_operation(comparison)),
+        Condition::FieldComparison(comparison) => {
+            describe_operation(comparison).replacen(' ', " @", 1)
+        }
+        Condition::Position { index, comparison } => {
+            format!("[{}] {}", index.value(), describe_operation(comparison))
+        }
+        Condition::GeoDistance { point, comparison } => {
+            let [lat, lng] = point;
+            format!("({}, {}) {}", lat.value(), lng.value(), describe_operation(comparison))
+        }
+    }
+}
+
+/// The `OR`s of numeric ranges (`<`, `<=`, `>`, `>=` or `TO`) on a single attribute of a
+/// filter, detected once from its root and following the shape of the filter. They are
+/// evaluated at once by [`Filter::evaluate_ranges_union`].
+enum RangeUnions<'f, 'a> {
+    /// The node is a numeric range or an `OR` of numeric ranges on the attribute,
+    /// the attribute being filterable and known to the fields ids map.
+    Ranges { field_id: FieldId, operands: Vec<(&'f Token<'a>, &'f Condition<'a>)> },
+    /// The node is an `AND` or an `OR`, its operands can contain unions.
+    Node(Box<Self>, Box<Self>),
+    /// The node is any other operation.
+    Nothing,
+}
+
+impl<'f, 'a> RangeUnions<'f, 'a> {
+    fn detect(
+        condition: &'f FilterCondition<'a>,
+        filterable_fields: &HashSet<String>,
+        fields_ids_map: &FieldsIdsMap,
+    ) -> Self {
+        match condition {
+            FilterCondition::Or(lhs, rhs) | FilterCondition::And(lhs, rhs) => {
+                let lhs = Self::detect(lhs, filterable_fields, fields_ids_map);
+                let rhs = Self::detect(rhs, filterable_fields, fields_ids_map);
+                match (lhs, rhs) {
+                    (
+                        RangeUnions::Ranges { field_id, mut operands },
+                        RangeUnions::Ranges { field_id: other_id, operands: others },
+                    ) if field_id == other_id && matches!(condition, FilterCondition::Or(..)) => {
+                        operands.extend(others);
+                        RangeUnions::Ranges { field_id, operands }
+                    }
+                    (lhs, rhs) => RangeUnions::Node(Box::new(lhs), Box::new(rhs)),
+                }
+            }
+            FilterCondition::Condition {
+                fid,
+                op:
+                    op @ (Condition::GreaterThan(_)
+                    | Condition::GreaterThanOrEqual(_)
+                    | Condition::LowerThan(_)
+                    | Condition::LowerThanOrEqual(_)
+                    | Condition::Between { .. }),
+            } if filterable_fields.contains(fid.value()) => match fields_ids_map.id(fid.value()) {
+                Some(field_id) => RangeUnions::Ranges { field_id, operands: vec![(fid, op)] },
+                None => RangeUnions::Nothing,
+            },
+            _ => RangeUnions::Nothing,
+        }
+    }
+}
+
+/// Sorts the numeric ranges and merges the ones that overlap or are adjacent,
+/// i.e. `0 TO 10` and `> 10`, the merged ranges cover the same numbers.
+fn merge_ranges(mut ranges: Vec<NumericBounds>) -> Vec<NumericBounds> {
+    let value = |bound: &Bound<f64>| match bound {
+        Included(n) | Excluded(n) => *n,
+        Unbounded => unreachable!("the numeric bounds are always bounded"),
+    };
+    // the ranges starting at the same number are sorted with the included bound first
+    ranges.sort_by(|(a, _), (b, _)| {
+        value(a)
+            .partial_cmp(&value(b))
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| matches!(b, Included(_)).cmp(&matches!(a, Included(_))))
+    });
+
+    let mut merged: Vec<NumericBounds> = Vec::with_capacity(ranges.len());
+    for (left, right) in ranges {
+        if let Some((_, last_right)) = merged.last_mut() {
+            let (start, end) = (value(&left), value(last_right));
+            let touches = start < end
+                || (start == end
+                    && (matches!(left, Included(_)) || matches!(last_right, Included(_))));
+            if touches {
+                let (new_end, last_end) = (value(&right), value(last_right));
+                if new_end > last_end || (new_end == last_end && matches!(right, Included(_))) {
+                    *last_right = right;
+                }
+                continue;
+            }
+        }
+        merged.push((left, right));
+    }
+    merged
+}
+
+/// The semantic versions precedence ignores the build metadata,
+/// unlike the `Ord` implementation of the `Version` type.
+fn semver_precedence(version: &Version) -> (u64, u64, u64, &Prerelease) {
+    (version.major, version.minor, version.patch, &version.pre)
+}
+
+fn collect_warnings<'a>(condition: &FilterCondition<'a>, warnings: &mut Vec<FilterWarning<'a>>) {
+    match condition {
+        FilterCondition::Condition { fid, op } => {
+            let attribute = **fid;
+            let (operator, value) = match op {
+                Condition::Equal(value) => ("=", value),
+                Condition::NotEqual(value) => ("!=", value),
+                Condition::NotBetween { from, to } => {
+                    warnings.push(FilterWarning::NegatedRange {
+                        attribute,
+                        from: **from,
+                        to: **to,
+                    });
+                    return;
+                }
+                _ => return,
+            };
+            if value.parse::<f64>().is_ok() {
+                let value = **value;
+                warnings.push(FilterWarning::ImplicitStringUnion { attribute, operator, value });
+            }
+        }
+        FilterCondition::Or(lhs, rhs) | FilterCondition::And(lhs, rhs) => {
+            collect_warnings(lhs, warnings);
+            collect_warnings(rhs, warnings);
+        }
+        _ => (),
+    }
+}
+
+/// Returns the first attribute of the condition that is not filterable, if any.
+fn unfilterable_attribute<'a>(
+    condition: &FilterCondition<'a>,
+    filterable_fields: &HashSet<String>,
+) -> Option<&'a str> {
+    match condition {
+        // the wildcards only match the filterable attributes
+        FilterCondition::Condition { fid, op: Condition::Exists | Condition::NotExists }
+            if fid.wildcard_prefix().is_some() =>
+        {
+            None
+        }
+        // the internal ids can always be filtered
+        FilterCondition::Condition { fid, .. } if fid.value() == "_id" => None,
+        FilterCondition::Condition { op: Condition::GeoDistance { .. }, .. } => {
+            (!filterable_fields.contains("_geo")).then(|| "_geo")
+        }
+        FilterCondition::Condition { fid, .. } => {
+            (!filterable_fields.contains(fid.value())).then(|| **fid)
+        }
+        FilterCondition::Or(lhs, rhs) | FilterCondition::And(lhs, rhs) => {
+            unfilterable_attribute(lhs, filterable_fields)
+                .or_else(|| unfilterable_attribute(rhs, filterable_fields))
+        }
+        FilterCondition::GeoLowerThan { .. }
+        | FilterCondition::GeoGreaterThan { .. }
+        | FilterCondition::GeoInsidePolygon { .. }
+        | FilterCondition::GeoOutsidePolygon { .. } => {
+            (!filterable_fields.contains("_geo")).then(|| "_geo")
+        }
+    }
+}
+
+/// Removes the operations using an attribute that is not filterable from the condition,
+/// see [`Filter::prune_unfilterable`].
+fn prune_unfilterable<'a>(
+    condition: FilterCondition<'a>,
+    filterable_fields: &HashSet<String>,
+    in_or: bool,
+) -> PrunedFilter<'a> {
+    use PrunedFilter::{Everything, Nothing};
+
+    match condition {
+        FilterCondition::Or(lhs, rhs) => {
+            let lhs = prune_unfilterable(*lhs, filterable_fields, true);
+            let rhs = prune_unfilterable(*rhs, filterable_fields, true);
+            match (lhs, rhs) {
+                (Everything, _) | (_, Everything) => Everything,
+                (Nothing, pruned) | (pruned, Nothing) => pruned,
+                (PrunedFilter::Filter(lhs), PrunedFilter::Filter(rhs)) => {
+                    let condition = FilterCondition::Or(lhs.condition.into(), rhs.condition.into());
+                    PrunedFilter::Filter(condition.into())
+                }
+            }
+        }
+        FilterCondition::And(lhs, rhs) => {
+            let lhs = prune_unfilterable(*lhs, filterable_fields, false);
+            let rhs = prune_unfilterable(*rhs, filterable_fields, false);
+            match (lhs, rhs) {
+                (Nothing, _) | (_, Nothing) => Nothing,
+                (Everything, pruned) | (pruned, Everything) => pruned,
+                (PrunedFilter::Filter(lhs), PrunedFilter::Filter(rhs)) => {
+                    let condition =
+                        FilterCondition::And(lhs.condition.into(), rhs.condition.into());
+                    PrunedFilter::Filter(condition.into())
+                }
+            }
+        }
+        condition => match unfilterable_attribute(&condition, filterable_fields) {
+            Some(_) if in_or => Nothing,
+            Some(_) => Everything,
+            None => PrunedFilter::Filter(condition.into()),
+        },
+    }
+}
+
+/// Returns an envelope of the cartesian space that contains every point of the sphere
+/// whose latitude and longitude fall in the bounding box of the given polygon.
+fn polygon_envelope(polygon: &[[f64; 2]]) -> AABB<[f64; 3]> {
+    let min_max = |(min, max): (f64, f64), value: f64| (min.min(value), max.max(value));
+    let (min_lat, max_lat) =
+        polygon.iter().map(|[lat, _]| lat.to_radians()).fold((f64::MAX, f64::MIN), min_max);
+    let (min_lng, max_lng) =
+        polygon.iter().map(|[_, lng]| lng.to_radians()).fold((f64::MAX, f64::MIN), min_max);
+
+    // The latitudes are between -90 and 90 degrees, their cosine is always positive
+    // and reaches its maximum when the latitude is zero.
+    let min_cos_lat = min_lat.cos().min(max_lat.cos());
+    let max_cos_lat =
+        if min_lat <= 0.0 && 0.0 <= max_lat { 1.0 } else { min_lat.cos().max(max_lat.cos()) };
+
+    // The longitudes are between -180 and 180 degrees, the cosine reaches its maximum
+    // when the longitude is zero, the sine reaches its extremums at -90 and 90 degrees.
+    let contains = |angle: f64| min_lng <= angle && angle <= max_lng;
+    let min_cos_lng = min_lng.cos().min(max_lng.cos());
+    let max_cos_lng = if contains(0.0) { 1.0 } else { min_lng.cos().max(max_lng.cos()) };
+    let min_sin_lng =
+        if contains(-90f64.to_radians()) { -1.0 } else { min_lng.sin().min(max_lng.sin()) };
+    let max_sin_lng =
+        if contains(90f64.to_radians()) { 1.0 } else { min_lng.sin().max(max_lng.sin()) };
+
+    // x = cos(lat) * cos(lng), y = cos(lat) * sin(lng) and z = sin(lat)
+    let products = |min: f64, max: f64| {
+        [min_cos_lat * min, min_cos_lat * max, max_cos_lat * min, max_cos_lat * max]
+            .iter()
+            .fold((f64::MAX, f64::MIN), |acc, value| min_max(acc, *value))
+    };
+    let (min_x, max_x) = products(min_cos_lng, max_cos_lng);
+    let (min_y, max_y) = products(min_sin_lng, max_sin_lng);
+
+    AABB::from_corners([min_x, min_y, min_lat.sin()], [max_x, max_y, max_lat.sin()])
+}
+
+/// Returns `true` if the point is inside the polygon, the edges of the polygon
+/// being straight lines between the latitudes and longitudes of its points.
+fn polygon_contains(polygon: &[[f64; 2]], [lat, lng]: &[f64; 2]) -> bool {
+    let mut inside = false;
+    let mut previous = polygon[polygon.len() - 1];
+    for &current in polygon {
+        let ([lat_a, lng_a], [lat_b, lng_b]) = (current, previous);
+        // We cast a ray from the point along its latitude and
+        // count the number of edges of the polygon it crosses.
+        if (lat_a > *lat) != (lat_b > *lat)
+            && *lng < (lng_b - lng_a) * (lat - lat_a) / (lat_b - lat_a) + lng_a
+        {
+            inside = !inside;
+        }
+        previous = current;
+    }
+    inside
+}
+
+impl<'a> From<FilterCondition<'a>> for Filter<'a> {
+    fn from(fc: FilterCondition<'a>) -> Self {
+        Self {
+            condition: fc,
+            unresolved_fields: UnresolvedFieldPolicy::default(),
+            numeric_strings: false,
+            lng_lat: false,
+            strict_types: false,
+            stop: None,
+        }
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use std::fmt::Write;
+    use std::io::Cursor;
+    use std::iter::FromIterator;
+
+    use big_s::S;
+    use either::Either;
+    use heed::types::ByteSlice;
+    use maplit::{hashmap, hashset};
+    use serde_json::json;
+
+    use super::*;
+    use crate::documents::DocumentBatchReader;
+    use crate::index::tests::TempIndex;
+    use crate::update::{IndexDocuments, IndexDocumentsConfig, IndexerConfig, Settings};
+
+    /// Returns an empty index with an `id` primary key and the given filterable fields.
+    fn filterable_index(filterable_fields: HashSet<String>) -> TempIndex {
+        let index = TempIndex::new_with_map_size(10 * 1024 * 1024); // 10 MB
+
+        let config = IndexerConfig::default();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_primary_key(S("id"));
+        builder.set_filterable_fields(filterable_fields);
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        index
+    }
+
+    /// Returns an index like [`filterable_index`] with the given documents added to it.
+    fn index_with_documents(
+        filterable_fields: HashSet<String>,
+        documents: DocumentBatchReader<Cursor<Vec<u8>>>,
+    ) -> TempIndex {
+        let index = filterable_index(filterable_fields);
+
+        let config = IndexerConfig::default();
+        let mut wtxn = index.write_txn().unwrap();
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(documents).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        index
+    }
+
+    #[test]
+    fn empty_db() {
+        let index = TempIndex::new_with_map_size(10 * 1024 * 1024); // 10 MB
+
+        // Set the filterable fields to be the channel.
+        let config = IndexerConfig::default();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_searchable_fields(vec![S("PrIcE")]); // to keep the fields order
+        builder.set_filterable_fields(hashset! { S("PrIcE") });
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str("PrIcE < 1000").unwrap().unwrap();
+        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
+        assert!(bitmap.is_empty());
+
+        let filter = Filter::from_str("NOT PrIcE >= 1000").unwrap().unwrap();
+        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
+        assert!(bitmap.is_empty());
+    }
+
+    #[test]
+    fn from_array() {
+        // Simple array with Left
+        let condition = Filter::from_array(vec![Either::Left(["channel = mv"])]).unwrap().unwrap();
+        let expected = Filter::from_str("channel = mv").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Simple array with Right
+        let condition = Filter::from_array::<_, Option<&str>>(vec![Either::Right("channel = mv")])
+            .unwrap()
+            .unwrap();
+        let expected = Filter::from_str("channel = mv").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Array with Left and escaped quote
+        let condition =
+            Filter::from_array(vec![Either::Left(["channel = \"Mister Mv\""])]).unwrap().unwrap();
+        let expected = Filter::from_str("channel = \"Mister Mv\"").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Array with Right and escaped quote
+        let condition =
+            Filter::from_array::<_, Option<&str>>(vec![Either::Right("channel = \"Mister Mv\"")])
+                .unwrap()
+                .unwrap();
+        let expected = Filter::from_str("channel = \"Mister Mv\"").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Array with Left and escaped simple quote
+        let condition =
+            Filter::from_array(vec![Either::Left(["channel = 'Mister Mv'"])]).unwrap().unwrap();
+        let expected = Filter::from_str("channel = 'Mister Mv'").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Array with Right and escaped simple quote
+        let condition =
+            Filter::from_array::<_, Option<&str>>(vec![Either::Right("channel = 'Mister Mv'")])
+                .unwrap()
+                .unwrap();
+        let expected = Filter::from_str("channel = 'Mister Mv'").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Simple with parenthesis
+        let condition =
+            Filter::from_array(vec![Either::Left(["(channel = mv)"])]).unwrap().unwrap();
+        let expected = Filter::from_str("(channel = mv)").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Test that the facet condition is correctly generated.
+        let condition = Filter::from_array(vec![
+            Either::Right("channel = gotaga"),
+            Either::Left(vec!["timestamp = 44", "channel != ponce"]),
+        ])
+        .unwrap()
+        .unwrap();
+        let expected =
+            Filter::from_str("channel = gotaga AND (timestamp = 44 OR channel != ponce)")
+                .unwrap()
+                .unwrap();
+        println!("\nExpecting: {:#?}\nGot: {:#?}\n", expected, condition);
+        assert_eq!(condition, expected);
+    }
+
+    #[test]
+    fn from_array_lenient() {
+        let index = TempIndex::new_with_map_size(10 * 1024 * 1024); // 10 MB
+
+        let config = IndexerConfig::default();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_filterable_fields(hashset! { S("channel"), S("timestamp") });
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let (filter, warnings) = Filter::from_array_lenient(
+            &rtxn,
+            &index,
+            vec![
+                Either::Right("channel = gotaga"),
+                Either::Right("dog = bernese"),
+                Either::Left(vec!["timestamp = 44", "_geoRadius(12, 13, 14)", "channel != ponce"]),
+            ],
+        )
+        .unwrap();
+        let expected = Filter::from_array(vec![
+            Either::Right("channel = gotaga"),
+            Either::Left(vec!["timestamp = 44", "channel != ponce"]),
+        ])
+        .unwrap();
+        assert_eq!(filter, expected);
+
+        let warnings: Vec<_> = warnings.iter().map(ToString::to_string).collect();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("Attribute `dog` is not filterable."), "{}", warnings[0]);
+        assert!(warnings[1].starts_with("Attribute `_geo` is not filterable."), "{}", warnings[1]);
+
+        // the syntax errors are still fatal
+        let result = Filter::from_array_lenient(
+            &rtxn,
+            &index,
+            vec![Either::<Vec<_>, _>::Right("dog = bernese"), Either::Right("channel =")],
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn from_json() {
+        // Simple string
+        let value = json!("channel = mv");
+        let condition = Filter::from_json(&value).unwrap().unwrap();
+        let expected = Filter::from_str("channel = mv").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Simple array with a nested array
+        let value = json!([["channel = mv"]]);
+        let condition = Filter::from_json(&value).unwrap().unwrap();
+        let expected = Filter::from_str("channel = mv").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Simple array with a string
+        let value = json!(["channel = mv"]);
+        let condition = Filter::from_json(&value).unwrap().unwrap();
+        let expected = Filter::from_str("channel = mv").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Array with a nested array and escaped quote
+        let value = json!([["channel = \"Mister Mv\""]]);
+        let condition = Filter::from_json(&value).unwrap().unwrap();
+        let expected = Filter::from_str("channel = \"Mister Mv\"").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Array with a string and escaped simple quote
+        let value = json!(["channel = 'Mister Mv'"]);
+        let condition = Filter::from_json(&value).unwrap().unwrap();
+        let expected = Filter::from_str("channel = 'Mister Mv'").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Test that the facet condition is correctly generated.
+        let value = json!(["channel = gotaga", ["timestamp = 44", "channel != ponce"]]);
+        let condition = Filter::from_json(&value).unwrap().unwrap();
+        let expected =
+            Filter::from_str("channel = gotaga AND (timestamp = 44 OR channel != ponce)")
+                .unwrap()
+                .unwrap();
+        assert_eq!(condition, expected);
+
+        // Null and empty arrays are no filter at all
+        assert_eq!(Filter::from_json(&json!(null)).unwrap(), None);
+        assert_eq!(Filter::from_json(&json!([])).unwrap(), None);
+        assert_eq!(Filter::from_json(&json!([[]])).unwrap(), None);
+
+        // Leaves that are neither strings nor arrays are reported with their JSON pointer
+        let value = json!(["channel = gotaga", ["timestamp = 44", 12]]);
+        let error = Filter::from_json(&value).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Invalid filter at `/1/1`: expected a string or an array of strings but instead got `12`."
+        ));
+
+        let value = json!(["channel = gotaga", { "timestamp": 44 }]);
+        let error = Filter::from_json(&value).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Invalid filter at `/1`: expected a string or an array of strings but instead got `{\"timestamp\":44}`."
+        ));
+
+        let value = json!([[["channel = gotaga"]]]);
+        let error = Filter::from_json(&value).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Invalid filter at `/0/0`: expected a string or an array of strings but instead got `[\"channel = gotaga\"]`."
+        ));
+
+        let value = json!(true);
+        let error = Filter::from_json(&value).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Invalid filter at ``: expected a string or an array of strings but instead got `true`."
+        ));
+
+        // Syntax errors are still reported by the parser
+        let value = json!([["channel = "]]);
+        let error = Filter::from_json(&value).unwrap_err();
+        assert!(error.to_string().starts_with("Was expecting a value but instead got nothing."));
+    }
+
+    #[test]
+    fn not_filterable() {
+        let index = TempIndex::new_with_map_size(10 * 1024 * 1024); // 10 MB
+
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("_geoRadius(42, 150, 10)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Attribute `_geo` is not filterable. No attribute is filterable yet, you must configure the filterable attributes of the index first."
+        ));
+
+        let filter = Filter::from_str("dog = \"bernese mountain\"").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Attribute `dog` is not filterable. No attribute is filterable yet, you must configure the filterable attributes of the index first."
+        ));
+
+        let filter = Filter::from_str("_geoPolygon(0, 0, 0, 10, 10, 0)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Attribute `_geo` is not filterable. No attribute is filterable yet, you must configure the filterable attributes of the index first."
+        ));
+        drop(rtxn);
+
+        let config = IndexerConfig::default();
+        // Set the filterable fields to be the channel.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_searchable_fields(vec![S("title")]);
+        builder.set_filterable_fields(hashset! { S("title") });
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str("_geoRadius(-100, 150, 10)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Attribute `_geo` is not filterable. Available filterable attributes are: `title`."
+        ));
+
+        let filter = Filter::from_str("name = 12").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Attribute `name` is not filterable. Available filterable attributes are: `title`."
+        ));
+    }
+
+    #[test]
+    fn filterable_wildcard() {
+        let content = documents!([
+            { "id": 0, "color": "red", "price": 5 },
+            { "id": 1, "color": "blue", "price": 15 },
+            { "id": 2, "size": "xl" }
+        ]);
+        let index = index_with_documents(hashset! { S("*") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        // the settings are kept as they were set
+        assert_eq!(index.filterable_fields(&rtxn).unwrap(), hashset! { S("*") });
+        assert_eq!(
+            index.resolved_filterable_fields(&rtxn).unwrap(),
+            hashset! { S("id"), S("color"), S("price"), S("size") }
+        );
+
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).map(|docids| docids.into_iter().collect::<Vec<_>>())
+        };
+        // all the fields of the index are filterable
+        assert_eq!(evaluate("color = red").unwrap(), vec![0]);
+        assert_eq!(evaluate("price > 10").unwrap(), vec![1]);
+        assert_eq!(evaluate("size EXISTS OR id = 0").unwrap(), vec![0, 2]);
+
+        // but a field that no document contains is unknown
+        let error = evaluate("weight = 12").unwrap_err();
+        assert!(
+            error.to_string().starts_with("Attribute `weight` is not filterable."),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn prune_unfilterable() {
+        let index = TempIndex::new_with_map_size(10 * 1024 * 1024); // 10 MB
+
+        let config = IndexerConfig::default();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_primary_key(S("id"));
+        builder.set_filterable_fields(hashset! { S("color"), S("price") });
+        builder.execute(|_| ()).unwrap();
+
+        let content = documents!([
+            { "id": 0, "color": "red", "price": 5 },
+            { "id": 1, "color": "blue", "price": 15 },
+            { "id": 2, "color": "red", "price": 25 }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+
+        // the price isn't filterable anymore
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_filterable_fields(hashset! { S("color") });
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let prune = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.prune_unfilterable(&rtxn, &index).unwrap()
+        };
+        let kept = |filter| PrunedFilter::Filter(Filter::from_str(filter).unwrap().unwrap());
+
+        // the removed operations are ignored by their parent
+        assert_eq!(prune("color = red AND price > 10"), kept("color = red"));
+        assert_eq!(prune("price > 10 OR color = red"), kept("color = red"));
+        assert_eq!(
+            prune("(color = red OR price > 10) AND (price < 2 AND color != blue)"),
+            kept("color = red AND color != blue")
+        );
+        assert_eq!(prune("color = red"), kept("color = red"));
+        // an AND whose operations are all removed matches everything
+        assert_eq!(prune("price > 10"), PrunedFilter::Everything);
+        assert_eq!(prune("price > 10 AND price < 20"), PrunedFilter::Everything);
+        assert_eq!(prune("color = red OR (price > 10 AND price < 20)"), PrunedFilter::Everything);
+        // but an OR whose operations are all removed matches nothing
+        assert_eq!(prune("price > 10 OR price < 2"), PrunedFilter::Nothing);
+        assert_eq!(prune("color = red AND (price > 10 OR price < 2)"), PrunedFilter::Nothing);
+
+        // the options of the filter are kept and the pruned filter can be evaluated
+        let filter = Filter::from_str("color = red AND price > 10").unwrap().unwrap();
+        let filter = filter.with_unresolved_field_policy(UnresolvedFieldPolicy::MatchEverything);
+        assert!(filter.evaluate(&rtxn, &index).is_err());
+        let pruned = match filter.prune_unfilterable(&rtxn, &index).unwrap() {
+            PrunedFilter::Filter(pruned) => pruned,
+            pruned => panic!("{:?}", pruned),
+        };
+        assert_eq!(pruned.unresolved_fields, UnresolvedFieldPolicy::MatchEverything);
+        assert_eq!(
+            pruned.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+    }
+
+    #[test]
+    fn geo_radius_error() {
+        let index = TempIndex::new_with_map_size(10 * 1024 * 1024); // 10 MB
+
+        let config = IndexerConfig::default();
+        // Set the filterable fields to be the channel.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_searchable_fields(vec![S("_geo"), S("price")]); // to keep the fields order
+        builder.set_filterable_fields(hashset! { S("_geo"), S("price") });
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        // georadius have a bad latitude
+        let filter = Filter::from_str("_geoRadius(-100, 150, 10)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(
+            error.to_string().starts_with(
+                "Bad latitude `-100`. Latitude must be contained between -90 and 90 degrees."
+            ),
+            "{}",
+            error.to_string()
+        );
+
+        // georadius have a bad latitude
+        let filter = Filter::from_str("_geoRadius(-90.0000001, 150, 10)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Bad latitude `-90.0000001`. Latitude must be contained between -90 and 90 degrees."
+        ));
+
+        // georadius have a bad longitude
+        let filter = Filter::from_str("_geoRadius(-10, 250, 10)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(
+            error.to_string().contains(
+                "Bad longitude `250`. Longitude must be contained between -180 and 180 degrees."
+            ),
+            "{}",
+            error.to_string(),
+        );
+
+        // georadius have a bad longitude
+        let filter = Filter::from_str("_geoRadius(-10, 180.000001, 10)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Bad longitude `180.000001`. Longitude must be contained between -180 and 180 degrees."
+        ));
+    }
+
+    #[test]
+    fn geo_misuse() {
+        let content = documents!([
+            { "id": 0, "tag": "red", "_geo": { "lat": 0.0, "lng": 0.0 } },
+            { "id": 1, "tag": "red", "_geo": { "lat": 10.0, "lng": 0.0 } },
+            { "id": 2, "tag": "red" },
+            { "id": 3, "tag": "blue", "_geo": { "lat": 10.0, "lng": 0.0 } }
+        ]);
+        let index = index_with_documents(hashset! { S("_geo"), S("tag") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).map(|docids| docids.into_iter().collect::<Vec<_>>())
+        };
+
+        // the comparison operators can't be used on `_geo` even if it is filterable
+        for filter in
+            ["_geo > 5", "_geo = 12", "_geo 1 TO 2", "_geo IN [1, 2]", "tag = red OR _geo != 1"]
+        {
+            let error = evaluate(filter).unwrap_err();
+            assert!(
+                error.to_string().starts_with("`_geo` is a reserved keyword and thus can't be used as a filter expression. Use the _geoRadius(latitude, longitude, distance) built-in rule"),
+                "{}: {}",
+                filter,
+                error
+            );
+        }
+        assert_eq!(evaluate("_geo EXISTS").unwrap(), vec![0, 1, 3]);
+
+        // the documents without coordinates are never within or outside of a radius
+        assert_eq!(evaluate("tag = red AND NOT _geoRadius(0, 0, 1000)").unwrap(), vec![1]);
+        assert_eq!(evaluate("NOT (tag = red AND NOT _geoRadius(0, 0, 1000))").unwrap(), vec![0, 3]);
+        assert_eq!(evaluate("NOT (NOT _geoRadius(0, 0, 1000))").unwrap(), vec![0]);
+
+        // the contradictions and the zero radii don't read the rtree
+        let stats = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let (docids, stats) = filter.evaluate_with_stats(&rtxn, &index).unwrap();
+            (docids.into_iter().collect::<Vec<_>>(), stats)
+        };
+        let (docids, operations) = stats("_geoRadius(0, 0, 1000) AND NOT _geoRadius(0.0, 0, 1000)");
+        assert_eq!(docids, Vec::<u32>::new());
+        assert!(operations.is_empty());
+        let (docids, operations) = stats("_geoRadius(0, 0, 0)");
+        assert_eq!(docids, Vec::<u32>::new());
+        assert!(!operations[0].1.geo_rtree);
+        assert_eq!(evaluate("NOT _geoRadius(0, 0, 0)").unwrap(), vec![0, 1, 3]);
+        // only the exact same point and radius are a contradiction
+        let (docids, operations) = stats("_geoRadius(0, 0, 1000) AND NOT _geoRadius(0.1, 0, 1000)");
+        assert_eq!(docids, vec![0]);
+        assert!(operations.iter().all(|(_, stats)| stats.geo_rtree));
+        // the attributes of a contradiction are still checked
+        let error = evaluate("color = red AND color != red").unwrap_err();
+        assert!(error.to_string().starts_with("Attribute `color` is not filterable."), "{}", error);
+        // and so are their values
+        for filter in [
+            "tag = red AND _geoRadius(100, 0, 0)",
+            "_geoRadius(100, 0, 10) AND NOT _geoRadius(100, 0, 10)",
+        ] {
+            let error = evaluate(filter).unwrap_err();
+            assert!(error.to_string().starts_with("Bad latitude `100`."), "{}: {}", filter, error);
+        }
+        let error = evaluate("tag = red AND tag > ten AND _geoRadius(0, 0, 0)").unwrap_err();
+        assert!(error.to_string().starts_with("invalid float literal"), "{}", error);
+    }
+
+    #[test]
+    fn geo_radius_limit() {
+        let content = documents!([
+            { "id": 0, "price": 1, "_geo": { "lat": 0.3, "lng": 0.0 } },
+            { "id": 1, "price": 1, "_geo": { "lat": 0.1, "lng": 0.0 } },
+            { "id": 2, "price": 1, "_geo": { "lat": 0.4, "lng": 0.0 } },
+            { "id": 3, "price": 1, "_geo": { "lat": 0.2, "lng": 0.0 } },
+            { "id": 4, "price": 1, "_geo": { "lat": 10.0, "lng": 0.0 } }
+        ]);
+        let index = index_with_documents(hashset! { S("_geo"), S("price") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+
+        // the documents 0 to 3 are in the radius, the nearest ones are 1 then 3
+        let filter = Filter::from_str("_geoRadius(0, 0, 100000)").unwrap().unwrap();
+        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(bitmap.into_iter().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+        let bitmap = filter.evaluate_with_geo_limit(&rtxn, &index, 2).unwrap();
+        assert_eq!(bitmap.into_iter().collect::<Vec<_>>(), vec![1, 3]);
+
+        // the limit is applied to each _geoRadius
+        let filter =
+            Filter::from_str("price = 1 AND _geoRadius(0, 0, 100000) OR _geoRadius(10, 0, 10)")
+                .unwrap()
+                .unwrap();
+        let bitmap = filter.evaluate_with_geo_limit(&rtxn, &index, 1).unwrap();
+        assert_eq!(bitmap.into_iter().collect::<Vec<_>>(), vec![1, 4]);
+
+        // but not to their complement
+        let filter = Filter::from_str("NOT _geoRadius(0, 0, 100000)").unwrap().unwrap();
+        let bitmap = filter.evaluate_with_geo_limit(&rtxn, &index, 1).unwrap();
+        assert_eq!(bitmap.into_iter().collect::<Vec<_>>(), vec![4]);
+    }
+
+    #[test]
+    fn geo_empty_rtree() {
+        let content = documents!([
+            { "id": 0, "_geo": { "lat": 0.1, "lng": 0.0 } },
+            { "id": 1, "_geo": { "lat": 10.0, "lng": 0.0 } },
+            { "id": 2 }
+        ]);
+        let index = index_with_documents(hashset! { S("_geo") }, content);
+
+        let evaluate = |filter| {
+            let rtxn = index.read_txn().unwrap();
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+        assert_eq!(evaluate("_geoRadius(0, 0, 100000)"), vec![0]);
+        assert_eq!(evaluate("NOT _geoRadius(0, 0, 100000)"), vec![1]);
+
+        // an empty rtree contains no point, all the geo faceted documents are outside
+        let mut wtxn = index.write_txn().unwrap();
+        index.put_geo_rtree(&mut wtxn, &RTree::new()).unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(evaluate("_geoRadius(0, 0, 100000)"), Vec::<u32>::new());
+        assert_eq!(evaluate("NOT _geoRadius(0, 0, 100000)"), vec![0, 1]);
+
+        // same thing without any rtree
+        let mut wtxn = index.write_txn().unwrap();
+        index.delete_geo_rtree(&mut wtxn).unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(evaluate("_geoRadius(0, 0, 100000)"), Vec::<u32>::new());
+        assert_eq!(evaluate("NOT _geoRadius(0, 0, 100000)"), vec![0, 1]);
+
+        // the complement never goes beyond the geo faceted documents
+        let mut wtxn = index.write_txn().unwrap();
+        index.put_geo_faceted_documents_ids(&mut wtxn, &RoaringBitmap::new()).unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(evaluate("NOT _geoRadius(0, 0, 100000)"), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn geo_ring() {
+        // a tenth of a degree of latitude is around 11km
+        let content = documents!([
+            { "id": 0, "_geo": { "lat": 0.1, "lng": 0.0 } },
+            { "id": 1, "_geo": { "lat": 0.3, "lng": 0.0 } },
+            { "id": 2, "_geo": { "lat": -0.5, "lng": 0.0 } },
+            { "id": 3, "_geo": { "lat": 1.0, "lng": 0.0 } },
+            { "id": 4 }
+        ]);
+        let index = index_with_documents(hashset! { S("_geo") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let docids = filter.evaluate(&rtxn, &index).unwrap();
+            // the operations are evaluated one by one when they are counted
+            let (counted, _) = filter.evaluate_with_stats(&rtxn, &index).unwrap();
+            assert_eq!(docids, counted);
+            docids.into_iter().collect::<Vec<_>>()
+        };
+
+        // the documents inside of the inner radius are excluded
+        assert_eq!(evaluate("_geoRing(0, 0, 20000, 60000)"), vec![1, 2]);
+        assert_eq!(evaluate("_geoRing(0, 0, 0, 60000)"), vec![0, 1, 2]);
+        assert_eq!(evaluate("_geoRing(0, 0, 60000, 200000)"), vec![3]);
+        assert_eq!(evaluate("_geoRing(0, 0, 20000, 30000)"), Vec::<u32>::new());
+        assert_eq!(evaluate("_geoRadius(0, 0, 60000) AND NOT _geoRadius(0, 0, 20000)"), vec![1, 2]);
+        assert_eq!(evaluate("NOT _geoRing(0, 0, 20000, 60000)"), vec![0, 3]);
+
+        // the limit keeps the nearest documents of the outer radius
+        let filter = Filter::from_str("_geoRing(0, 0, 20000, 60000)").unwrap().unwrap();
+        let bitmap = filter.evaluate_with_geo_limit(&rtxn, &index, 2).unwrap();
+        assert_eq!(bitmap.into_iter().collect::<Vec<_>>(), vec![1]);
+
+        let error = Filter::from_str("_geoRing(0, 0, 60000, 20000)").unwrap_err();
+        assert!(error.to_string().contains("the inner radius must be lower"), "{}", error);
+        let filter = Filter::from_str("_geoRing(91, 0, 20000, 60000)").unwrap().unwrap();
+        assert!(filter.evaluate(&rtxn, &index).is_err());
+    }
+
+    #[test]
+    fn geo_bounding_box() {
+        let content = documents!([
+            { "id": 0, "_geo": { "lat": 5.0, "lng": 5.0 } },
+            { "id": 1, "_geo": { "lat": 0.0, "lng": 0.0 } },
+            { "id": 2, "_geo": { "lat": 20.0, "lng": 0.0 } },
+            { "id": 3, "_geo": { "lat": -9.0, "lng": -9.0 } },
+            { "id": 4, "_geo": { "lat": 0.0, "lng": 179.0 } },
+            { "id": 5, "_geo": { "lat": 0.0, "lng": -175.0 } },
+            { "id": 6 }
+        ]);
+        let index = index_with_documents(hashset! { S("_geo") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let points = [[5., 5.], [0., 0.], [20., 0.], [-9., -9.], [0., 179.], [0., -175.]];
+        let check = |top_left: [f64; 2], bottom_right: [f64; 2], center, expected: &[u32]| {
+            let documents =
+                Filter::evaluate_geo_bounding_box(&rtxn, &index, top_left, bottom_right, center)
+                    .unwrap();
+            let ids: Vec<_> = documents.iter().map(|(id, _)| *id).collect();
+            assert_eq!(ids, expected);
+            for (id, distance) in documents {
+                let [lat, lng] = points[id as usize];
+                assert!(top_left[0] >= lat && lat >= bottom_right[0]);
+                assert!(distance.is_finite());
+                assert_eq!(distance, distance_between_two_points(&center, &[lat, lng]));
+            }
+        };
+
+        // from the nearest to the farthest document
+        check([10., -10.], [-10., 10.], [0., 0.], &[1, 0, 3]);
+        check([10., -10.], [0., 10.], [5., 0.], &[0, 1]);
+        check([30., 4.], [-30., 6.], [0., 5.], &[0]);
+        check([-20., -10.], [-30., 10.], [-25., 0.], &[]);
+        // the box crosses the 180th meridian
+        check([10., 170.], [-10., -170.], [0., 180.], &[4, 5]);
+
+        let error = Filter::evaluate_geo_bounding_box(&rtxn, &index, [91., 0.], [0., 0.], [0., 0.])
+            .unwrap_err();
+        assert!(error.to_string().starts_with("Bad latitude `91`."));
+        let error =
+            Filter::evaluate_geo_bounding_box(&rtxn, &index, [0., 0.], [0., 0.], [0., 181.])
+                .unwrap_err();
+        assert!(error.to_string().starts_with("Bad longitude `181`."));
+    }
+
+    #[test]
+    fn geo_radii() {
+        let content = documents!([
+            { "id": 0, "_geo": { "lat": 0.3, "lng": 0.0 } },
+            { "id": 1, "_geo": { "lat": 0.1, "lng": 0.0 } },
+            { "id": 2, "_geo": { "lat": 0.4, "lng": 0.0 } },
+            { "id": 3, "_geo": { "lat": 0.2, "lng": 0.0 } },
+            { "id": 4, "_geo": { "lat": 10.0, "lng": 0.0 } }
+        ]);
+        let index = index_with_documents(hashset! { S("_geo") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+
+        // the radii don't need to be sorted
+        let radii = [50000., 12000., 25000.];
+        let bitmaps = Filter::evaluate_geo_radii(&rtxn, &index, [0., 0.], &radii).unwrap();
+        assert_eq!(bitmaps[0].iter().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+        assert_eq!(bitmaps[1].iter().collect::<Vec<_>>(), vec![1]);
+        assert_eq!(bitmaps[2].iter().collect::<Vec<_>>(), vec![1, 3]);
+        assert!(bitmaps[1].is_subset(&bitmaps[2]));
+        assert!(bitmaps[2].is_subset(&bitmaps[0]));
+
+        // the same documents are returned by the _geoRadius filters
+        for (radius, bitmap) in radii.iter().zip(&bitmaps) {
+            let filter = format!("_geoRadius(0, 0, {})", radius);
+            let filter = Filter::from_str(&filter).unwrap().unwrap();
+            assert_eq!(&filter.evaluate(&rtxn, &index).unwrap(), bitmap);
+        }
+
+        let error = Filter::evaluate_geo_radii(&rtxn, &index, [91., 0.], &radii).unwrap_err();
+        assert!(error.to_string().starts_with("Bad latitude `91`."));
+
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).map(|docids| docids.into_iter().collect::<Vec<_>>())
+        };
+
+        // the document 3 is within the two overlapping radii
+        let filter = "_geoRadius([0, 0], [0.4, 0], 25000)";
+        assert_eq!(evaluate(filter).unwrap(), vec![0, 1, 2, 3]);
+        let filter = "_geoRadius([0, 0], [10, 0], 12000)";
+        assert_eq!(evaluate(filter).unwrap(), vec![1, 4]);
+        let filter = "NOT _geoRadius([0, 0], [10, 0], 12000)";
+        assert_eq!(evaluate(filter).unwrap(), vec![0, 2, 3]);
+
+        // all the points are validated
+        let error = evaluate("_geoRadius([0, 0], [91, 0], 12000)").unwrap_err();
+        assert!(error.to_string().starts_with("Bad latitude `91`."));
+    }
+
+    #[test]
+    fn complement_shortcuts() {
+        let universe: RoaringBitmap = (0..1000).collect();
+
+        // nothing is matched, the universe is returned as is
+        assert_eq!(complement(universe.clone(), RoaringBitmap::new()), universe);
+        // the whole universe is matched, even with documents outside of it
+        let matched: RoaringBitmap = (0..2000).collect();
+        assert!(complement(universe.clone(), matched).is_empty());
+        let matched: RoaringBitmap = (0..1000).collect();
+        assert!(complement(universe.clone(), matched).is_empty());
+        // as many documents are matched but not the same ones
+        let matched: RoaringBitmap = (1..1001).collect();
+        assert_eq!(complement(universe.clone(), matched).into_iter().collect::<Vec<_>>(), vec![0]);
+        assert!(complement(RoaringBitmap::new(), universe).is_empty());
+
+        let content = documents!([
+            { "id": 0, "price": 10, "_geo": { "lat": 0.0, "lng": 0.0 } },
+            { "id": 1, "price": 10, "_geo": { "lat": 1.0, "lng": 1.0 } },
+            { "id": 2, "price": "ten" }
+        ]);
+        let index = index_with_documents(hashset! { S("price"), S("_geo") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+
+        // nothing is matched by the operation
+        assert_eq!(evaluate("price != 12"), vec![0, 1, 2]);
+        assert_eq!(evaluate("price NOT IN [12, eleven]"), vec![0, 1, 2]);
+        assert_eq!(evaluate("NOT _geoRadius(50, 50, 1)"), vec![0, 1]);
+        // all the documents are matched by the operation
+        assert_eq!(evaluate("price !=# 10"), Vec::<u32>::new());
+        assert_eq!(evaluate("price NOT EXISTS"), Vec::<u32>::new());
+        assert_eq!(evaluate("NOT _geoRadius(0, 0, 1000000)"), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn position() {
+        let content = documents!([
+            { "id": 0, "tags": ["Primary", "secondary"], "scores": [3, 10] },
+            { "id": 1, "tags": ["secondary", "primary"], "scores": [12, 1] },
+            { "id": 2, "tags": "primary", "scores": 5 },
+            { "id": 3, "tags": ["x"], "scores": [] },
+            { "id": 4 }
+        ]);
+        let index = index_with_documents(hashset! { S("tags"), S("scores"), S("_geo") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).map(|docids| docids.into_iter().collect::<Vec<_>>())
+        };
+
+        assert_eq!(evaluate("tags[0] = primary").unwrap(), vec![0, 2]);
+        assert_eq!(evaluate("tags[1] = primary").unwrap(), vec![1]);
+        assert_eq!(evaluate("tags[2] = primary").unwrap(), Vec::<u32>::new());
+        // the documents without a value at this position don't match the negation
+        assert_eq!(evaluate("NOT tags[0] = primary").unwrap(), vec![1, 3]);
+        assert_eq!(evaluate("tags[1] != primary").unwrap(), vec![0]);
+        assert_eq!(evaluate("scores[0] > 4").unwrap(), vec![1, 2]);
+        assert_eq!(evaluate("scores[1] <= 10").unwrap(), vec![0, 1]);
+        assert_eq!(evaluate("scores[0] = 3").unwrap(), vec![0]);
+
+        // the attribute must be filterable and not reserved
+        let error = evaluate("title[0] = primary").unwrap_err();
+        assert!(error.to_string().starts_with("Attribute `title` is not filterable."), "{}", error);
+        let error = evaluate("_geo[0] = 12").unwrap_err();
+        assert!(error.to_string().contains("`_geo` is a reserved keyword"), "{}", error);
+        let error = evaluate("scores[0] > high").unwrap_err();
+        assert!(error.to_string().ends_with("13:17 scores[0] > high"), "{}", error);
+    }
+
+    #[test]
+    fn negate_or() {
+        let content = documents!([
+            { "id": 0, "n": 0 },
+            { "id": 1, "n": 1 },
+            { "id": 2, "n": 2 },
+            { "id": 3, "n": 3 },
+            { "id": 4, "n": 4 },
+            { "id": 5, "n": 5 },
+            { "id": 6, "n": 6 },
+            { "id": 7, "n": 7 },
+            { "id": 8 }
+        ]);
+        let index = index_with_documents(hashset! { S("n") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+
+        let or = "n = 1 OR n = 2 OR n > 5 OR n 3 TO 4 OR n NOT EXISTS";
+        assert_eq!(evaluate(or), vec![1, 2, 3, 4, 6, 7, 8]);
+        // the NOT is pushed down to each operand of the OR, which becomes an AND
+        let negated = format!("NOT ({})", or);
+        let and = "n != 1 AND n != 2 AND n <= 5 AND NOT n 3 TO 4 AND n EXISTS";
+        assert_eq!(Filter::from_str(&negated).unwrap(), Filter::from_str(and).unwrap());
+        let de_morgan = "n != 1 AND n != 2 AND n <= 5 AND (n < 3 OR n > 4) AND n EXISTS";
+        assert_eq!(evaluate(&negated), vec![0, 5]);
+        assert_eq!(evaluate(de_morgan), vec![0, 5]);
+    }
+
+    #[test]
+    fn any_all() {
+        let content = documents!([
+            { "id": 0, "tags": ["a", "b"] },
+            { "id": 1, "tags": ["a"] },
+            { "id": 2, "tags": ["b", "c"] },
+            { "id": 3, "tags": "a" },
+            { "id": 4 }
+        ]);
+        let index = index_with_documents(hashset! { S("tags") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+
+        assert_eq!(evaluate("ALL(tags = a, tags = b)"), vec![0]);
+        assert_eq!(evaluate("ANY(tags = a, tags = c)"), vec![0, 1, 2, 3]);
+        assert_eq!(evaluate("NOT ALL(tags = a, tags = b)"), vec![1, 2, 3]);
+        assert_eq!(evaluate("ALL(ANY(tags = a, tags = c), tags = b)"), vec![0, 2]);
+    }
+
+    #[test]
+    fn equal_numbers_and_strings() {
+        let content = documents!([
+            { "id": 0, "price": "5" },
+            { "id": 1, "price": "05" },
+            { "id": 2, "price": 5 }
+        ]);
+        let index = index_with_documents(hashset! { S("price") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+
+        // the strings are compared as written, the numbers once parsed
+        assert_eq!(evaluate("price = 5"), vec![0, 2]);
+        assert_eq!(evaluate("price = '5'"), vec![0, 2]);
+        assert_eq!(evaluate("price = 05"), vec![1, 2]);
+        assert_eq!(evaluate("price = 5.0"), vec![2]);
+        assert_eq!(evaluate("price != 05"), vec![0]);
+        assert_eq!(evaluate("price =# 05"), vec![2]);
+    }
+
+    #[test]
+    fn not_equal_case() {
+        let content = documents!([
+            { "id": 0, "channel": "Ponce" },
+            { "id": 1, "channel": "Boat" },
+            { "id": 2, "channel": "PONCE" }
+        ]);
+        let index = index_with_documents(hashset! { S("channel") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        // the operations built without the parser aren't lowercased beforehand
+        let evaluate = |op: Condition| {
+            let fid = Span::new_extra("channel", "channel").into();
+            let filter = Filter::from(FilterCondition::Condition { fid, op });
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+        let token = |value| Token::from(Span::new_extra(value, value));
+
+        assert_eq!(evaluate(Condition::Equal(token("PONCE"))), vec![0, 2]);
+        assert_eq!(evaluate(Condition::NotEqual(token("Ponce"))), vec![1]);
+        assert_eq!(evaluate(Condition::NotEqual(token("boat"))), vec![0, 2]);
+        assert_eq!(evaluate(Condition::NotIn(vec![token("PONCE"), token("BoAt")])), vec![]);
+    }
+
+    #[test]
+    fn equal_number_lookup() {
+        // enough distinct values to have several facet levels
+        let documents: Vec<_> =
+            (0..500).map(|id| json!({ "id": id, "price": (id % 100) as f64 / 2. })).collect();
+        let content = documents!(documents);
+        let index = index_with_documents(hashset! { S("price") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap()
+        };
+
+        // the lookup of `=` matches the same numbers as the levels walk of the ranges
+        let values =
+            [("0", 5), ("7", 5), ("7.5", 5), ("49.5", 5), ("-1", 0), ("3.25", 0), ("50", 0)];
+        for &(value, count) in &values {
+            let equal = evaluate(&format!("price = {}", value));
+            assert_eq!(equal, evaluate(&format!("price {} TO {}", value, value)), "{}", value);
+            assert_eq!(equal.len(), count, "{}", value);
+        }
+
+        let filter = Filter::from_str("price = 7").unwrap().unwrap();
+        let (_, stats) = filter.evaluate_with_stats(&rtxn, &index).unwrap();
+        assert_eq!(stats[0].1.scanned_keys, 2);
+    }
+
+    #[test]
+    fn numeric_array_membership() {
+        // enough distinct sizes to have several facet levels
+        let mut documents = vec![
+            json!({ "id": 0, "sizes": [38, 40, 42] }),
+            json!({ "id": 1, "sizes": [40] }),
+            json!({ "id": 2, "sizes": [36, 44.5] }),
+            json!({ "id": 3, "sizes": 40 }),
+            json!({ "id": 4, "sizes": [] }),
+        ];
+        documents.extend((5..300).map(|id| json!({ "id": id, "sizes": [1000 + id, 2000 + id] })));
+        let content = documents!(documents);
+        let index = index_with_documents(hashset! { S("sizes") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let docids = filter.evaluate(&rtxn, &index).unwrap();
+            docids.into_iter().filter(|&id| id < 5).collect::<Vec<_>>()
+        };
+
+        // every number of an array is a facet value of the document
+        assert_eq!(evaluate("sizes = 40"), vec![0, 1, 3]);
+        assert_eq!(evaluate("sizes =# 44.5"), vec![2]);
+        assert_eq!(evaluate("sizes IN [36, 42]"), vec![0, 2]);
+        assert_eq!(evaluate("sizes 41 TO 43"), vec![0]);
+        assert_eq!(evaluate("sizes > 43"), vec![2]);
+        // the negation matches the documents having numbers but not this one,
+        // an empty array has no value
+        assert_eq!(evaluate("sizes != 40"), vec![2]);
+        assert_eq!(evaluate("sizes = 40 AND sizes = 42"), vec![0]);
+    }
+
+    #[test]
+    fn sorted_vec() {
+        let documents: Vec<_> = (0..100).map(|id| json!({ "id": id, "price": id % 7 })).collect();
+        let content = documents!(documents);
+        let index = index_with_documents(hashset! { S("price") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("price = 3 OR price = 5").unwrap().unwrap();
+        let docids = filter.evaluate(&rtxn, &index).unwrap();
+        let expected: Vec<_> = docids.iter().collect();
+        assert_eq!(expected.len(), 28);
+
+        let sorted = filter.evaluate_sorted_vec(&rtxn, &index, 0, None).unwrap();
+        assert_eq!(sorted, expected);
+        assert!(sorted.windows(2).all(|pair| pair[0] < pair[1]));
+
+        for &(offset, limit) in &[(0, 10), (5, 10), (20, 10), (27, 1), (28, 10), (100, 5), (3, 0)] {
+            let page = filter.evaluate_sorted_vec(&rtxn, &index, offset, Some(limit)).unwrap();
+            let expected: Vec<_> = expected.iter().copied().skip(offset).take(limit).collect();
+            assert_eq!(page, expected, "offset {} limit {}", offset, limit);
+        }
+    }
+
+    #[test]
+    fn external_cache() {
+        let content = documents!([
+            { "id": 0, "status": "active", "price": 5, "color": "red" },
+            { "id": 1, "status": "active", "price": 15, "color": "blue" },
+            { "id": 2, "status": "inactive", "price": 25, "color": "red" },
+            { "id": 3, "status": "active", "price": 35 }
+        ]);
+        let index = index_with_documents(
+            hashset! { S("status"), S("price"), S("color"), S("size") },
+            content,
+        );
+
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let status = fields_ids_map.id("status").unwrap();
+        let mut cache = HashMap::new();
+        let mut evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let cached = filter.evaluate_with_external_cache(&rtxn, &index, &mut cache).unwrap();
+            assert_eq!(cached, filter.evaluate(&rtxn, &index).unwrap());
+            cached.into_iter().collect::<Vec<_>>()
+        };
+
+        assert_eq!(evaluate("status = active AND price > 10"), vec![1, 3]);
+        // the leaf shared by the filters is only evaluated by the first one
+        assert_eq!(evaluate("status = active AND color = red"), vec![0]);
+        assert_eq!(evaluate("(status = active) OR (price > 10 AND color = blue)"), vec![0, 1, 3]);
+        // no document contains a size, it has no field id and isn't cached
+        assert_eq!(evaluate("size = xl OR color = blue"), vec![1]);
+        assert_eq!(cache.len(), 4);
+
+        // the cached documents are used instead of evaluating the leaf again
+        let active = cache.keys().find(|(fid, _)| *fid == status).unwrap().clone();
+        cache.insert(active, RoaringBitmap::from_iter(Some(2)));
+        let filter = Filter::from_str("status = active OR price < 0").unwrap().unwrap();
+        let docids = filter.evaluate_with_external_cache(&rtxn, &index, &mut cache).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![2]);
+    }
+
+    #[test]
+    fn stop_flag() {
+        let mut documents: Vec<_> = (0..1000)
+            .map(|id| json!({ "id": id, "name": format!("name {}", id), "price": id }))
+            .collect();
+        documents.push(json!({ "id": 1000, "name": "" }));
+        let content = documents!(documents);
+        let index = index_with_documents(hashset! { S("name"), S("price") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let stop = AtomicBool::new(false);
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap().with_stop_flag(&stop);
+            filter.evaluate(&rtxn, &index)
+        };
+
+        assert_eq!(evaluate("name ~ '^name 99'").unwrap().len(), 11);
+        assert_eq!(evaluate("price < 10 OR name ~ 7$").unwrap().len(), 109);
+        assert_eq!(evaluate("name IS EMPTY").unwrap().len(), 1);
+
+        // once the flag is set the evaluation stops at the next node or scanned value
+        stop.store(true, atomic::Ordering::Relaxed);
+        for filter in ["name ~ '^name 99'", "NOT name ~ 1", "price < 10 OR price > 20", "price = 1"]
+        {
+            let error = evaluate(filter).unwrap_err();
+            assert!(matches!(error, Error::Interrupted), "{}: {}", filter, error);
+        }
+
+        // the scan of the values of a `~` is interrupted before the next value
+        let name = index.fields_ids_map(&rtxn).unwrap().id("name").unwrap();
+        let pattern = Token::from(Span::new_extra("name", "name"));
+        let mut stats = OperationStats::default();
+        let result = Filter::evaluate_operator(
+            &rtxn,
+            &index,
+            index.facet_id_f64_docids,
+            index.facet_id_string_docids,
+            name,
+            &Condition::Regex(pattern),
+            &mut HashMap::new(),
+            &mut stats,
+            Some(StopFlag(&stop)),
+        );
+        assert!(result.is_err());
+        assert_eq!(stats.scanned_keys, 0);
+
+        // and so are the read of the documents of an `IS EMPTY` and the scans of the
+        // values of the versions and of the values counts
+        let value = |value| Token::from(Span::new_extra(value, value));
+        for operator in [
+            Condition::IsEmpty,
+            Condition::SemVer(Box::new(Condition::GreaterThan(value("1.0.0")))),
+            Condition::ValuesCount(Box::new(Condition::Equal(value("1")))),
+        ] {
+            let mut stats = OperationStats::default();
+            let result = Filter::evaluate_operator(
+                &rtxn,
+                &index,
+                index.facet_id_f64_docids,
+                index.facet_id_string_docids,
+                name,
+                &operator,
+                &mut HashMap::new(),
+                &mut stats,
+                Some(StopFlag(&stop)),
+            );
+            assert!(matches!(result, Err(Error::Interrupted)), "{:?}", operator);
+            assert_eq!(stats.scanned_keys, 0);
+        }
+    }
+
+    #[test]
+    fn strict_types() {
+        let content = documents!([
+            { "id": 0, "price": 12, "name": "abc", "size": 12 },
+            { "id": 1, "price": 20, "name": "12", "size": "abc" },
+            { "id": 2 }
+        ]);
+        let index = index_with_documents(hashset! { S("price"), S("name"), S("size") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str, strict: bool| {
+            let filter = Filter::from_str(filter).unwrap().unwrap().with_strict_types(strict);
+            filter
+                .evaluate(&rtxn, &index)
+                .map(|docids| docids.into_iter().collect::<Vec<_>>())
+                .map_err(|e| e.to_string())
+        };
+
+        // by default the mismatched values match nothing
+        assert_eq!(evaluate("price = abc", false), Ok(vec![]));
+        assert_eq!(evaluate("price != abc", false), Ok(vec![0, 1]));
+        assert_eq!(evaluate("name > 10", false), Ok(vec![]));
+
+        let error = evaluate("price = abc", true).unwrap_err();
+        assert!(
+            error.starts_with("Attribute `price` only contains numbers but `abc` is not a number.")
+        );
+        assert!(error.ends_with("9:12 price = abc"), "{}", error);
+        assert!(evaluate("price != abc", true).is_err());
+        assert!(evaluate("NOT price = abc", true).is_err());
+        let error = evaluate("price IN [12, abc]", true).unwrap_err();
+        assert!(error.ends_with("15:18 price IN [12, abc]"), "{}", error);
+        assert_eq!(evaluate("price = 12 OR price IN [20]", true), Ok(vec![0, 1]));
+        assert_eq!(evaluate("price > 15", true), Ok(vec![1]));
+
+        let error = evaluate("name > 10", true).unwrap_err();
+        assert!(error.starts_with(
+            "Attribute `name` only contains strings and can't be compared with numbers."
+        ));
+        assert!(evaluate("name 1 TO 3 OR name > 10", true).is_err());
+        assert!(evaluate("name =# 12", true).is_err());
+        // a number is also a valid string
+        assert_eq!(evaluate("name = 12", true), Ok(vec![1]));
+
+        // the attributes with both numbers and strings accept everything
+        assert_eq!(evaluate("size = abc", true), Ok(vec![1]));
+        assert_eq!(evaluate("size > 10", true), Ok(vec![0]));
+    }
+
+    #[test]
+    fn ranges_union() {
+        let documents: Vec<_> = (0..200)
+            .map(|id| json!({ "id": id, "price": id as f64 / 4., "stock": id % 7 }))
+            .collect();
+        let content = documents!(documents);
+        let index = index_with_documents(hashset! { S("price"), S("stock") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap()
+        };
+
+        let filters = [
+            "price 0 TO 10 OR price 20 TO 30 OR price 40 TO 50",
+            "price 0 TO 10 OR price 5 TO 15 OR price 12 TO 13",
+            "price < 5 OR price >= 5",
+            "price < 10 OR price > 10",
+            "price 10 TO 20 OR price > 20 OR price <= 3.5",
+            "price * TO 2 OR price 45 TO *",
+            "price 20 TO 10 OR price > 48",
+            "price < 3 OR stock > 5",
+            // the unions are also found among the operands of an `OR`
+            "price < 3 OR price > 45 OR stock > 5",
+            "stock = 1 OR price < 3 OR price > 45",
+        ];
+        for filter in &filters {
+            // the operands evaluated one by one
+            let naive = filter
+                .split(" OR ")
+                .fold(RoaringBitmap::new(), |acc, operand| acc | evaluate(operand));
+            assert_eq!(evaluate(filter), naive, "{}", filter);
+            assert!(!naive.is_empty(), "{}", filter);
+        }
+        assert_eq!(evaluate("price < 10 OR price > 10").len(), 199);
+
+        let ranges = vec![
+            (Included(20.), Included(30.)),
+            (Included(0.), Excluded(10.)),
+            (Included(10.), Included(15.)),
+            (Excluded(30.), Excluded(35.)),
+            (Excluded(40.), Included(50.)),
+        ];
+        assert_eq!(
+            merge_ranges(ranges),
+            vec![
+                (Included(0.), Included(15.)),
+                (Included(20.), Excluded(35.)),
+                (Excluded(40.), Included(50.))
+            ]
+        );
+    }
+
+    #[test]
+    fn geo_distance() {
+        let content = documents!([
+            { "id": 0, "_geo": { "lat": 0.3, "lng": 0.0 }, "price": 10 },
+            { "id": 1, "_geo": { "lat": 0.1, "lng": 0.0 }, "price": 20 },
+            { "id": 2, "_geo": { "lat": 0.4, "lng": 0.0 }, "price": 30 },
+            { "id": 3, "_geo": { "lat": 0.2, "lng": 0.0 }, "price": 40 },
+            { "id": 4, "price": 50 }
+        ]);
+        let index = index_with_documents(hashset! { S("_geo"), S("price") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).map(|docids| docids.into_iter().collect::<Vec<_>>())
+        };
+
+        // the same documents as the equivalent radius
+        assert_eq!(evaluate("_geoDistance(0, 0) < 25000").unwrap(), vec![1, 3]);
+        assert_eq!(evaluate("_geoRadius(0, 0, 25000)").unwrap(), vec![1, 3]);
+        assert_eq!(evaluate("_geoDistance(0, 0) >= 25000").unwrap(), vec![0, 2]);
+        assert_eq!(evaluate("NOT _geoRadius(0, 0, 25000)").unwrap(), vec![0, 2]);
+        // `>` is the complement of `<=` among the documents having a point
+        let distance = distance_between_two_points(&[0., 0.], &[0.2, 0.]);
+        let lower = evaluate(&format!("_geoDistance(0, 0) <= {}", distance)).unwrap();
+        let greater = evaluate(&format!("_geoDistance(0, 0) > {}", distance)).unwrap();
+        assert_eq!(lower, vec![1, 3]);
+        assert_eq!(greater, vec![0, 2]);
+        assert_eq!(evaluate(&format!("_geoDistance(0, 0) < {}", distance)).unwrap(), vec![1]);
+        assert_eq!(evaluate("NOT _geoDistance(0, 0) > 25000").unwrap(), vec![1, 3]);
+        assert_eq!(evaluate("_geoDistance(0, 0) > 25000 AND price > 10").unwrap(), vec![2]);
+
+        // the point is validated like the one of a `_geoRadius`
+        let error = evaluate("_geoDistance(-100, 150) < 10").unwrap_err();
+        assert!(error.to_string().starts_with("Bad latitude `-100`."), "{}", error);
+        let error = evaluate("_geoDistance(10, 250) > 10").unwrap_err();
+        assert!(error.to_string().starts_with("Bad longitude `250`."), "{}", error);
+    }
+
+    #[test]
+    fn lng_lat_order() {
+        let content = documents!([
+            { "id": 0, "_geo": { "lat": 45.0, "lng": 120.0 } },
+            { "id": 1, "_geo": { "lat": 45.0, "lng": 121.0 } }
+        ]);
+        let index = index_with_documents(hashset! { S("_geo") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter, lng_lat| {
+            let filter = Filter::from_str(filter).unwrap().unwrap().with_lng_lat_order(lng_lat);
+            filter.evaluate(&rtxn, &index).map(|docids| docids.into_iter().collect::<Vec<_>>())
+        };
+
+        // the same point written in both orders
+        let lat_lng = Filter::from_str("_geoRadius(45, 120, 1000)").unwrap().unwrap();
+        let lng_lat = Filter::from_str("_geoRadius(120, 45, 1000)").unwrap().unwrap();
+        assert_eq!(
+            FilterCondition::from(lat_lng.clone()),
+            FilterCondition::from(lng_lat.with_lng_lat_order(true))
+        );
+        // the option can be disabled and doesn't swap the points twice
+        assert_eq!(lat_lng.clone().with_lng_lat_order(true).with_lng_lat_order(false), lat_lng);
+        assert_eq!(
+            lat_lng.clone().with_lng_lat_order(true).with_lng_lat_order(true),
+            lat_lng.with_lng_lat_order(true)
+        );
+
+        assert_eq!(evaluate("_geoRadius(45, 120, 1000)", false).unwrap(), vec![0]);
+        assert_eq!(evaluate("_geoRadius(120, 45, 1000)", true).unwrap(), vec![0]);
+        assert_eq!(evaluate("NOT _geoRadius(120, 45, 1000)", true).unwrap(), vec![1]);
+        let filter = "_geoPolygon(119, 44, 119, 46, 122, 46, 122, 44)";
+        assert_eq!(evaluate(filter, true).unwrap(), vec![0, 1]);
+
+        // the errors name the coordinate according to the chosen order
+        let error = evaluate("_geoRadius(45, 120, 1000)", true).unwrap_err();
+        assert!(error.to_string().starts_with("Bad latitude `120`."), "{}", error);
+        assert!(error.to_string().ends_with("16:19 _geoRadius(45, 120, 1000)"), "{}", error);
+        let error = evaluate("_geoRadius(200, 45, 1000)", true).unwrap_err();
+        assert!(error.to_string().starts_with("Bad longitude `200`."), "{}", error);
+        assert!(error.to_string().ends_with("12:15 _geoRadius(200, 45, 1000)"), "{}", error);
+    }
+
+    #[test]
+    fn geo_polygon() {
+        let content = documents!([
+            { "id": 0, "_geo": { "lat": 2.0, "lng": 2.0 } },
+            { "id": 1, "_geo": { "lat": 6.0, "lng": 6.0 } },
+            { "id": 2, "_geo": { "lat": -1.0, "lng": 5.0 } },
+            { "id": 3, "_geo": { "lat": 4.0, "lng": 4.0 } },
+            { "id": 4 }
+        ]);
+        let index = index_with_documents(hashset! { S("_geo") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+
+        // this triangle contains (2, 2) and (4, 4) but neither (6, 6) nor (-1, 5)
+        let filter = Filter::from_str("_geoPolygon(0, 0, 0, 10, 10, 0)").unwrap().unwrap();
+        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(bitmap.into_iter().collect::<Vec<_>>(), vec![0, 3]);
+
+        // the order of the points doesn't matter
+        let filter = Filter::from_str("_geoPolygon(10, 0, 0, 10, 0, 0)").unwrap().unwrap();
+        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(bitmap.into_iter().collect::<Vec<_>>(), vec![0, 3]);
+
+        // only the documents with a _geo field can be outside of the polygon
+        let filter = Filter::from_str("NOT _geoPolygon(0, 0, 0, 10, 10, 0)").unwrap().unwrap();
+        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(bitmap.into_iter().collect::<Vec<_>>(), vec![1, 2]);
+
+        // the points of the polygon are validated like the _geoRadius one
+        let filter = Filter::from_str("_geoPolygon(0, 0, 0, 10, 100, 0)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(
+            error.to_string().starts_with(
+                "Bad latitude `100`. Latitude must be contained between -90 and 90 degrees."
+            ),
+            "{}",
+            error.to_string()
+        );
+
+        let filter = Filter::from_str("_geoPolygon(0, 0, 0, 200, 10, 0)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(
+            error.to_string().starts_with(
+                "Bad longitude `200`. Longitude must be contained between -180 and 180 degrees."
+            ),
+            "{}",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn exists() {
+        let index = TempIndex::new_with_map_size(10 * 1024 * 1024); // 10 MB
+
+        let config = IndexerConfig::default();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_primary_key(S("id"));
+        builder.set_filterable_fields(hashset! { S("_geo"), S("price"), S("color") });
+        builder.execute(|_| ()).unwrap();
+
+        let content = documents!([
+            { "id": 0, "price": 10, "_geo": { "lat": 2.0, "lng": 2.0 } },
+            { "id": 1, "color": "blue" },
+            { "id": 2, "price": "cheap", "_geo": { "lat": -1.0, "lng": 5.0 } },
+            { "id": 3 }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+
+        // both the numbers and the strings are taken into account
+        assert_eq!(evaluate("price EXISTS"), vec![0, 2]);
+        assert_eq!(evaluate("price NOT EXISTS"), vec![1, 3]);
+        assert_eq!(evaluate("NOT price EXISTS"), vec![1, 3]);
+        assert_eq!(evaluate("color EXISTS AND price NOT EXISTS"), vec![1]);
+
+        // `_geo` is not a reserved keyword when used with `EXISTS`
+        assert_eq!(evaluate("_geo EXISTS"), vec![0, 2]);
+        assert_eq!(evaluate("_geo NOT EXISTS"), vec![1, 3]);
+        assert_eq!(evaluate("NOT _geo EXISTS"), vec![1, 3]);
+
+        // `_geo` must still be filterable
+        drop(rtxn);
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_filterable_fields(hashset! { S("price") });
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("_geo EXISTS").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Attribute `_geo` is not filterable. Available filterable attributes are: `price`."
+        ));
+    }
+
+    #[test]
+    fn between_single_value() {
+        let content = documents!([
+            { "id": 0, "price": 10 },
+            { "id": 1, "price": "10" },
+            { "id": 2, "price": 11 },
+            { "id": 3, "price": 10.0 }
+        ]);
+        let index = index_with_documents(hashset! { S("price") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+
+        // a range only matches the numbers, even if both of its bounds are equal
+        let filter = Filter::from_str("price 10 TO 10").unwrap().unwrap();
+        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(bitmap.into_iter().collect::<Vec<_>>(), vec![0, 3]);
+
+        // while an equality also matches the strings
+        let filter = Filter::from_str("price = 10").unwrap().unwrap();
+        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(bitmap.into_iter().collect::<Vec<_>>(), vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn in_list() {
+        let content = documents!([
+            { "id": 0, "colour": "green", "size": 40 },
+            { "id": 1, "colour": "Dark Blue", "size": 42 },
+            { "id": 2, "colour": "red", "size": 44 },
+            { "id": 3, "size": 42 }
+        ]);
+        let index = index_with_documents(hashset! { S("colour"), S("size") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+
+        assert_eq!(evaluate("colour IN [green, 'dark blue']"), vec![0, 1]);
+        assert_eq!(evaluate("colour IN []"), Vec::<u32>::new());
+        assert_eq!(evaluate("size IN [40, 44]"), vec![0, 2]);
+        assert_eq!(evaluate("colour NOT IN [green, 'dark blue']"), vec![2]);
+        assert_eq!(evaluate("NOT colour IN [red]"), vec![0, 1]);
+        assert_eq!(evaluate("size NOT IN [42]"), vec![0, 2]);
+    }
+
+    #[test]
+    fn date_literals() {
+        let content = documents!([
+            { "id": 0, "timestamp": 1672531200 }, // 2023-01-01
+            { "id": 1, "timestamp": 1704067200 }, // 2024-01-01
+            { "id": 2, "timestamp": 1704110400 }, // 2024-01-01T12:00:00Z
+            { "id": 3, "timestamp": 1735689600 }  // 2025-01-01
+        ]);
+        let index = index_with_documents(hashset! { S("timestamp") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).map(|b| b.into_iter().collect::<Vec<_>>())
+        };
+
+        assert_eq!(evaluate("timestamp > 2024-01-01T00:00:00Z").unwrap(), vec![2, 3]);
+        assert_eq!(evaluate("timestamp >= 2024-01-01").unwrap(), vec![1, 2, 3]);
+        assert_eq!(
+            evaluate("timestamp 2024-01-01 TO 2024-01-01T14:00:00+02:00").unwrap(),
+            vec![1, 2]
+        );
+        // the bare numbers are still supported
+        assert_eq!(evaluate("timestamp < 1704067200").unwrap(), vec![0]);
+
+        let error = evaluate("timestamp > 2024-02-30").unwrap_err();
+        assert!(error.to_string().starts_with(
+            "`2024-02-30` is not a valid ISO-8601 date. Expected a date like `2024-01-01` or `2024-01-01T00:00:00Z`."
+        ));
+    }
+
+    #[test]
+    fn matches_document() {
+        let content = documents!([
+            { "id": 0, "price": 10, "tag": "red", "_geo": { "lat": 2.0, "lng": 2.0 } },
+            { "id": 1, "price": 25, "tag": "blue", "_geo": { "lat": 6.0, "lng": 6.0 } },
+            { "id": 2, "price": 40, "tag": "red" },
+            // the coordinates can be strings
+            { "id": 3, "price": 55, "tag": "blue", "_geo": { "lat": "6.0", "lng": "6" } }
+        ]);
+        let index = index_with_documents(hashset! { S("_geo"), S("price"), S("tag") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+
+        for filter in [
+            "price > 20",
+            "price 10 TO 25",
+            "tag = red",
+            "tag != red",
+            "tag = red AND price >= 40",
+            "tag = blue OR price = 10",
+            "_geoRadius(2, 2, 10000)",
+            "NOT _geoRadius(2, 2, 10000)",
+            "_geoPolygon(0, 0, 0, 10, 10, 0)",
+            "NOT _geoPolygon(0, 0, 0, 10, 10, 0)",
+            "_geoRadius(6, 6, 10000) OR tag = red",
+        ] {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let expected = filter.evaluate(&rtxn, &index).unwrap();
+            for docid in 0..4 {
+                assert_eq!(
+                    filter.matches_document(&rtxn, &index, docid).unwrap(),
+                    expected.contains(docid),
+                    "document {} with filter {:?}",
+                    docid,
+                    filter
+                );
+            }
+        }
+
+        let filter = Filter::from_str("_geoRadius(6, 6, 10000)").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([1, 3]));
+
+        // the geo filters are still validated
+        let filter = Filter::from_str("_geoRadius(100, 2, 10)").unwrap().unwrap();
+        assert!(filter.matches_document(&rtxn, &index, 0).is_err());
+    }
+
+    #[test]
+    fn match_trace() {
+        let content = documents!([
+            { "id": 0, "price": 10, "tag": "red" },
+            { "id": 1, "price": 25, "tag": "blue" },
+            { "id": 2, "price": 40, "tag": "green" },
+            { "id": 3, "price": 55, "tag": "yellow" }
+        ]);
+        let index = index_with_documents(hashset! { S("price"), S("tag") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let trace = |filter, docid| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter
+                .match_trace(&rtxn, &index, docid)
+                .unwrap()
+                .map(|trace| Filter::from(trace).explain_structure())
+        };
+
+        let filter = "tag = red OR price > 30 OR tag = blue";
+        assert_eq!(trace(filter, 0).as_deref(), Some("tag = red\n"));
+        assert_eq!(trace(filter, 1).as_deref(), Some("tag = blue\n"));
+        assert_eq!(trace(filter, 2).as_deref(), Some("price > 30\n"));
+        // the first matching branch is returned
+        assert_eq!(trace(filter, 3).as_deref(), Some("price > 30\n"));
+
+        let filter = "tag = green OR (price > 30 AND tag != green) OR tag = red";
+        assert_eq!(trace(filter, 0).as_deref(), Some("tag = red\n"));
+        assert_eq!(trace(filter, 1), None);
+        assert_eq!(trace(filter, 2).as_deref(), Some("tag = green\n"));
+        assert_eq!(trace(filter, 3).as_deref(), Some("AND\n  price > 30\n  tag != green\n"));
+    }
+
+    #[test]
+    fn internal_ids() {
+        let content = documents!([
+            { "id": 10, "channel": "mv" },
+            { "id": 11, "channel": "ponce" },
+            { "id": 12, "channel": "mv" },
+            { "id": 13, "channel": "ponce" }
+        ]);
+        let index = index_with_documents(hashset! { S("channel") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).map(|docids| docids.into_iter().collect::<Vec<_>>())
+        };
+
+        // the ids that don't belong to any document are ignored
+        assert_eq!(evaluate("_id IN [1, 3, 9]").unwrap(), vec![1, 3]);
+        assert_eq!(evaluate("_id = 2").unwrap(), vec![2]);
+        assert_eq!(evaluate("_id NOT IN [0, 2]").unwrap(), vec![1, 3]);
+        assert_eq!(evaluate("_id != 0").unwrap(), vec![1, 2, 3]);
+        assert_eq!(evaluate("_id IN [0, 1, 2] AND channel = mv").unwrap(), vec![0, 2]);
+
+        let error = evaluate("_id IN [1, two]").unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Bad internal document id `two`. The `_id` attribute only accepts positive integers."
+        ));
+        let error = evaluate("_id = -1").unwrap_err();
+        assert!(error.to_string().starts_with("Bad internal document id `-1`."));
+        let error = evaluate("_id > 1").unwrap_err();
+        assert!(error.to_string().starts_with("The `_id` attribute only supports"));
+    }
+
+    #[test]
+    fn corrupted_facet_value() {
+        let index = TempIndex::new_with_map_size(10 * 1024 * 1024); // 10 MB
+
+        let config = IndexerConfig::default();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_primary_key(S("id"));
+        builder.set_filterable_fields(hashset! { S("tag") });
+        builder.execute(|_| ()).unwrap();
+
+        let content = documents!([
+            { "id": 0, "tag": "red" },
+            { "id": 1, "tag": "blue" }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+
+        // the value is too short to even contain the original string
+        let field_id = index.fields_ids_map(&wtxn).unwrap().id("tag").unwrap();
+        index
+            .facet_id_string_docids
+            .remap_data_type::<ByteSlice>()
+            .put(&mut wtxn, &(field_id, "red"), &[0xff][..])
+            .unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("tag = Red").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        let expected = format!(
+            "The documents ids of the value `Red` of the attribute `tag` (field id {}) could not be decoded, the index may be corrupted.",
+            field_id
+        );
+        assert!(error.to_string().starts_with(&expected), "{}", error);
+
+        // the other values can still be filtered
+        let filter = Filter::from_str("tag = blue").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>(), [1]);
+    }
+
+    #[test]
+    fn compiled_filter() {
+        let documents: Vec<_> = (0..200)
+            .map(|i| {
+                json!({
+                    "id": i,
+                    "price": i % 50,
+                    "tag": ["red", "blue", "green"][i % 3],
+                    "_geo": { "lat": (i % 10) as f64, "lng": (i % 7) as f64 },
+                })
+            })
+            .collect();
+        let content = documents!(documents);
+        let index = index_with_documents(hashset! { S("price"), S("tag"), S("_geo") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        for filter in [
+            "price > 20",
+            "price 10 TO 25 AND tag = red",
+            "NOT price 10 TO 25 OR tag IN [blue, green]",
+            "tag = blue AND (price < 5 OR price >= 45)",
+            "_geoRadius(0, 0, 300000) AND price != 12",
+            "unknown EXISTS OR price = 3",
+        ] {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let expected = filter.evaluate(&rtxn, &index);
+            let compiled = filter.clone().compile(&rtxn, &index).unwrap();
+            match expected {
+                Ok(expected) => {
+                    assert_eq!(compiled.evaluate(&rtxn).unwrap(), expected, "{:?}", filter);
+                    // the compiled filter can be evaluated many times
+                    assert_eq!(compiled.evaluate(&rtxn).unwrap(), expected, "{:?}", filter);
+                }
+                Err(expected) => {
+                    let error = compiled.evaluate(&rtxn).unwrap_err();
+                    assert_eq!(error.to_string(), expected.to_string());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unresolved_field_policy() {
+        let index = TempIndex::new_with_map_size(10 * 1024 * 1024); // 10 MB
+
+        // no document contains the `color` attribute
+        let config = IndexerConfig::default();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_primary_key(S("id"));
+        builder.set_filterable_fields(hashset! { S("price"), S("color") });
+        builder.execute(|_| ()).unwrap();
+
+        let content = documents!([
+            { "id": 0, "price": 10 },
+            { "id": 1, "price": 20 },
+            { "id": 2, "price": 30 }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter, policy| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let filter = filter.with_unresolved_field_policy(policy);
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+
+        let policy = UnresolvedFieldPolicy::default();
+        assert_eq!(evaluate("color = red", policy), Vec::<u32>::new());
+        assert_eq!(evaluate("color NOT EXISTS", policy), vec![0, 1, 2]);
+        assert_eq!(evaluate("price > 15 AND color = red", policy), Vec::<u32>::new());
+        assert_eq!(evaluate("price > 15 OR color = red", policy), vec![1, 2]);
+
+        let policy = UnresolvedFieldPolicy::MatchEverything;
+        assert_eq!(evaluate("color = red", policy), vec![0, 1, 2]);
+        assert_eq!(evaluate("color EXISTS", policy), vec![0, 1, 2]);
+        assert_eq!(evaluate("price > 15 AND color = red", policy), vec![1, 2]);
+        assert_eq!(evaluate("price > 15 AND (color = red OR color = blue)", policy), vec![1, 2]);
+        assert_eq!(evaluate("price > 15 OR color = red", policy), vec![0, 1, 2]);
+
+        // the policy is kept when the document is checked alone
+        let filter = Filter::from_str("price > 15 AND color = red").unwrap().unwrap();
+        let filter = filter.with_unresolved_field_policy(policy);
+        assert!(filter.matches_document(&rtxn, &index, 1).unwrap());
+        assert!(!filter.matches_document(&rtxn, &index, 0).unwrap());
+    }
+
+    #[test]
+    fn estimate_selectivity() {
+        // the 80 first documents have a tag, the prices go from 0 to 99
+        let documents: Vec<_> = (0..100)
+            .map(|i| match i {
+                0..=79 => json!({ "id": i, "price": i, "tag": ["red", "blue"][i % 2] }),
+                _ => json!({ "id": i, "price": i }),
+            })
+            .collect();
+        let content = documents!(documents);
+        let index = index_with_documents(hashset! { S("price"), S("tag") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let estimate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.estimate_selectivity(&rtxn, &index).unwrap()
+        };
+        let approx = |estimate: f64, expected: f64| (estimate - expected).abs() < 0.02;
+
+        assert!(approx(estimate("tag EXISTS"), 0.8));
+        assert!(approx(estimate("tag NOT EXISTS"), 0.2));
+        assert!(approx(estimate("tag = red"), 0.4));
+        assert!(approx(estimate("tag IN [red, blue]"), 0.8));
+        assert!(approx(estimate("tag != red"), 0.4));
+        assert!(approx(estimate("price = 12"), 0.01));
+        assert!(approx(estimate("price > 49.5"), 0.5));
+        assert!(approx(estimate("price 10 TO 10"), 0.01));
+        assert!(approx(estimate("NOT price 0 TO 24.75"), 0.75));
+        assert!(approx(estimate("unknown EXISTS"), 0.));
+
+        // an equality is more selective than an existence
+        assert!(estimate("tag = red") < estimate("tag EXISTS"));
+        assert!(estimate("price = 12") < estimate("price EXISTS"));
+        // the operands are assumed to be independent
+        assert!(approx(estimate("tag = red AND price > 49.5"), 0.2));
+        assert!(approx(estimate("tag = red OR price > 49.5"), 0.7));
+        assert!(estimate("tag = red AND price > 49.5") < estimate("tag = red"));
+        assert!(estimate("tag = red OR price > 49.5") > estimate("price > 49.5"));
+    }
+
+    #[test]
+    fn to_dnf() {
+        let documents: Vec<_> = (0..30)
+            .map(|i| match i {
+                0..=19 => json!({
+                    "id": i,
+                    "price": i,
+                    "tag": ["red", "blue", "green"][i % 3],
+                    "color": ["dark", "light"][i % 2],
+                }),
+                _ => json!({ "id": i, "price": i }),
+            })
+            .collect();
+        let content = documents!(documents);
+        let index = index_with_documents(hashset! { S("price"), S("tag"), S("color") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        for filter in [
+            "tag = red AND (price < 5 OR price > 15)",
+            "(tag = red OR color = dark) AND (price 2 TO 8 OR price > 25 OR tag NOT EXISTS)",
+            "NOT (tag = blue AND (color = light OR price >= 10)) AND price < 20",
+            "NOT ((tag = red OR tag = green) AND NOT (color = dark OR price IN [3, 4]))",
+        ] {
+            let condition = FilterCondition::parse(filter).unwrap().unwrap();
+            let dnf = condition.clone().to_dnf(64).unwrap();
+            assert_eq!(
+                Filter::from(dnf).evaluate(&rtxn, &index).unwrap(),
+                Filter::from(condition).evaluate(&rtxn, &index).unwrap(),
+                "{}",
+                filter
+            );
+        }
+    }
+
+    #[test]
+    fn try_from_str() {
+        let (filter, error) = Filter::try_from_str("channel = mv AND price >");
+        assert_eq!(filter, Filter::from_str("channel = mv").unwrap());
+        assert!(error
+            .unwrap()
+            .to_string()
+            .starts_with("Was expecting a value but instead got nothing."));
+
+        let (filter, error) = Filter::try_from_str("channel = mv");
+        assert_eq!(filter, Filter::from_str("channel = mv").unwrap());
+        assert!(error.is_none());
+
+        let (filter, error) = Filter::try_from_str("");
+        assert!(filter.is_none() && error.is_none());
+
+        let (filter, error) = Filter::try_from_str("chan");
+        assert!(filter.is_none());
+        assert!(error.is_some());
+    }
+
+    #[test]
+    fn map_operators() {
+        let content = documents!([
+            { "id": 0, "color": "red", "price": 10 },
+            { "id": 1, "color": "dark red", "price": 20 },
+            { "id": 2, "color": "blue", "price": 30 },
+        ]);
+        let index = index_with_documents(hashset! { S("color"), S("price") }, content);
+
+        // rewrites the equalities into "includes", the values are matched as regexes
+        let includes = |fid, op| match op {
+            Condition::Equal(value) => (fid, Condition::Regex(value)),
+            op => (fid, op),
+        };
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: FilterCondition| {
+            let docids = Filter::from(filter).evaluate(&rtxn, &index).unwrap();
+            docids.into_iter().collect::<Vec<_>>()
+        };
+
+        let condition = FilterCondition::parse("color = red AND price < 35").unwrap().unwrap();
+        assert_eq!(evaluate(condition.clone()), vec![0]);
+        assert_eq!(evaluate(condition.map_operators(includes)), vec![0, 1]);
+
+        let condition = FilterCondition::parse("color = blue OR color = dark").unwrap().unwrap();
+        assert_eq!(evaluate(condition.clone()), vec![2]);
+        assert_eq!(evaluate(condition.map_operators(includes)), vec![1, 2]);
+    }
+
+    #[test]
+    fn field_comparison() {
+        let content = documents!([
+            { "id": 0, "start": 10, "end": 20 },
+            { "id": 1, "start": 30, "end": 20 },
+            { "id": 2, "start": 20, "end": 20 },
+            { "id": 3, "start": -5, "end": -10 },
+            { "id": 4, "start": 15 },
+            { "id": 5, "start": "10", "end": 20 },
+            { "id": 6, "start": [25, 5], "end": 20 },
+        ]);
+        let index = index_with_documents(hashset! { S("start"), S("end"), S("title") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).map(|docids| docids.into_iter().collect::<Vec<_>>())
+        };
+
+        // only the documents with numbers for both attributes are compared
+        assert_eq!(evaluate("start < @end").unwrap(), vec![0, 6]);
+        assert_eq!(evaluate("start <= @end").unwrap(), vec![0, 2, 6]);
+        assert_eq!(evaluate("start > @end").unwrap(), vec![1, 3, 6]);
+        assert_eq!(evaluate("start >= @end").unwrap(), vec![1, 2, 3, 6]);
+        assert_eq!(evaluate("start = @end").unwrap(), vec![2]);
+        assert_eq!(evaluate("start != @end").unwrap(), vec![0, 1, 3, 6]);
+        assert_eq!(evaluate("end > @start").unwrap(), evaluate("start < @end").unwrap());
+        assert_eq!(evaluate("NOT start < @end").unwrap(), vec![1, 2, 3, 6]);
+        assert_eq!(evaluate("start < @end AND start > 5").unwrap(), vec![0, 6]);
+        // the attribute is compared to itself
+        assert_eq!(evaluate("start = @start").unwrap(), vec![0, 1, 2, 3, 4, 6]);
+        // no document has a number for the title
+        assert_eq!(evaluate("start < @title").unwrap(), Vec::<u32>::new());
+
+        let error = evaluate("start < @price").unwrap_err();
+        assert!(error.to_string().starts_with("Attribute `price` is not filterable."), "{}", error);
+    }
+
+    #[test]
+    fn numeric_strings() {
+        let content = documents!([
+            { "id": 0, "price": 5 },
+            { "id": 1, "price": 15 },
+            { "id": 2, "price": "5" },
+            { "id": 3, "price": "15.5" },
+            { "id": 4, "price": " 20 " },
+            { "id": 5, "price": "cheap" },
+            { "id": 6, "price": ["3", 30] },
+        ]);
+        let index = index_with_documents(hashset! { S("price") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter, numeric_strings| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let filter = filter.with_numeric_strings(numeric_strings);
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+
+        // by default the ranges only match the numbers
+        assert_eq!(evaluate("price > 10", false), vec![1, 6]);
+        assert_eq!(evaluate("price <= 5", false), vec![0]);
+        assert_eq!(evaluate("price 4 TO 16", false), vec![0, 1]);
+        assert_eq!(evaluate("NOT price 4 TO 16", false), vec![6]);
+
+        // the strings that can be parsed as numbers are also compared
+        assert_eq!(evaluate("price > 10", true), vec![1, 3, 4, 6]);
+        assert_eq!(evaluate("price <= 5", true), vec![0, 2, 6]);
+        assert_eq!(evaluate("price 4 TO 16", true), vec![0, 1, 2, 3]);
+        assert_eq!(evaluate("NOT price 4 TO 16", true), vec![4, 6]);
+        // the values of a document can match different sides of the `AND`
+        assert_eq!(evaluate("price > 10 AND price < 16", true), vec![1, 3, 6]);
+
+        // the other operators are unchanged
+        assert_eq!(evaluate("price = 5", true), evaluate("price = 5", false));
+        assert_eq!(evaluate("price != 5", true), evaluate("price != 5", false));
+        assert_eq!(evaluate("price EXISTS", true), evaluate("price EXISTS", false));
+    }
+
+    #[test]
+    fn evaluation_log() {
+        use std::sync::Mutex;
+
+        use once_cell::sync::Lazy;
+
+        /// Keeps the messages logged on the evaluation target.
+        struct CapturingLogger(Mutex<Vec<String>>);
+
+        impl log::Log for CapturingLogger {
+            fn enabled(&self, metadata: &log::Metadata) -> bool {
+                metadata.target() == EVALUATION_LOG_TARGET
+            }
+
+            fn log(&self, record: &log::Record) {
+                if self.enabled(record.metadata()) {
+                    self.0.lock().unwrap().push(record.args().to_string());
+                }
+            }
+
+            fn flush(&self) {}
+        }
+
+        static LOGGER: Lazy<CapturingLogger> = Lazy::new(|| CapturingLogger(Mutex::default()));
+        log::set_logger(&*LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+
+        let documents: Vec<_> = (0..10)
+            .map(|i| json!({ "id": i, "evaluation_log_price": i, "tag": ["red", "blue"][i % 2] }))
+            .collect();
+        let content = documents!(documents);
+        let index = index_with_documents(hashset! { S("evaluation_log_price"), S("tag") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str(
+            "tag = red AND (evaluation_log_price < 3 OR evaluation_log_price >= 8)",
+        )
+        .unwrap()
+        .unwrap();
+        let docids = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![0, 2, 8]);
+
+        // the other tests may evaluate filters concurrently
+        let logs = LOGGER.0.lock().unwrap();
+        let log = logs.iter().find(|log| log.contains("evaluation_log_price")).unwrap();
+        let (header, summary) = log.split_once('\n').unwrap();
+        assert!(header.starts_with("filter evaluated in "), "{}", header);
+        assert_eq!(
+            summary,
+            "AND (3 documents)
+  tag = red (5 documents)
+  OR (5 documents)
+    evaluation_log_price < 3 (3 documents)
+    evaluation_log_price >= 8 (2 documents)"
+        );
+    }
+
+    #[test]
+    fn numeric_equal() {
+        let content = documents!([
+            { "id": 0, "rating": 5 },
+            { "id": 1, "rating": "5" },
+            { "id": 2, "rating": 3 },
+            { "id": 3, "rating": "4" }
+        ]);
+        let index = index_with_documents(hashset! { S("rating") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+
+        assert_eq!(evaluate("rating = 5"), vec![0, 1]);
+        assert_eq!(evaluate("rating =# 5"), vec![0]);
+        assert_eq!(evaluate("NOT rating =# 5"), vec![2]);
+        // `!=` also matches the documents with string values while `!=#` only matches the numbers
+        assert_eq!(evaluate("rating != 4"), vec![0, 1, 2]);
+        assert_eq!(evaluate("rating !=# 4"), vec![0, 2]);
+        assert_eq!(evaluate("rating !=# 5"), vec![2]);
+        assert_eq!(evaluate("NOT rating !=# 5"), evaluate("rating =# 5"));
+        assert_eq!(evaluate("rating 1 TO 3"), vec![2]);
+        assert_eq!(evaluate("NOT rating 1 TO 3"), vec![0]);
+        assert_eq!(evaluate("NOT rating 4 TO 5"), vec![2]);
+        // the negation of a range is the same as the union of its two sides
+        assert_eq!(evaluate("NOT rating 2 TO 4"), evaluate("rating < 2 OR rating > 4"));
+    }
+
+    #[test]
+    fn values_count() {
+        let content = documents!([
+            { "id": 0, "tags": ["rust"] },
+            { "id": 1, "tags": ["rust", "go"] },
+            { "id": 2, "tags": ["rust", "go", 12] },
+            { "id": 3 },
+            // the values are normalized before being counted
+            { "id": 4, "tags": ["Rust", "rust "] }
+        ]);
+        let index = index_with_documents(hashset! { S("tags") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+
+        assert_eq!(evaluate("tags HAS >= 2 VALUES"), vec![1, 2]);
+        assert_eq!(evaluate("tags HAS = 3 VALUES"), vec![2]);
+        assert_eq!(evaluate("tags HAS < 2 VALUES"), vec![0, 3, 4]);
+        assert_eq!(evaluate("tags HAS = 0 VALUES"), vec![3]);
+        assert_eq!(evaluate("NOT tags HAS >= 2 VALUES"), vec![0, 3, 4]);
+    }
+
+    #[test]
+    fn explain() {
+        let content = documents!([
+            { "id": 0, "price": 10, "tag": "red" },
+            { "id": 1, "price": 25, "tag": "blue" },
+            { "id": 2, "price": 40, "tag": "red" },
+            { "id": 3, "price": 55, "tag": "green" },
+            { "id": 4, "tag": "blue" }
+        ]);
+        let index = index_with_documents(hashset! { S("price"), S("tag") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str(
+            "(tag = red OR tag = blue OR price >= 40) AND price 10 TO 40 AND tag NOT IN [green]",
+        )
+        .unwrap()
+        .unwrap();
+
+        let expected = "\
+AND
+  OR
+    tag = red
+    tag = blue
+    price >= 40
+  price 10 TO 40
+  tag NOT IN [green]
+";
+        assert_eq!(filter.explain_structure(), expected);
+
+        let expected = "\
+AND (3 documents)
+  OR (5 documents)
+    tag = red (2 documents)
+    tag = blue (2 documents)
+    price >= 40 (2 documents)
+  price 10 TO 40 (3 documents)
+  tag NOT IN [green] (4 documents)
+";
+        assert_eq!(filter.explain(&rtxn, &index).unwrap(), expected);
+    }
+
+    #[test]
+    fn quoted_field_name() {
+        let content = documents!([
+            { "id": 0, "release date": 2021 },
+            { "id": 1, "release date": 2022 }
+        ]);
+        let index = index_with_documents(hashset! { S("release date") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+
+        assert_eq!(evaluate("`release date` = 2022"), vec![1]);
+        assert_eq!(evaluate("'release date' = 2022"), vec![1]);
+        assert_eq!(evaluate("\"release date\" < 2022"), vec![0]);
+
+        // the quoted reserved keywords are still rejected
+        let filter = Filter::from_str("`_geo` = 12").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with("`_geo` is a reserved keyword"));
+    }
 
-    use big_s::S;
-    use either::Either;
-    use heed::EnvOpenOptions;
-    use maplit::hashset;
+    #[test]
+    fn warnings() {
+        let (filter, warnings) = Filter::from_str_with_warnings(
+            "price = 10 AND (NOT price 1 TO 5 OR tag = red) AND price != 3.5 AND price =# 12",
+        )
+        .unwrap();
+        assert!(filter.is_some());
+        let warnings: Vec<_> = warnings.iter().map(|w| (w.warning_code(), w.to_string())).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                ("implicit_string_union", S("`price = 10` also matches the `\"10\"` strings. Use `price =# 10` to only match the numbers.")),
+                ("negated_range", S("`NOT price 1 TO 5` only matches the numbers outside of the range. Prefer the explicit `price < 1 OR price > 5`.")),
+                ("implicit_string_union", S("`price != 3.5` also matches the `\"3.5\"` strings. Use `price !=# 3.5` to only match the numbers.")),
+            ]
+        );
 
-    use super::*;
-    use crate::update::{IndexerConfig, Settings};
-    use crate::Index;
+        // the filters without any warning are parsed the same way
+        let (filter, warnings) =
+            Filter::from_str_with_warnings("price =# 10 AND tag = red").unwrap();
+        assert_eq!(filter, Filter::from_str("price =# 10 AND tag = red").unwrap());
+        assert!(warnings.is_empty());
+
+        let (filter, warnings) = Filter::from_str_with_warnings("   ").unwrap();
+        assert!(filter.is_none());
+        assert!(warnings.is_empty());
+    }
 
     #[test]
-    fn empty_db() {
-        let path = tempfile::tempdir().unwrap();
-        let mut options = EnvOpenOptions::new();
-        options.map_size(10 * 1024 * 1024); // 10 MB
-        let index = Index::new(options, &path).unwrap();
+    fn mismatched_range_bounds() {
+        let content = documents!([{ "id": 0, "price": 12 }, { "id": 1, "price": "abc" }]);
+        let index = index_with_documents(hashset! { S("price") }, content);
 
-        // Set the filterable fields to be the channel.
-        let config = IndexerConfig::default();
-        let mut wtxn = index.write_txn().unwrap();
-        let mut builder = Settings::new(&mut wtxn, &index, &config);
-        builder.set_searchable_fields(vec![S("PrIcE")]); // to keep the fields order
-        builder.set_filterable_fields(hashset! { S("PrIcE") });
-        builder.execute(|_| ()).unwrap();
-        wtxn.commit().unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index);
+
+        // both bounds are highlighted when only one of them is a number
+        let error = evaluate("price 10 TO abc").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The bounds of a range must both be numbers but `10` and `abc` are not of the same type.\n7:16 price 10 TO abc"
+        );
+        let error = evaluate("NOT price abc TO 10").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The bounds of a range must both be numbers but `abc` and `10` are not of the same type.\n11:20 NOT price abc TO 10"
+        );
+        let error = evaluate("price * TO abc").unwrap_err();
+        assert_eq!(error.to_string().lines().last(), Some("7:15 price * TO abc"));
+
+        // when both bounds are invalid the first one is reported
+        let error = evaluate("price abc TO def").unwrap_err();
+        assert!(error.to_string().ends_with("7:10 price abc TO def"), "{}", error);
+        assert_eq!(evaluate("price 10 TO 20").unwrap().into_iter().collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[test]
+    fn signed_ranges() {
+        // enough distinct values to create several facet levels,
+        // the document `i` has a price of `i - 10`.
+        let documents: Vec<_> =
+            (0..=20).map(|i| serde_json::json!({ "id": i, "price": i - 10 })).collect();
+        let content = documents!(documents);
+        let index = index_with_documents(hashset! { S("price") }, content);
 
         let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+        let expected = |matches: &dyn Fn(f64) -> bool| {
+            (0..=20u32).filter(|i| matches(*i as f64 - 10.)).collect::<Vec<_>>()
+        };
 
-        let filter = Filter::from_str("PrIcE < 1000").unwrap().unwrap();
-        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
-        assert!(bitmap.is_empty());
+        // every bound around and across the sign boundary
+        for n in -24..=24 {
+            let n = n as f64 / 2.;
+            assert_eq!(evaluate(&format!("price > {}", n)), expected(&|p| p > n), "> {}", n);
+            assert_eq!(evaluate(&format!("price >= {}", n)), expected(&|p| p >= n), ">= {}", n);
+            assert_eq!(evaluate(&format!("price < {}", n)), expected(&|p| p < n), "< {}", n);
+            assert_eq!(evaluate(&format!("price <= {}", n)), expected(&|p| p <= n), "<= {}", n);
+            assert_eq!(
+                evaluate(&format!("price {} TO {}", n, n + 5.)),
+                expected(&|p| p >= n && p <= n + 5.),
+                "{} TO {}",
+                n,
+                n + 5.
+            );
+            // the open-ended ranges are the same as `>=` and `<=`
+            assert_eq!(evaluate(&format!("price {} TO *", n)), expected(&|p| p >= n), "{} TO *", n);
+            assert_eq!(evaluate(&format!("price * TO {}", n)), expected(&|p| p <= n), "* TO {}", n);
+            assert_eq!(
+                evaluate(&format!("NOT price {} TO *", n)),
+                expected(&|p| p < n),
+                "NOT {} TO *",
+                n
+            );
+        }
 
-        let filter = Filter::from_str("NOT PrIcE >= 1000").unwrap().unwrap();
-        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
-        assert!(bitmap.is_empty());
+        assert_eq!(evaluate("price > -10"), (1..=20).collect::<Vec<_>>());
+        assert_eq!(evaluate("price -0 TO 0"), vec![10]);
+        assert_eq!(evaluate("price -3 TO 2"), vec![7, 8, 9, 10, 11, 12]);
     }
 
     #[test]
-    fn from_array() {
-        // Simple array with Left
-        let condition = Filter::from_array(vec![Either::Left(["channel = mv"])]).unwrap().unwrap();
-        let expected = Filter::from_str("channel = mv").unwrap().unwrap();
-        assert_eq!(condition, expected);
+    fn regex() {
+        let content = documents!([
+            { "id": 0, "name": "Ponce" },
+            { "id": 1, "name": "pounce" },
+            { "id": 2, "name": "spoon" },
+            { "id": 3, "name": 12 },
+            { "id": 4 },
+            { "id": 5, "name": ["cat", "pounce"] }
+        ]);
+        let index = index_with_documents(hashset! { S("name") }, content);
 
-        // Simple array with Right
-        let condition = Filter::from_array::<_, Option<&str>>(vec![Either::Right("channel = mv")])
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+
+        // the expressions are matched against the normalized, lowercased, values
+        assert_eq!(evaluate("name ~ \"^po.*ce$\""), vec![0, 1, 5]);
+        assert_eq!(evaluate("name ~ '^p.n'"), vec![0]);
+        assert_eq!(evaluate("name ~ po"), vec![0, 1, 2, 5]);
+        assert_eq!(evaluate("name ~ '^po'"), vec![0, 1, 5]);
+        assert_eq!(evaluate("name ~ '[0-9]'"), Vec::<u32>::new());
+        // only the documents with string values can be matched
+        assert_eq!(evaluate("NOT name ~ '^po'"), vec![2]);
+        // a document matches the negation only if none of its values match
+        assert_eq!(evaluate("NOT name ~ cat"), vec![0, 1, 2]);
+        assert_eq!(evaluate("NOT name ~ '^(cat|spoon)$'"), vec![0, 1]);
+
+        let filter = Filter::from_str("name ~ 'po(nce'").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains("regex parse error"), "{}", error);
+        assert!(error.to_string().ends_with("9:15 name ~ 'po(nce'"), "{}", error);
+    }
+
+    #[test]
+    fn evaluation_stats() {
+        let content = documents!([
+            { "id": 0, "name": "Ponce", "price": 10 },
+            { "id": 1, "name": "pounce", "price": 20 },
+            { "id": 2, "name": "spoon" },
+            { "id": 3, "name": 12 },
+            { "id": 4, "name": ["cat", "pounce"], "price": 30 }
+        ]);
+        let index = index_with_documents(hashset! { S("name"), S("price") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let name = index.fields_ids_map(&rtxn).unwrap().id("name").unwrap();
+        let distinct_strings = FacetStringLevelZeroRange::new(
+            &rtxn,
+            index.facet_id_string_docids,
+            name,
+            Unbounded,
+            Unbounded,
+        )
+        .unwrap()
+        .count() as u64;
+        assert_eq!(distinct_strings, 4);
+
+        let filter = Filter::from_str("name ~ po AND price > 15").unwrap().unwrap();
+        let (docids, stats) = filter.evaluate_with_stats(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![1, 4]);
+        assert_eq!(stats.len(), 2);
+
+        // a substring match scans all the distinct strings of the field
+        let (description, regex) = &stats[0];
+        assert_eq!(description, "name ~ po");
+        assert_eq!(
+            *regex,
+            OperationStats {
+                strings_db: true,
+                scanned_keys: distinct_strings,
+                ..Default::default()
+            }
+        );
+
+        let (description, range) = &stats[1];
+        assert_eq!(description, "price > 15");
+        assert!(range.numbers_db && !range.strings_db && !range.geo_rtree);
+        assert!(range.scanned_keys > 0);
+
+        let filter = Filter::from_str("name = cat OR price EXISTS").unwrap().unwrap();
+        let (_, stats) = filter.evaluate_with_stats(&rtxn, &index).unwrap();
+        assert_eq!(
+            stats[0].1,
+            OperationStats { strings_db: true, scanned_keys: 1, ..Default::default() }
+        );
+        assert_eq!(stats[1].1, OperationStats { faceted_documents: true, ..Default::default() });
+    }
+
+    #[test]
+    fn field_stats() {
+        let content = documents!([
+            { "id": 0, "name": "Ponce", "price": 10, "_geo": { "lat": 0.1, "lng": 0.0 } },
+            { "id": 1, "name": "pounce", "price": 20, "_geo": { "lat": 1.0, "lng": 0.0 } },
+            { "id": 2, "name": "spoon", "_geo": { "lat": 0.2, "lng": 0.0 } },
+            { "id": 3, "name": 12 },
+            { "id": 4, "name": ["cat", "pounce"], "price": 30 }
+        ]);
+        let index = index_with_documents(hashset! { S("name"), S("price"), S("_geo") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let name = fields_ids_map.id("name").unwrap();
+        let price = fields_ids_map.id("price").unwrap();
+        let geo = fields_ids_map.id("_geo").unwrap();
+
+        let filter = Filter::from_str("name ~ po AND price > 15").unwrap().unwrap();
+        let (docids, fields) = filter.evaluate_with_field_stats(&rtxn, &index).unwrap();
+        assert_eq!(docids, filter.evaluate(&rtxn, &index).unwrap());
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![1, 4]);
+        assert_eq!(fields, hashmap! { name => 4, price => 2 });
+
+        // the operations of the same attribute are summed
+        let filter = Filter::from_str("price > 15 OR price < 15 AND _geoRadius(0, 0, 50000)")
             .unwrap()
             .unwrap();
-        let expected = Filter::from_str("channel = mv").unwrap().unwrap();
-        assert_eq!(condition, expected);
+        let (docids, fields) = filter.evaluate_with_field_stats(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![0, 1, 4]);
+        assert_eq!(fields, hashmap! { price => 3, geo => 2 });
+    }
 
-        // Array with Left and escaped quote
-        let condition =
-            Filter::from_array(vec![Either::Left(["channel = \"Mister Mv\""])]).unwrap().unwrap();
-        let expected = Filter::from_str("channel = \"Mister Mv\"").unwrap().unwrap();
-        assert_eq!(condition, expected);
+    #[test]
+    fn highlights() {
+        let content = documents!([
+            { "id": 0, "name": "Ponce" },
+            { "id": 1, "name": "İstanbul" },
+            { "id": 2, "name": "spoon" },
+            { "id": 3, "name": ["cat", "Ponce"] }
+        ]);
+        let index = index_with_documents(hashset! { S("name") }, content);
 
-        // Array with Right and escaped quote
-        let condition =
-            Filter::from_array::<_, Option<&str>>(vec![Either::Right("channel = \"Mister Mv\"")])
-                .unwrap()
-                .unwrap();
-        let expected = Filter::from_str("channel = \"Mister Mv\"").unwrap().unwrap();
-        assert_eq!(condition, expected);
+        let rtxn = index.read_txn().unwrap();
+        let highlights = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let (_, highlights) = filter.evaluate_with_highlights(&rtxn, &index).unwrap();
+            highlights
+                .into_iter()
+                .map(|h| (h.original, h.offsets, h.docids.into_iter().collect::<Vec<_>>()))
+                .collect::<Vec<_>>()
+        };
 
-        // Array with Left and escaped simple quote
-        let condition =
-            Filter::from_array(vec![Either::Left(["channel = 'Mister Mv'"])]).unwrap().unwrap();
-        let expected = Filter::from_str("channel = 'Mister Mv'").unwrap().unwrap();
-        assert_eq!(condition, expected);
+        assert_eq!(
+            highlights("name ~ on"),
+            vec![(S("Ponce"), vec![1..3], vec![0, 3]), (S("spoon"), vec![3..5], vec![2])]
+        );
+        assert_eq!(
+            highlights("name ~ 'o'"),
+            vec![(S("Ponce"), vec![1..2], vec![0, 3]), (S("spoon"), vec![2..3, 3..4], vec![2])]
+        );
+        // only the values of the matching documents are highlighted
+        assert_eq!(
+            highlights("name ~ on AND name != spoon"),
+            vec![(S("Ponce"), vec![1..3], vec![0, 3])]
+        );
+        assert_eq!(highlights("NOT name ~ on"), vec![]);
 
-        // Array with Right and escaped simple quote
-        let condition =
-            Filter::from_array::<_, Option<&str>>(vec![Either::Right("channel = 'Mister Mv'")])
-                .unwrap()
-                .unwrap();
-        let expected = Filter::from_str("channel = 'Mister Mv'").unwrap().unwrap();
-        assert_eq!(condition, expected);
+        // `İ` is lowercased as `i̇`, three bytes long instead of two
+        assert_eq!(highlights("name ~ stan"), vec![(S("İstanbul"), vec![2..6], vec![1])]);
+        assert_eq!(highlights("name ~ '^i'"), vec![(S("İstanbul"), vec![0..2], vec![1])]);
+        assert_eq!(highlights("name ~ '^'"), vec![]);
+    }
 
-        // Simple with parenthesis
-        let condition =
-            Filter::from_array(vec![Either::Left(["(channel = mv)"])]).unwrap().unwrap();
-        let expected = Filter::from_str("(channel = mv)").unwrap().unwrap();
-        assert_eq!(condition, expected);
+    #[test]
+    fn in_buckets() {
+        let content = documents!([
+            { "id": 0, "price": 5 },
+            { "id": 1, "price": 10 },
+            { "id": 2, "price": 50 },
+            { "id": 3, "price": 100 },
+            { "id": 4, "price": 150 },
+            { "id": 5, "price": "20" },
+            { "id": 6 },
+            { "id": 7, "price": [-3, 7.5] }
+        ]);
+        let index = index_with_documents(hashset! { S("price") }, content);
 
-        // Test that the facet condition is correctly generated.
-        let condition = Filter::from_array(vec![
-            Either::Right("channel = gotaga"),
-            Either::Left(vec!["timestamp = 44", "channel != ponce"]),
-        ])
-        .unwrap()
-        .unwrap();
-        let expected =
-            Filter::from_str("channel = gotaga AND (timestamp = 44 OR channel != ponce)")
-                .unwrap()
-                .unwrap();
-        println!("\nExpecting: {:#?}\nGot: {:#?}\n", expected, condition);
-        assert_eq!(condition, expected);
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+        let buckets = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let buckets = filter.evaluate_buckets(&rtxn, &index).unwrap();
+            buckets.into_iter().map(|b| b.into_iter().collect::<Vec<_>>()).collect::<Vec<_>>()
+        };
+
+        // only the numbers are matched, the last edge is included
+        assert_eq!(evaluate("price IN_BUCKETS [0, 10, 100]"), vec![0, 1, 2, 3, 7]);
+        assert_eq!(evaluate("NOT price IN_BUCKETS [0, 10, 100]"), vec![4, 7]);
+        assert_eq!(buckets("price IN_BUCKETS [0, 10, 100]"), vec![vec![0, 7], vec![1, 2, 3]]);
+        assert_eq!(
+            buckets("price IN_BUCKETS [-10, 0, 10, 100, 1000]"),
+            vec![vec![7], vec![0, 7], vec![1, 2, 3], vec![4]]
+        );
+        // the buckets only contain the documents matching the whole filter
+        assert_eq!(
+            buckets("price IN_BUCKETS [0, 10, 100] AND price != 50"),
+            vec![vec![0, 7], vec![1, 3]]
+        );
+        assert_eq!(buckets("price > 10"), Vec::<Vec<u32>>::new());
+
+        let filter = Filter::from_str("price IN_BUCKETS [10, 0]").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err().to_string();
+        assert!(error.contains("sorted in ascending order without duplicates"), "{}", error);
+        assert!(error.ends_with("19:24 price IN_BUCKETS [10, 0]"), "{}", error);
+
+        let filter = Filter::from_str("price IN_BUCKETS [0, 10, 10]").unwrap().unwrap();
+        assert!(filter.evaluate(&rtxn, &index).is_err());
+
+        let filter = Filter::from_str("price IN_BUCKETS [0, inf]").unwrap().unwrap();
+        let error = filter.evaluate_buckets(&rtxn, &index).unwrap_err().to_string();
+        assert!(error.contains("must be finite numbers but got `inf`"), "{}", error);
     }
 
     #[test]
-    fn not_filterable() {
-        let path = tempfile::tempdir().unwrap();
-        let mut options = EnvOpenOptions::new();
-        options.map_size(10 * 1024 * 1024); // 10 MB
-        let index = Index::new(options, &path).unwrap();
+    fn semver() {
+        let content = documents!([
+            { "id": 0, "version": "1.9.0" },
+            { "id": 1, "version": "1.10.0" },
+            { "id": 2, "version": "1.10.0-alpha" },
+            { "id": 3, "version": "1.10.0-beta.2" },
+            { "id": 4, "version": "2.0.0+build.5" },
+            { "id": 5, "version": "not a version" },
+            { "id": 6, "version": 2 }
+        ]);
+        let index = index_with_documents(hashset! { S("version") }, content);
 
         let rtxn = index.read_txn().unwrap();
-        let filter = Filter::from_str("_geoRadius(42, 150, 10)").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().starts_with(
-            "Attribute `_geo` is not filterable. Available filterable attributes are: ``."
-        ));
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
 
-        let filter = Filter::from_str("dog = \"bernese mountain\"").unwrap().unwrap();
+        // 1.10.0 is greater than 1.9.0 and the prereleases are lower than their release
+        assert_eq!(evaluate("version SEMVER > 1.9.0"), vec![1, 2, 3, 4]);
+        assert_eq!(evaluate("version SEMVER < 1.10.0"), vec![0, 2, 3]);
+        assert_eq!(evaluate("version SEMVER < 1.10.0-beta"), vec![0, 2]);
+        assert_eq!(evaluate("version SEMVER >= '1.10.0-beta.2'"), vec![1, 3, 4]);
+        // the build metadata is ignored
+        assert_eq!(evaluate("version SEMVER = 2.0.0"), vec![4]);
+        assert_eq!(evaluate("version SEMVER != 1.10.0"), vec![0, 2, 3, 4]);
+        assert_eq!(evaluate("NOT version SEMVER <= 1.10.0"), vec![4]);
+
+        let filter = Filter::from_str("version SEMVER >= 1.9").unwrap().unwrap();
         let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().starts_with(
-            "Attribute `dog` is not filterable. Available filterable attributes are: ``."
-        ));
-        drop(rtxn);
+        assert!(error.to_string().ends_with("19:22 version SEMVER >= 1.9"), "{}", error);
+    }
+
+    #[test]
+    fn words_and_chars_count() {
+        let content = documents!([
+            { "id": 0, "title": "Dune" },
+            { "id": 1, "title": "  The Left Hand of Darkness " },
+            { "id": 2, "title": "Do Androids Dream of Electric Sheep?" },
+            { "id": 3, "title": ["Hyperion", "The Fall of Hyperion"] },
+            { "id": 4, "title": 1984 },
+            { "id": 5, "title": "Élantris" }
+        ]);
+        let index = index_with_documents(hashset! { S("title") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+
+        // the multi-valued documents match if any of their values matches
+        assert_eq!(evaluate("title LENGTH > 3"), vec![1, 2, 3]);
+        assert_eq!(evaluate("title LENGTH = 1"), vec![0, 3, 5]);
+        assert_eq!(evaluate("title LENGTH >= 6"), vec![2]);
+        assert_eq!(evaluate("NOT title LENGTH > 1"), vec![0, 3, 5]);
+        // the numbers have no length
+        assert_eq!(evaluate("title LENGTH < 10"), vec![0, 1, 2, 3, 5]);
+
+        // the characters are counted once the value is trimmed
+        assert_eq!(evaluate("title CHARS = 4"), vec![0]);
+        assert_eq!(evaluate("title CHARS = 8"), vec![3, 5]);
+        assert_eq!(evaluate("title CHARS > 20"), vec![1, 2]);
+        assert_eq!(evaluate("title CHARS <= 25"), vec![0, 1, 3, 5]);
+        assert_eq!(evaluate("title CHARS != 4"), vec![1, 2, 3, 5]);
+
+        let filter = Filter::from_str("title CHARS > many").unwrap().unwrap();
+        assert!(filter.evaluate(&rtxn, &index).is_err());
+    }
+
+    #[test]
+    fn is_empty() {
+        let content = documents!([
+            { "id": 0, "description": "" },
+            { "id": 1, "description": "   " },
+            { "id": 2 },
+            { "id": 3, "description": "a nice kitten" },
+            { "id": 4, "description": null },
+            { "id": 5, "description": ["", " "] },
+            { "id": 6, "description": ["", "kitten"] }
+        ]);
+        let index = index_with_documents(hashset! { S("description") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+
+        // the whitespaces are trimmed by the normalization, an absent or null field is not empty
+        assert_eq!(evaluate("description IS EMPTY"), vec![0, 1, 5]);
+        assert_eq!(evaluate("description IS NOT EMPTY"), vec![2, 3, 4, 6]);
+        assert_eq!(evaluate("NOT description IS EMPTY"), evaluate("description IS NOT EMPTY"));
+        // the empty strings are not indexed, the documents are considered without value
+        assert_eq!(evaluate("description EXISTS"), vec![3, 6]);
+    }
+
+    #[test]
+    fn equals_each() {
+        let content = documents!([
+            { "id": 0, "tag": "Red" },
+            { "id": 1, "tag": ["blue", "red"] },
+            { "id": 2, "tag": "green" },
+            { "id": 3, "price": 12 }
+        ]);
+        let index = index_with_documents(hashset! { S("tag"), S("price") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let filters = Filter::equals_each(&rtxn, &index, "tag").unwrap();
+        let filters: Vec<_> = filters
+            .into_iter()
+            .map(|(value, filter)| {
+                let docids = filter.evaluate(&rtxn, &index).unwrap();
+                // the original casing of `red` is the one of any of the documents
+                (value.to_lowercase(), docids.into_iter().collect::<Vec<_>>())
+            })
+            .collect();
+        assert_eq!(
+            filters,
+            vec![(S("blue"), vec![1]), (S("green"), vec![2]), (S("red"), vec![0, 1])]
+        );
+
+        // the numbers are not string facet values
+        assert!(Filter::equals_each(&rtxn, &index, "price").unwrap().is_empty());
+
+        let error = Filter::equals_each(&rtxn, &index, "name").unwrap_err();
+        assert!(error.to_string().starts_with("Attribute `name` is not filterable."));
+    }
+
+    #[test]
+    fn revalidate() {
+        let index = TempIndex::new_with_map_size(10 * 1024 * 1024); // 10 MB
 
         let config = IndexerConfig::default();
-        // Set the filterable fields to be the channel.
         let mut wtxn = index.write_txn().unwrap();
         let mut builder = Settings::new(&mut wtxn, &index, &config);
-        builder.set_searchable_fields(vec![S("title")]);
-        builder.set_filterable_fields(hashset! { S("title") });
+        builder.set_filterable_fields(hashset! { S("price"), S("_geo") });
         builder.execute(|_| ()).unwrap();
         wtxn.commit().unwrap();
 
+        let filter = Filter::from_str("price > 10 AND _geoRadius(12, 13, 14)").unwrap().unwrap();
         let rtxn = index.read_txn().unwrap();
+        filter.revalidate(&rtxn, &index).unwrap();
+        drop(rtxn);
 
-        let filter = Filter::from_str("_geoRadius(-100, 150, 10)").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().starts_with(
-            "Attribute `_geo` is not filterable. Available filterable attributes are: `title`."
-        ));
+        // `_geo` is not filterable anymore
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_filterable_fields(hashset! { S("price") });
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
 
-        let filter = Filter::from_str("name = 12").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        let rtxn = index.read_txn().unwrap();
+        let error = filter.revalidate(&rtxn, &index).unwrap_err();
         assert!(error.to_string().starts_with(
-            "Attribute `name` is not filterable. Available filterable attributes are: `title`."
+            "Attribute `_geo` is not filterable. Available filterable attributes are: `price`."
         ));
-    }
-
-    #[test]
-    fn geo_radius_error() {
-        let path = tempfile::tempdir().unwrap();
-        let mut options = EnvOpenOptions::new();
-        options.map_size(10 * 1024 * 1024); // 10 MB
-        let index = Index::new(options, &path).unwrap();
+        drop(rtxn);
 
-        let config = IndexerConfig::default();
-        // Set the filterable fields to be the channel.
+        // and neither is `price`
         let mut wtxn = index.write_txn().unwrap();
         let mut builder = Settings::new(&mut wtxn, &index, &config);
-        builder.set_searchable_fields(vec![S("_geo"), S("price")]); // to keep the fields order
-        builder.set_filterable_fields(hashset! { S("_geo"), S("price") });
+        builder.reset_filterable_fields();
         builder.execute(|_| ()).unwrap();
         wtxn.commit().unwrap();
 
         let rtxn = index.read_txn().unwrap();
+        let error = filter.revalidate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with("Attribute `price` is not filterable. No attribute"));
+    }
 
-        // georadius have a bad latitude
-        let filter = Filter::from_str("_geoRadius(-100, 150, 10)").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(
-            error.to_string().starts_with(
-                "Bad latitude `-100`. Latitude must be contained between -90 and 90 degrees."
-            ),
-            "{}",
-            error.to_string()
+    #[test]
+    fn wildcard_exists() {
+        let content = documents!([
+            { "id": 0, "price": 10 },
+            { "id": 1, "price_min": 5 },
+            { "id": 2, "tag": "red" },
+            { "id": 3, "title": "not filterable" }
+        ]);
+        let index =
+            index_with_documents(hashset! { S("price"), S("price_min"), S("tag") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+
+        assert_eq!(evaluate("* EXISTS"), vec![0, 1, 2]);
+        assert_eq!(evaluate("* NOT EXISTS"), vec![3]);
+        assert_eq!(evaluate("price* EXISTS"), vec![0, 1]);
+        assert_eq!(evaluate("NOT price* EXISTS"), vec![2, 3]);
+        // `title` is not filterable and nothing starts with `size`
+        assert_eq!(evaluate("title* EXISTS"), Vec::<u32>::new());
+        assert_eq!(evaluate("size* EXISTS"), Vec::<u32>::new());
+        assert_eq!(evaluate("size* NOT EXISTS"), vec![0, 1, 2, 3]);
+
+        // a quoted `*` is an attribute or a value like any other
+        for filter in ["\"*\" EXISTS", "price \"*\" TO 10"] {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            assert!(filter.evaluate(&rtxn, &index).is_err());
+        }
+    }
+
+    #[test]
+    fn evaluate_many() {
+        let content = documents!([
+            { "id": 0, "price": 10, "tag": "red" },
+            { "id": 1, "price": 25, "tag": "blue" },
+            { "id": 2, "price": 40, "tag": "red" },
+            { "id": 3, "price": 55, "tag": "green" },
+            { "id": 4, "tag": "blue" }
+        ]);
+        let index = index_with_documents(hashset! { S("price"), S("tag") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filters: Vec<_> = [
+            "price > 20",
+            "price 10 TO 40",
+            "tag = red AND price < 30",
+            "tag = blue OR price >= 55",
+            "NOT price = 25",
+        ]
+        .iter()
+        .map(|s| Filter::from_str(s).unwrap().unwrap())
+        .collect();
+
+        let bitmaps = Filter::evaluate_many(&rtxn, &index, &filters).unwrap();
+        assert_eq!(bitmaps.len(), filters.len());
+        for (filter, bitmap) in filters.iter().zip(&bitmaps) {
+            assert_eq!(bitmap, &filter.evaluate(&rtxn, &index).unwrap());
+        }
+
+        let bitmaps: Vec<Vec<_>> = bitmaps.into_iter().map(|b| b.into_iter().collect()).collect();
+        assert_eq!(
+            bitmaps,
+            vec![vec![1, 2, 3], vec![0, 1, 2], vec![0], vec![1, 3, 4], vec![0, 2, 3, 4]]
         );
 
-        // georadius have a bad latitude
-        let filter = Filter::from_str("_geoRadius(-90.0000001, 150, 10)").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().contains(
-            "Bad latitude `-90.0000001`. Latitude must be contained between -90 and 90 degrees."
-        ));
+        assert!(Filter::evaluate_many(&rtxn, &index, &[]).unwrap().is_empty());
+    }
 
-        // georadius have a bad longitude
-        let filter = Filter::from_str("_geoRadius(-10, 250, 10)").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(
-            error.to_string().contains(
-                "Bad longitude `250`. Longitude must be contained between -180 and 180 degrees."
-            ),
-            "{}",
-            error.to_string(),
+    #[test]
+    fn error_codes() {
+        let value = json!(12);
+        let errors = [
+            FilterError::AttributeNotFilterable { attribute: "dog", filterable: S("title") },
+            FilterError::BadGeo("_geo"),
+            FilterError::BadGeoLat(100.),
+            FilterError::BadGeoLng(200.),
+            FilterError::BadJsonRule { pointer: S("/0"), value: &value },
+            FilterError::NoFilterableFields("dog"),
+            FilterError::Reserved("_geoDistance"),
+            FilterError::TooDeep,
+        ];
+
+        let codes: HashSet<_> = errors.iter().map(FilterError::error_code).collect();
+        assert_eq!(codes.len(), errors.len());
+
+        // the codes are part of the API and must not change
+        assert_eq!(errors[0].error_code(), "attribute_not_filterable");
+        assert_eq!(errors[2].error_code(), "bad_geo_latitude");
+        assert_eq!(errors[7].error_code(), "filter_too_deep");
+
+        // and they are returned with the errors of the filters, along with the
+        // byte offsets of the erroneous part of the filter
+        let index = filterable_index(hashset! { S("_geo"), S("price") });
+
+        let rtxn = index.read_txn().unwrap();
+        let error = |filter| {
+            let error = match Filter::from_str(filter) {
+                Ok(filter) => filter.unwrap().evaluate(&rtxn, &index).unwrap_err(),
+                Err(error) => error,
+            };
+            match error {
+                Error::UserError(UserError::InvalidFilter { code, byte_offsets, .. }) => {
+                    (code, byte_offsets)
+                }
+                error => panic!("{}: unexpected error {}", filter, error),
+            }
+        };
+        assert_eq!(error("dog = 1"), ("attribute_not_filterable", Some((0, 3))));
+        assert_eq!(
+            error("price > 10 AND _geoRadius(100, 0, 10)"),
+            ("bad_geo_latitude", Some((26, 29)))
         );
+        assert_eq!(error("_geoRadius(0, 0, ten)").0, INVALID_FILTER_CODE);
+        assert_eq!(error("price >").0, INVALID_FILTER_CODE);
+        assert!(error("price >").1.is_some());
+    }
 
-        // georadius have a bad longitude
-        let filter = Filter::from_str("_geoRadius(-10, 180.000001, 10)").unwrap().unwrap();
+    #[test]
+    fn optimize_order() {
+        let content = documents!([
+            { "id": 0, "price": 10, "tag": "red", "_geo": { "lat": 1.0, "lng": 1.0 } },
+            { "id": 1, "price": 25, "tag": "blue", "_geo": { "lat": 2.0, "lng": 2.0 } },
+            { "id": 2, "price": 40, "tag": "red", "_geo": { "lat": 45.0, "lng": 45.0 } },
+            { "id": 3, "price": 55, "tag": "red", "_geo": { "lat": 1.5, "lng": 1.5 } }
+        ]);
+        let index = index_with_documents(hashset! { S("_geo"), S("price"), S("tag") }, content);
+
+        let rtxn = index.read_txn().unwrap();
+
+        for filter in [
+            "_geoRadius(1, 1, 500000) AND price > 20 AND tag = red",
+            "price 10 TO 40 AND (tag = blue OR _geoRadius(45, 45, 10)) AND tag != green",
+            "NOT (price < 30 AND tag = red) AND _geoRadius(1, 1, 500000)",
+            "price > 20 AND _geoRing(1, 1, 100, 500000) AND tag = red",
+            "tag = green AND price > 20",
+        ] {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            // the counted operations are evaluated in the order they are written
+            let (expected, _) = filter.evaluate_with_stats(&rtxn, &index).unwrap();
+            assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), expected);
+            let condition: FilterCondition = filter.into();
+            let optimized = Filter::from(condition.optimize_order());
+            assert_eq!(optimized.evaluate(&rtxn, &index).unwrap(), expected);
+        }
+
+        // the operand skipped after an empty intersection is still validated
+        let filter = Filter::from_str("tag = green AND dog = 1").unwrap().unwrap();
         let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().contains(
-            "Bad longitude `180.000001`. Longitude must be contained between -180 and 180 degrees."
+        assert!(matches!(
+            error,
+            Error::UserError(UserError::InvalidFilter { code: "attribute_not_filterable", .. })
         ));
     }
 
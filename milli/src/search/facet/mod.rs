@@ -1,7 +1,10 @@
 pub use self::facet_distribution::FacetDistribution;
 pub use self::facet_number::{FacetNumberIter, FacetNumberRange, FacetNumberRevRange};
 pub use self::facet_string::FacetStringIter;
-pub use self::filter::Filter;
+pub use self::filter::{
+    CompiledFilter, Filter, FilterError, FilterWarning, Highlight, OperationStats, PrunedFilter,
+    UnresolvedFieldPolicy, EVALUATION_LOG_TARGET, INVALID_FILTER_CODE,
+};
 
 mod facet_distribution;
 mod facet_number;
@@ -14,7 +14,11 @@ use meilisearch_tokenizer::{Analyzer, AnalyzerConfig};
 use once_cell::sync::Lazy;
 use roaring::bitmap::RoaringBitmap;
 
-pub use self::facet::{FacetDistribution, FacetNumberIter, Filter};
+pub use self::facet::{
+    CompiledFilter, FacetDistribution, FacetNumberIter, Filter, FilterError, FilterWarning,
+    Highlight, OperationStats, PrunedFilter, UnresolvedFieldPolicy, EVALUATION_LOG_TARGET,
+    INVALID_FILTER_CODE,
+};
 pub use self::matching_words::MatchingWords;
 use self::query_tree::QueryTreeBuilder;
 use crate::error::UserError;